@@ -0,0 +1,378 @@
+use crate::error::Error;
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+use std::sync::Mutex;
+use tracing::debug;
+
+/// Backend de encriptación usado por el almacén de secretos.
+///
+/// Cada backend sabe desencriptar y encriptar un documento YAML completo
+/// asociado a una ruta, y listar los destinatarios que pueden leerlo. Los
+/// backends se comparten entre hilos (por ejemplo en `secret-service`).
+pub trait CryptoBackend: Send + Sync {
+    /// Nombre corto del backend (`sops`, `age`, `plain`)
+    fn name(&self) -> &'static str;
+
+    /// Comprueba que el backend puede usarse (por ejemplo, que `sops` esté instalado)
+    fn check(&self) -> Result<()> {
+        Ok(())
+    }
+
+    /// Indica si existe un documento en `path`
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    /// Desencripta el documento en `path` y devuelve el YAML en claro
+    fn decrypt(&self, path: &Path) -> Result<String>;
+
+    /// Encripta el YAML en claro y lo guarda en `path`
+    fn encrypt(&self, path: &Path, plaintext: &str) -> Result<()>;
+
+    /// Lista los destinatarios (claves públicas) del documento en `path`
+    fn recipients(&self, path: &Path) -> Result<Vec<String>>;
+}
+
+/// Crea el backend correspondiente a un nombre (`sops`, `age` o `plain`)
+pub fn from_name(name: &str) -> Result<Box<dyn CryptoBackend>> {
+    match name {
+        "sops" => Ok(Box::new(SopsBackend)),
+        "age" => Ok(Box::new(AgeBackend::new())),
+        "plain" | "plaintext" | "memory" => Ok(Box::new(PlaintextBackend::new())),
+        other => anyhow::bail!(
+            "Backend de encriptación desconocido: '{}'. Valores válidos: sops, age, plain",
+            other
+        ),
+    }
+}
+
+/// Backend que delega en el binario externo `sops`
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SopsBackend;
+
+impl CryptoBackend for SopsBackend {
+    fn name(&self) -> &'static str {
+        "sops"
+    }
+
+    fn check(&self) -> Result<()> {
+        verify_sops_installed()
+    }
+
+    fn decrypt(&self, path: &Path) -> Result<String> {
+        debug!("Desencriptando con sops...");
+        let output = Command::new("sops")
+            .arg("-d")
+            .arg(path)
+            .output()
+            .context("No se pudo ejecutar sops")?;
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            // sops informa de un MAC no válido cuando el archivo ha sido manipulado
+            if error.contains("MAC mismatch") || error.contains("integrity") {
+                return Err(Error::IntegrityFailed(error).into());
+            }
+            return Err(Error::DecryptFailed(error).into());
+        }
+
+        String::from_utf8(output.stdout).context("El contenido desencriptado no es UTF-8 válido")
+    }
+
+    fn encrypt(&self, path: &Path, plaintext: &str) -> Result<()> {
+        debug!("Encriptando con sops...");
+
+        // Obtener el directorio del archivo de secretos para .sops.yaml
+        let work_dir = path
+            .parent()
+            .context("No se pudo obtener el directorio del archivo de secretos")?;
+
+        // Escribir contenido a un archivo temporal .yml en el mismo directorio
+        // para que SOPS pueda aplicar las reglas de creación basadas en path
+        let temp_file_path = work_dir.join(".crypta_temp.yml");
+        let mut temp_file =
+            fs::File::create(&temp_file_path).context("No se pudo crear archivo temporal")?;
+        temp_file
+            .write_all(plaintext.as_bytes())
+            .context("No se pudo escribir al archivo temporal")?;
+        drop(temp_file); // Cerrar el archivo
+
+        // Encriptar el archivo temporal
+        let output = Command::new("sops")
+            .arg("-e")
+            .arg(&temp_file_path)
+            .current_dir(work_dir)
+            .output()
+            .context("No se pudo ejecutar sops")?;
+
+        // Limpiar archivo temporal
+        let _ = fs::remove_file(&temp_file_path);
+
+        if !output.status.success() {
+            let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            return Err(Error::EncryptFailed(error).into());
+        }
+
+        fs::write(path, output.stdout).context("No se pudo escribir el archivo de secretos")
+    }
+
+    fn recipients(&self, path: &Path) -> Result<Vec<String>> {
+        // Si el documento existe, los destinatarios están en sus metadatos
+        if path.exists() {
+            let content =
+                fs::read_to_string(path).context("No se pudo leer el archivo de secretos")?;
+            let doc: Value =
+                serde_yaml::from_str(&content).context("No se pudo parsear el contenido YAML")?;
+            let recipients: Vec<String> = doc
+                .get("sops")
+                .and_then(|s| s.get("age"))
+                .and_then(|a| a.as_sequence())
+                .map(|seq| {
+                    seq.iter()
+                        .filter_map(|r| r.get("recipient").and_then(|v| v.as_str()))
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default();
+            if !recipients.is_empty() {
+                return Ok(recipients);
+            }
+        }
+
+        sops_config_recipients(path)
+    }
+}
+
+/// Backend nativo de age: el documento completo se guarda encriptado con age
+/// (formato ASCII armor) para los destinatarios definidos en `.sops.yaml`.
+#[derive(Debug, Default, Clone)]
+pub struct AgeBackend {
+    identity_file: Option<PathBuf>,
+}
+
+impl AgeBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Usa un archivo de identidad concreto en lugar de buscarlo automáticamente
+    pub fn with_identity_file(identity_file: impl Into<PathBuf>) -> Self {
+        Self {
+            identity_file: Some(identity_file.into()),
+        }
+    }
+
+    /// Busca la identidad Age: archivo explícito, `SOPS_AGE_KEY`, `SOPS_AGE_KEY_FILE`
+    /// o la clave generada por `crypta init` en el directorio del archivo de
+    /// secretos o en alguno de sus ancestros (los vaults comparten la clave)
+    fn identities(&self, path: &Path) -> Result<Vec<Box<dyn age::Identity>>> {
+        let data = if let Some(file) = &self.identity_file {
+            fs::read_to_string(file).context("No se pudo leer el archivo de identidad Age")?
+        } else if let Ok(key) = std::env::var("SOPS_AGE_KEY") {
+            key
+        } else {
+            let file = match std::env::var("SOPS_AGE_KEY_FILE") {
+                Ok(file) => PathBuf::from(file),
+                Err(_) => {
+                    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+                    dir.ancestors()
+                        .map(|d| d.join("sops/age/key.txt"))
+                        .find(|f| f.exists())
+                        .unwrap_or_else(|| dir.join("sops/age/key.txt"))
+                }
+            };
+            debug!("Leyendo identidad Age de {}", file.display());
+            fs::read_to_string(&file).with_context(|| {
+                format!(
+                    "No se pudo leer la identidad Age: {}. Define SOPS_AGE_KEY_FILE",
+                    file.display()
+                )
+            })?
+        };
+
+        age::IdentityFile::from_buffer(data.as_bytes())
+            .context("No se pudo parsear la identidad Age")?
+            .into_identities()
+            .map_err(|e| anyhow::anyhow!("Identidad Age no válida: {}", e))
+    }
+}
+
+impl CryptoBackend for AgeBackend {
+    fn name(&self) -> &'static str {
+        "age"
+    }
+
+    fn decrypt(&self, path: &Path) -> Result<String> {
+        debug!("Desencriptando con age...");
+        let identities = self.identities(path)?;
+        let ciphertext = fs::read(path).context("No se pudo leer el archivo de secretos")?;
+
+        let decryptor =
+            age::Decryptor::new_buffered(age::armor::ArmoredReader::new(ciphertext.as_slice()))
+                .map_err(age_decrypt_error)?;
+        let mut reader = decryptor
+            .decrypt(identities.iter().map(|i| i.as_ref() as &dyn age::Identity))
+            .map_err(age_decrypt_error)?;
+
+        // El stream de age verifica cada bloque al leerlo
+        let mut plaintext = Vec::new();
+        reader
+            .read_to_end(&mut plaintext)
+            .map_err(|e| Error::IntegrityFailed(e.to_string()))?;
+        let plaintext = String::from_utf8(plaintext)
+            .context("El contenido desencriptado no es UTF-8 válido")?;
+        Ok(plaintext)
+    }
+
+    fn encrypt(&self, path: &Path, plaintext: &str) -> Result<()> {
+        debug!("Encriptando con age...");
+        let recipients = self
+            .recipients(path)?
+            .iter()
+            .map(|r| {
+                age::x25519::Recipient::from_str(r)
+                    .map_err(|e| anyhow::anyhow!("Destinatario Age no válido '{}': {}", r, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let encryptor =
+            age::Encryptor::with_recipients(recipients.iter().map(|r| r as &dyn age::Recipient))
+                .map_err(|e| Error::EncryptFailed(e.to_string()))?;
+
+        let mut output = Vec::new();
+        let armor =
+            age::armor::ArmoredWriter::wrap_output(&mut output, age::armor::Format::AsciiArmor)?;
+        let mut writer = encryptor.wrap_output(armor)?;
+        writer.write_all(plaintext.as_bytes())?;
+        writer.finish()?.finish()?;
+
+        fs::write(path, output).context("No se pudo escribir el archivo de secretos")
+    }
+
+    fn recipients(&self, path: &Path) -> Result<Vec<String>> {
+        sops_config_recipients(path)
+    }
+}
+
+/// Clasifica los errores de age: los MAC no válidos indican manipulación
+fn age_decrypt_error(err: age::DecryptError) -> anyhow::Error {
+    match err {
+        age::DecryptError::DecryptionFailed
+        | age::DecryptError::InvalidHeader
+        | age::DecryptError::InvalidMac => Error::IntegrityFailed(err.to_string()).into(),
+        other => Error::DecryptFailed(other.to_string()).into(),
+    }
+}
+
+/// Backend en memoria sin encriptación, pensado para tests
+#[derive(Debug, Default)]
+pub struct PlaintextBackend {
+    documents: Mutex<HashMap<PathBuf, String>>,
+}
+
+impl PlaintextBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CryptoBackend for PlaintextBackend {
+    fn name(&self) -> &'static str {
+        "plain"
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.documents.lock().unwrap().contains_key(path)
+    }
+
+    fn decrypt(&self, path: &Path) -> Result<String> {
+        self.documents
+            .lock()
+            .unwrap()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::StoreNotFound(path.to_path_buf()).into())
+    }
+
+    fn encrypt(&self, path: &Path, plaintext: &str) -> Result<()> {
+        self.documents
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), plaintext.to_string());
+        Ok(())
+    }
+
+    fn recipients(&self, _path: &Path) -> Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Lee los destinatarios Age de las reglas de creación del `.sops.yaml`
+/// situado en el mismo directorio que el documento
+pub fn sops_config_recipients(path: &Path) -> Result<Vec<String>> {
+    let config_path = path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(".sops.yaml");
+    debug!("Leyendo destinatarios de {}", config_path.display());
+
+    let content = fs::read_to_string(&config_path).with_context(|| {
+        format!(
+            "No se pudo leer {}. Ejecuta 'crypta init' primero",
+            config_path.display()
+        )
+    })?;
+    let config: Value =
+        serde_yaml::from_str(&content).context("No se pudo parsear el archivo .sops.yaml")?;
+
+    let mut recipients = Vec::new();
+    if let Some(rules) = config.get("creation_rules").and_then(|r| r.as_sequence()) {
+        for rule in rules {
+            if let Some(age) = rule.get("age").and_then(|a| a.as_str()) {
+                for recipient in age.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+                    if !recipients.iter().any(|r| r == recipient) {
+                        recipients.push(recipient.to_string());
+                    }
+                }
+            }
+        }
+    }
+
+    if recipients.is_empty() {
+        anyhow::bail!(
+            "No hay destinatarios Age definidos en {}",
+            config_path.display()
+        );
+    }
+    Ok(recipients)
+}
+
+pub(crate) fn verify_sops_installed() -> Result<()> {
+    debug!("Verificando que sops esté instalado...");
+
+    let output = Command::new("which").arg("sops").output();
+
+    match output {
+        Ok(out) if out.status.success() => {
+            let path = String::from_utf8_lossy(&out.stdout).trim().to_string();
+            debug!("sops encontrado en: {}", path);
+            Ok(())
+        }
+        _ => Err(
+            anyhow::Error::new(Error::BackendMissing("sops".to_string())).context(
+                "El comando 'sops' no está instalado.\n\n\
+                Instala SOPS:\n\
+                - Arch Linux: sudo pacman -S sops\n\
+                - Ubuntu/Debian: sudo apt install sops\n\
+                - macOS: brew install sops\n\
+                - O descarga desde: https://github.com/getsops/sops/releases",
+            ),
+        ),
+    }
+}
@@ -0,0 +1,51 @@
+//! Helper de credenciales de Docker: `credsStore: "crypta"` en
+//! `~/.docker/config.json` hace que Docker ejecute este binario.
+//!
+//! Resuelve el almacén igual que `crypta` ([`Vault::open`]: `CRYPTA_DIR`,
+//! `CRYPTA_VAULT`, `CRYPTA_FILE` y configuración) y equivale a
+//! `crypta docker-credential`.
+
+use anyhow::Result;
+use clap::Parser;
+use crypta::config::Config;
+use crypta::docker_credential::{self, Operation};
+use crypta::vault::Vault;
+
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Helper de credenciales de Docker respaldado por crypta"
+)]
+struct Cli {
+    /// Operación que pide Docker
+    #[arg(value_enum)]
+    operation: Operation,
+    /// Ruta del almacén con las credenciales (`docker/REGISTRO`)
+    #[arg(
+        long,
+        env = "CRYPTA_DOCKER_NAMESPACE",
+        default_value = docker_credential::DEFAULT_NAMESPACE
+    )]
+    namespace: String,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(&cli) {
+        eprintln!("❌ Error: {}", e);
+        std::process::exit(crypta::error::exit_code(&e));
+    }
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    let config = Config::load()?;
+    let vault = Vault::open(&config, None, None, None)?;
+    let store = vault.store(&config)?;
+
+    docker_credential::serve(&store, &cli.namespace, cli.operation)?;
+
+    if matches!(cli.operation, Operation::Store | Operation::Erase) {
+        vault.auto_sync(&config)?;
+    }
+    Ok(())
+}
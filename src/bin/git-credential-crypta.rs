@@ -0,0 +1,44 @@
+//! Helper de credenciales de git: `credential.helper = crypta` hace que git
+//! ejecute este binario.
+//!
+//! Resuelve el almacén igual que `crypta` ([`Vault::open`]: `CRYPTA_DIR`,
+//! `CRYPTA_VAULT`, `CRYPTA_FILE` y configuración) y equivale a
+//! `crypta git-credential`.
+
+use anyhow::Result;
+use clap::Parser;
+use crypta::config::Config;
+use crypta::git_credential::{self, Operation};
+use crypta::vault::Vault;
+
+#[derive(Parser)]
+#[command(version, about = "Helper de credenciales de git respaldado por crypta")]
+struct Cli {
+    /// Operación que pide git
+    #[arg(value_enum)]
+    operation: Operation,
+    /// Ruta del almacén con las credenciales (`git/HOST/USUARIO`)
+    #[arg(long, default_value = git_credential::DEFAULT_NAMESPACE)]
+    namespace: String,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = run(&cli) {
+        eprintln!("❌ Error: {}", e);
+        std::process::exit(crypta::error::exit_code(&e));
+    }
+}
+
+fn run(cli: &Cli) -> Result<()> {
+    let config = Config::load()?;
+    let vault = Vault::open(&config, None, None, None)?;
+    let store = vault.store(&config)?;
+
+    git_credential::serve(&store, &cli.namespace, cli.operation)?;
+
+    if matches!(cli.operation, Operation::Store | Operation::Erase) {
+        vault.auto_sync(&config)?;
+    }
+    Ok(())
+}
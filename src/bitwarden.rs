@@ -0,0 +1,158 @@
+use crate::login::Login;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use tracing::warn;
+
+/// Tipos de elemento de Bitwarden
+const TYPE_LOGIN: u8 = 1;
+const TYPE_SECURE_NOTE: u8 = 2;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Export {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    folders: Vec<Folder>,
+    #[serde(default)]
+    items: Vec<Item>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Folder {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Item {
+    #[serde(rename = "type")]
+    kind: u8,
+    #[serde(default)]
+    name: String,
+    folder_id: Option<String>,
+    notes: Option<String>,
+    login: Option<ItemLogin>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ItemLogin {
+    username: Option<String>,
+    password: Option<String>,
+    uris: Option<Vec<Uri>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Uri {
+    uri: Option<String>,
+}
+
+/// Lee una exportación JSON sin cifrar de Bitwarden. Se importan los
+/// inicios de sesión y las notas seguras; las tarjetas, las identidades y
+/// los elementos sin nombre se omiten con un aviso.
+pub fn parse(content: &str) -> Result<Vec<Login>> {
+    let export: Export =
+        serde_json::from_str(content).context("Exportación JSON de Bitwarden no válida")?;
+    if export.encrypted {
+        anyhow::bail!(
+            "La exportación de Bitwarden está cifrada; exporta en formato JSON sin cifrar"
+        );
+    }
+
+    let folders: HashMap<String, String> =
+        export.folders.into_iter().map(|f| (f.id, f.name)).collect();
+    let non_empty = |s: Option<String>| s.filter(|s| !s.trim().is_empty());
+
+    let mut logins = Vec::new();
+    for (i, item) in export.items.into_iter().enumerate() {
+        if item.name.trim().is_empty() {
+            warn!("Elemento {} de Bitwarden sin nombre; se omite", i + 1);
+            continue;
+        }
+        let folder = item
+            .folder_id
+            .as_ref()
+            .and_then(|id| folders.get(id))
+            .cloned();
+        match item.kind {
+            TYPE_LOGIN => {
+                let login = item.login.unwrap_or_default();
+                let url = login
+                    .uris
+                    .unwrap_or_default()
+                    .into_iter()
+                    .find_map(|u| non_empty(u.uri));
+                logins.push(Login {
+                    folder,
+                    name: item.name,
+                    password: login.password.unwrap_or_default(),
+                    username: non_empty(login.username),
+                    url,
+                    notes: non_empty(item.notes),
+                });
+            }
+            TYPE_SECURE_NOTE => logins.push(Login {
+                folder,
+                name: item.name,
+                notes: non_empty(item.notes),
+                ..Login::default()
+            }),
+            _ => warn!(
+                "'{}' no es un inicio de sesión ni una nota segura; se omite",
+                item.name
+            ),
+        }
+    }
+    Ok(logins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_export() {
+        let content = r#"{
+          "encrypted": false,
+          "folders": [{"id": "f1", "name": "Trabajo/Cloud"}],
+          "items": [
+            {"id": "1", "type": 1, "name": "AWS", "folderId": "f1", "notes": "cuenta root",
+             "login": {"username": "admin", "password": "aws-p4ss",
+                       "uris": [{"match": null, "uri": "https://aws.amazon.com"}]}},
+            {"id": "2", "type": 2, "name": "Alarma", "folderId": null, "notes": "1234",
+             "secureNote": {"type": 0}},
+            {"id": "3", "type": 3, "name": "Visa", "folderId": null, "notes": null},
+            {"id": "4", "type": 1, "name": " ", "folderId": "f1", "notes": null,
+             "login": {"username": null, "password": "sin-nombre", "uris": null}},
+            {"id": "5", "type": 2, "folderId": null, "notes": "sin nombre"}
+          ]
+        }"#;
+        let logins = parse(content).unwrap();
+        assert_eq!(
+            logins,
+            vec![
+                Login {
+                    folder: Some("Trabajo/Cloud".to_string()),
+                    name: "AWS".to_string(),
+                    password: "aws-p4ss".to_string(),
+                    username: Some("admin".to_string()),
+                    url: Some("https://aws.amazon.com".to_string()),
+                    notes: Some("cuenta root".to_string()),
+                },
+                Login {
+                    name: "Alarma".to_string(),
+                    notes: Some("1234".to_string()),
+                    ..Login::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_encrypted_export() {
+        assert!(parse(r#"{"encrypted": true, "data": "..."}"#).is_err());
+        assert!(parse("no es json").is_err());
+    }
+}
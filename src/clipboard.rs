@@ -0,0 +1,339 @@
+//! Copia de secretos al portapapeles con borrado automático.
+//!
+//! El CLI termina en cuanto copia el valor, pero en X11 (y Wayland vía
+//! XWayland) el contenido del portapapeles lo sirve el proceso que lo copió:
+//! si sale, el valor desaparece o pasa al gestor del portapapeles y se
+//! queda allí indefinidamente. Por eso la copia la hace un proceso auxiliar
+//! separado (`crypta clipboard-helper`) que sirve el valor y, pasado el
+//! tiempo límite, lo borra si el portapapeles todavía lo contiene.
+//!
+//! Las copias se marcan como sensibles (`x-kde-passwordManagerHint` en
+//! Linux, `org.nspasteboard.ConcealedType` en macOS y las exclusiones de
+//! historial y nube en Windows) para que los historiales del portapapeles
+//! no las guarden. Sin pantalla, por ejemplo por SSH, el destino `osc52`
+//! envía el valor a la terminal local con una secuencia de escape.
+
+use anyhow::{Context, Result};
+use arboard::Clipboard;
+use base64::Engine;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Subcomando oculto que ejecuta el proceso auxiliar
+pub const HELPER_COMMAND: &str = "clipboard-helper";
+
+/// Respuesta del proceso auxiliar cuando el valor ya está en el portapapeles
+const READY: &str = "ok";
+
+/// Dónde se copia el secreto
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Target {
+    /// Portapapeles del sistema (Ctrl+V)
+    #[default]
+    Clipboard,
+    /// Selección PRIMARY de X11/Wayland (clic central)
+    Primary,
+    /// Portapapeles de la terminal local con la secuencia OSC 52 (SSH)
+    Osc52,
+}
+
+impl Target {
+    fn as_str(self) -> &'static str {
+        match self {
+            Target::Clipboard => "clipboard",
+            Target::Primary => "primary",
+            Target::Osc52 => "osc52",
+        }
+    }
+}
+
+/// Copia `value` en `target` y lo borra pasado `timeout` (si sigue allí).
+/// Con un tiempo límite de cero no se borra, pero el proceso auxiliar sigue
+/// sirviendo el valor hasta que otro programa ocupa el portapapeles. Con
+/// OSC 52 no hay borrado: la terminal no deja comprobar qué contiene su
+/// portapapeles.
+pub fn copy(value: &str, target: Target, timeout: Duration) -> Result<()> {
+    if target == Target::Osc52 {
+        return write_osc52(value);
+    }
+    check_target(target)?;
+
+    let exe = std::env::current_exe().context("No se pudo localizar el ejecutable de crypta")?;
+    let mut cmd = Command::new(exe);
+    cmd.arg(HELPER_COMMAND)
+        .arg("--timeout")
+        .arg(timeout.as_secs().to_string())
+        .arg("--target")
+        .arg(target.as_str())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    // En su propio grupo de procesos, para que Ctrl+C en la terminal no lo mate
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    let mut child = cmd
+        .spawn()
+        .context("No se pudo iniciar el proceso del portapapeles")?;
+    debug!("Proceso del portapapeles: {}", child.id());
+
+    // El valor va por stdin: en los argumentos sería visible con `ps`
+    let mut stdin = child
+        .stdin
+        .take()
+        .context("No se pudo escribir en el proceso del portapapeles")?;
+    stdin
+        .write_all(value.as_bytes())
+        .context("No se pudo escribir en el proceso del portapapeles")?;
+    drop(stdin);
+
+    let stdout = child
+        .stdout
+        .take()
+        .context("No se pudo leer el proceso del portapapeles")?;
+    read_ready(stdout)
+}
+
+/// Espera la confirmación del proceso auxiliar; cualquier otra respuesta es
+/// el error con el que ha terminado
+fn read_ready(reader: impl Read) -> Result<()> {
+    let mut line = String::new();
+    BufReader::new(reader)
+        .read_line(&mut line)
+        .context("No se pudo leer el proceso del portapapeles")?;
+    match line.trim_end() {
+        READY => Ok(()),
+        "" => anyhow::bail!("El proceso del portapapeles terminó sin responder"),
+        error => anyhow::bail!("{}", error),
+    }
+}
+
+/// Proceso auxiliar: lee el valor de stdin, lo copia, confirma por stdout y
+/// lo mantiene en el portapapeles durante `timeout`. Después lo borra si no
+/// se ha copiado otra cosa entretanto. Con un tiempo límite de cero no se
+/// borra: se sirve hasta que otro programa ocupa el portapapeles.
+pub fn helper(target: Target, timeout: Duration) -> Result<()> {
+    let mut value = String::new();
+    std::io::stdin()
+        .read_to_string(&mut value)
+        .context("No se pudo leer el valor a copiar")?;
+
+    let mut stdout = std::io::stdout();
+    let copied = open().and_then(|mut clipboard| {
+        set_text(&mut clipboard, &value, target)?;
+        Ok(clipboard)
+    });
+    let mut clipboard = match copied {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            // El error lo muestra el proceso que ha lanzado este
+            let _ = writeln!(stdout, "{}", format!("{:#}", e).replace('\n', " "));
+            return Err(e);
+        }
+    };
+    let _ = writeln!(stdout, "{}", READY);
+    let _ = stdout.flush();
+
+    if timeout.is_zero() {
+        debug!("Sirviendo el portapapeles sin borrado automático");
+        return platform::serve(&mut clipboard, &value, target)
+            .context("No se pudo servir el portapapeles");
+    }
+    std::thread::sleep(timeout);
+    if get_text(&mut clipboard, target).as_deref() == Some(value.as_str()) {
+        info!("Borrando el portapapeles tras {} s", timeout.as_secs());
+        clear(&mut clipboard, target)?;
+    }
+    Ok(())
+}
+
+fn open() -> Result<Clipboard> {
+    let ssh = ["SSH_CONNECTION", "SSH_TTY"]
+        .iter()
+        .any(|var| std::env::var_os(var).is_some());
+    Clipboard::new().with_context(|| {
+        if ssh {
+            "No se pudo acceder al portapapeles (en una sesión SSH, usa --target osc52)"
+        } else {
+            "No se pudo acceder al portapapeles"
+        }
+    })
+}
+
+/// PRIMARY solo existe en X11 y Wayland
+fn check_target(target: Target) -> Result<()> {
+    if target == Target::Primary && !platform::HAS_PRIMARY {
+        anyhow::bail!("La selección PRIMARY solo existe en X11 y Wayland");
+    }
+    Ok(())
+}
+
+/// Copia `text` marcado como sensible para que no entre en los historiales
+fn set_text(clipboard: &mut Clipboard, text: &str, target: Target) -> Result<()> {
+    platform::set_text(clipboard, text, target).context("No se pudo copiar al portapapeles")
+}
+
+fn get_text(clipboard: &mut Clipboard, target: Target) -> Option<String> {
+    platform::get_text(clipboard, target).ok()
+}
+
+fn clear(clipboard: &mut Clipboard, target: Target) -> Result<()> {
+    platform::clear(clipboard, target).context("No se pudo borrar el portapapeles")
+}
+
+/// X11 y Wayland: cada operación se hace sobre la selección del destino
+#[cfg(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+))]
+mod platform {
+    use super::Target;
+    use arboard::{ClearExtLinux, Clipboard, Error, GetExtLinux, LinuxClipboardKind, SetExtLinux};
+
+    pub const HAS_PRIMARY: bool = true;
+
+    fn selection(target: Target) -> LinuxClipboardKind {
+        match target {
+            Target::Primary => LinuxClipboardKind::Primary,
+            _ => LinuxClipboardKind::Clipboard,
+        }
+    }
+
+    pub fn set_text(clipboard: &mut Clipboard, text: &str, target: Target) -> Result<(), Error> {
+        clipboard
+            .set()
+            .clipboard(selection(target))
+            .exclude_from_history()
+            .text(text)
+    }
+
+    /// Vuelve a copiar `text` y lo sirve hasta que otro programa ocupa la
+    /// selección
+    pub fn serve(clipboard: &mut Clipboard, text: &str, target: Target) -> Result<(), Error> {
+        clipboard
+            .set()
+            .clipboard(selection(target))
+            .exclude_from_history()
+            .wait()
+            .text(text)
+    }
+
+    pub fn get_text(clipboard: &mut Clipboard, target: Target) -> Result<String, Error> {
+        clipboard.get().clipboard(selection(target)).text()
+    }
+
+    pub fn clear(clipboard: &mut Clipboard, target: Target) -> Result<(), Error> {
+        clipboard.clear_with().clipboard(selection(target))
+    }
+}
+
+/// macOS y Windows: solo hay un portapapeles
+#[cfg(not(all(
+    unix,
+    not(any(target_os = "macos", target_os = "android", target_os = "emscripten"))
+)))]
+mod platform {
+    use super::Target;
+    use arboard::{Clipboard, Error};
+
+    pub const HAS_PRIMARY: bool = false;
+
+    pub fn set_text(clipboard: &mut Clipboard, text: &str, _: Target) -> Result<(), Error> {
+        let set = clipboard.set();
+        #[cfg(target_os = "macos")]
+        let set = {
+            use arboard::SetExtApple;
+            set.exclude_from_history()
+        };
+        #[cfg(windows)]
+        let set = {
+            use arboard::SetExtWindows;
+            set.exclude_from_history().exclude_from_cloud()
+        };
+        set.text(text)
+    }
+
+    /// El sistema conserva el contenido cuando el proceso termina
+    pub fn serve(_: &mut Clipboard, _: &str, _: Target) -> Result<(), Error> {
+        Ok(())
+    }
+
+    pub fn get_text(clipboard: &mut Clipboard, _: Target) -> Result<String, Error> {
+        clipboard.get_text()
+    }
+
+    pub fn clear(clipboard: &mut Clipboard, _: Target) -> Result<(), Error> {
+        clipboard.clear()
+    }
+}
+
+/// Secuencia OSC 52 que pone `value` en el portapapeles de la terminal.
+/// Dentro de tmux (`$TMUX`) o screen (`$TERM` = `screen*`) se envuelve en
+/// una secuencia DCS para que llegue a la terminal exterior.
+fn osc52(value: &str, tmux: bool, term: &str) -> String {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(value);
+    let sequence = format!("\x1b]52;c;{}\x07", encoded);
+    if tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else if term.starts_with("screen") {
+        format!("\x1bP{}\x1b\\", sequence)
+    } else {
+        sequence
+    }
+}
+
+/// Escribe la secuencia OSC 52 en la terminal (`/dev/tty`, o stdout si es
+/// una terminal), aunque la salida del comando esté redirigida
+fn write_osc52(value: &str) -> Result<()> {
+    let tmux = std::env::var_os("TMUX").is_some();
+    let term = std::env::var("TERM").unwrap_or_default();
+    let sequence = osc52(value, tmux, &term);
+
+    match OpenOptions::new().write(true).open("/dev/tty") {
+        Ok(mut tty) => tty.write_all(sequence.as_bytes()),
+        Err(e) if !std::io::stdout().is_terminal() => {
+            return Err(e).context("No hay una terminal a la que enviar la secuencia OSC 52");
+        }
+        Err(_) => {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(sequence.as_bytes())
+                .and_then(|_| stdout.flush())
+        }
+    }
+    .context("No se pudo escribir en la terminal")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_ready() {
+        assert!(read_ready("ok\n".as_bytes()).is_ok());
+        let error = read_ready("No se pudo acceder al portapapeles\n".as_bytes()).unwrap_err();
+        assert_eq!(error.to_string(), "No se pudo acceder al portapapeles");
+        assert!(read_ready("".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_osc52() {
+        assert_eq!(
+            osc52("s3cret", false, "xterm-256color"),
+            "\x1b]52;c;czNjcmV0\x07"
+        );
+        assert_eq!(
+            osc52("s3cret", true, "tmux-256color"),
+            "\x1bPtmux;\x1b\x1b]52;c;czNjcmV0\x07\x1b\\"
+        );
+        assert_eq!(
+            osc52("s3cret", false, "screen"),
+            "\x1bP\x1b]52;c;czNjcmV0\x07\x1b\\"
+        );
+    }
+}
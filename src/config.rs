@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Nombre por defecto del archivo de secretos de cada vault
+pub const DEFAULT_FILE: &str = "secrets.yml";
+
+/// Configuración de crypta leída de `~/.config/crypta/config.toml`.
+///
+/// Todos los campos son opcionales. Las variables de entorno y los flags del
+/// CLI tienen prioridad sobre el archivo.
+///
+/// ```toml
+/// dir = "~/.secrets"
+/// file = "secrets.yml"
+/// default_vault = "work"
+/// backend = "sops"
+/// clipboard_timeout = 45
+///
+/// [sync]
+/// auto = true
+/// message = "Sync secrets"
+///
+/// [git]
+/// remote = "origin"
+/// branch = "main"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Directorio raíz de los secretos (`CRYPTA_DIR`, `--dir`)
+    pub dir: Option<PathBuf>,
+    /// Archivo de secretos, relativo al directorio del vault (`CRYPTA_FILE`, `--file`)
+    pub file: Option<PathBuf>,
+    /// Vault usado cuando no se indica `--vault` ni `CRYPTA_VAULT`
+    pub default_vault: Option<String>,
+    /// Backend de encriptación (`CRYPTA_BACKEND`)
+    pub backend: Option<String>,
+    /// Segundos que permanece un secreto en el portapapeles
+    pub clipboard_timeout: u64,
+    pub sync: SyncConfig,
+    pub git: GitConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            file: None,
+            default_vault: None,
+            backend: None,
+            clipboard_timeout: 45,
+            sync: SyncConfig::default(),
+            git: GitConfig::default(),
+        }
+    }
+}
+
+/// Comportamiento de la sincronización con git
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SyncConfig {
+    /// Sincroniza automáticamente después de cada modificación
+    pub auto: bool,
+    /// Mensaje de commit por defecto
+    pub message: String,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            auto: false,
+            message: "Sync secrets".to_string(),
+        }
+    }
+}
+
+/// Remoto y rama usados por `crypta sync`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GitConfig {
+    pub remote: String,
+    pub branch: String,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            remote: "origin".to_string(),
+            branch: "main".to_string(),
+        }
+    }
+}
+
+impl Config {
+    /// Ruta del archivo de configuración: `CRYPTA_CONFIG`,
+    /// `$XDG_CONFIG_HOME/crypta/config.toml` o `~/.config/crypta/config.toml`
+    pub fn path() -> Option<PathBuf> {
+        if let Some(path) = env_var("CRYPTA_CONFIG") {
+            return Some(PathBuf::from(path));
+        }
+        if let Some(xdg) = env_var("XDG_CONFIG_HOME") {
+            return Some(Path::new(&xdg).join("crypta/config.toml"));
+        }
+        env_var("HOME").map(|home| Path::new(&home).join(".config/crypta/config.toml"))
+    }
+
+    /// Carga la configuración; si el archivo no existe devuelve la configuración por defecto
+    pub fn load() -> Result<Self> {
+        match Self::path() {
+            Some(path) if path.exists() => Self::from_path(&path),
+            _ => {
+                debug!("Sin archivo de configuración, usando valores por defecto");
+                Ok(Self::default())
+            }
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self> {
+        debug!("Leyendo configuración de {}", path.display());
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("No se pudo leer la configuración: {}", path.display()))?;
+        Self::from_toml(&content)
+            .with_context(|| format!("Configuración no válida: {}", path.display()))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self> {
+        toml::from_str(content).context("No se pudo parsear el archivo TOML")
+    }
+
+    /// Directorio raíz de los secretos: `--dir`, `CRYPTA_DIR`, `dir` o `$HOME/.secrets`
+    pub fn secrets_dir(&self, flag: Option<&Path>) -> Result<PathBuf> {
+        if let Some(dir) = flag {
+            return Ok(expand_home(dir));
+        }
+        if let Some(dir) = env_var("CRYPTA_DIR") {
+            return Ok(expand_home(Path::new(&dir)));
+        }
+        if let Some(dir) = &self.dir {
+            return Ok(expand_home(dir));
+        }
+        match env_var("HOME") {
+            Some(home) => Ok(Path::new(&home).join(".secrets")),
+            None => anyhow::bail!(
+                "No se pudo determinar el directorio de secretos: $HOME no está definida. \
+                Usa --dir, la variable CRYPTA_DIR o 'dir' en el archivo de configuración"
+            ),
+        }
+    }
+
+    /// Archivo de secretos: `--file`, `CRYPTA_FILE`, `file` o `secrets.yml`.
+    ///
+    /// Las rutas relativas se interpretan respecto al directorio del vault.
+    pub fn secrets_file(&self, flag: Option<&Path>) -> PathBuf {
+        if let Some(file) = flag {
+            return expand_home(file);
+        }
+        if let Some(file) = env_var("CRYPTA_FILE") {
+            return expand_home(Path::new(&file));
+        }
+        self.file
+            .as_deref()
+            .map(expand_home)
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_FILE))
+    }
+
+    /// Vault a usar: el indicado por `--vault`/`CRYPTA_VAULT` o `default_vault`
+    pub fn vault<'a>(&'a self, flag: Option<&'a str>) -> Option<&'a str> {
+        flag.or(self.default_vault.as_deref())
+    }
+
+    /// Backend de encriptación: `CRYPTA_BACKEND`, `backend` o `sops`
+    pub fn backend(&self) -> String {
+        env_var("CRYPTA_BACKEND")
+            .or_else(|| self.backend.clone())
+            .unwrap_or_else(|| "sops".to_string())
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Expande `~/` al principio de una ruta
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env_var("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
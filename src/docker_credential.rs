@@ -0,0 +1,201 @@
+use crate::error::{self, Error};
+use crate::import::{self, ConflictPolicy, Record};
+use crate::secrets;
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use tracing::info;
+
+/// Ruta bajo la que se guardan las credenciales de registros por defecto
+pub const DEFAULT_NAMESPACE: &str = "docker";
+
+/// Mensaje con el que Docker reconoce una credencial inexistente
+pub const NOT_FOUND: &str = "credentials not found in native keychain";
+
+/// Operaciones del protocolo de los helpers de credenciales de Docker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Operation {
+    /// Devuelve la credencial de un registro (URL por stdin)
+    Get,
+    /// Guarda una credencial (JSON por stdin)
+    Store,
+    /// Elimina la credencial de un registro (URL por stdin)
+    Erase,
+    /// Lista los registros con credencial y su usuario
+    List,
+}
+
+/// Credencial en el formato JSON del protocolo
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Credentials {
+    #[serde(rename = "ServerURL")]
+    pub server_url: String,
+    #[serde(rename = "Username")]
+    pub username: String,
+    #[serde(rename = "Secret")]
+    pub secret: String,
+}
+
+/// Clave del secreto de un registro: la URL sin esquema ni `/` final
+/// (`https://index.docker.io/v1/` → `docker/index.docker.io/v1`)
+pub fn key(namespace: &str, server_url: &str) -> Result<String> {
+    let server = server_url.trim();
+    let server = server.split_once("://").map_or(server, |(_, rest)| rest);
+    let server = tree::segments(server).join(&tree::SEPARATOR.to_string());
+    if server.is_empty() {
+        anyhow::bail!("URL de registro no válida: '{}'", server_url);
+    }
+    Ok(tree::segments(namespace)
+        .into_iter()
+        .chain([server.as_str()])
+        .collect::<Vec<_>>()
+        .join(&tree::SEPARATOR.to_string()))
+}
+
+/// Busca la credencial de un registro en el documento ya desencriptado
+pub fn get(doc: &Value, namespace: &str, server_url: &str) -> Result<Credentials> {
+    let key = key(namespace, server_url)?;
+    let entry = secrets::find(doc, &key)?;
+    Ok(Credentials {
+        server_url: server_url.trim().to_string(),
+        username: entry.meta.username.clone().unwrap_or_default(),
+        secret: entry.text().context("La credencial no es texto")?,
+    })
+}
+
+/// Registros con credencial y su usuario. La URL es la que se guardó con
+/// `store` o, si no hay, la ruta del secreto.
+pub fn list(doc: &Value, namespace: &str) -> BTreeMap<String, String> {
+    let base = tree::segments(namespace).join(&tree::SEPARATOR.to_string());
+    tree::leaves(doc, &base)
+        .into_iter()
+        .filter_map(|key| {
+            let entry = secrets::find(doc, &key).ok()?;
+            let url = entry.meta.url.clone().unwrap_or_else(|| {
+                key.strip_prefix(&base)
+                    .unwrap_or(&key)
+                    .trim_start_matches(tree::SEPARATOR)
+                    .to_string()
+            });
+            Some((url, entry.meta.username.unwrap_or_default()))
+        })
+        .collect()
+}
+
+/// Atiende una operación con la entrada de stdin y devuelve la respuesta.
+/// Si no hay credencial para el registro, `get` y `erase` fallan con
+/// [`Error::NotFound`].
+pub fn run(store: &Store, namespace: &str, operation: Operation, input: &str) -> Result<String> {
+    match operation {
+        Operation::Get => {
+            let doc = load(store)?;
+            let credentials = get(&doc, namespace, input)?;
+            serde_json::to_string(&credentials).context("No se pudo generar la credencial")
+        }
+        Operation::Store => {
+            let credentials: Credentials =
+                serde_json::from_str(input).context("Credencial JSON de Docker no válida")?;
+            let key = key(namespace, &credentials.server_url)?;
+            info!("Guardando la credencial de Docker '{}'", key);
+
+            let mut doc = store.load_or_default()?;
+            let mut record = Record::new(key, credentials.secret);
+            record.meta.username = Some(credentials.username);
+            record.meta.url = Some(credentials.server_url);
+            import::apply(&mut doc, vec![record], ConflictPolicy::Overwrite)?;
+            store.save(&doc)?;
+            Ok(String::new())
+        }
+        Operation::Erase => {
+            let mut doc = load(store)?;
+            let key = key(namespace, input)?;
+            if tree::remove(&mut doc, &key, false)?.is_none() {
+                return Err(Error::NotFound(key).into());
+            }
+            info!("Eliminando la credencial de Docker '{}'", key);
+            store.save(&doc)?;
+            Ok(String::new())
+        }
+        Operation::List => {
+            let doc = if store.exists() {
+                store.load()?
+            } else {
+                Value::Null
+            };
+            serde_json::to_string(&list(&doc, namespace)).context("No se pudo generar la lista")
+        }
+    }
+}
+
+fn load(store: &Store) -> Result<Value> {
+    if !store.exists() {
+        return Err(Error::NotFound(store.file().display().to_string()).into());
+    }
+    store.load()
+}
+
+/// Lee stdin, atiende la operación y escribe la respuesta en stdout. Si no
+/// hay credencial se escribe el mensaje que espera Docker y se devuelve el
+/// error `NotFound`, para que el proceso termine con su código de salida.
+pub fn serve(store: &Store, namespace: &str, operation: Operation) -> Result<()> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    match run(store, namespace, operation, &input) {
+        Ok(output) => {
+            io::stdout().write_all(output.as_bytes())?;
+            Ok(())
+        }
+        Err(e) if matches!(error::find(&e), Some(Error::NotFound(_))) => {
+            println!("{}", NOT_FOUND);
+            Err(e)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key() {
+        assert_eq!(
+            key("docker", "https://index.docker.io/v1/").unwrap(),
+            "docker/index.docker.io/v1"
+        );
+        assert_eq!(key("docker/", "ghcr.io").unwrap(), "docker/ghcr.io");
+        assert_eq!(
+            key("", "registry.example.com:5000\n").unwrap(),
+            "registry.example.com:5000"
+        );
+        assert!(key("docker", "https://").is_err());
+    }
+
+    #[test]
+    fn test_get_and_list() {
+        let doc: Value = serde_yaml::from_str(
+            "docker:\n  ghcr.io:\n    _value: t0ken\n    _meta:\n      username: bob\n      url: https://ghcr.io\n  quay.io: sin-usuario\n",
+        )
+        .unwrap();
+        assert_eq!(
+            get(&doc, "docker", "https://ghcr.io").unwrap(),
+            Credentials {
+                server_url: "https://ghcr.io".to_string(),
+                username: "bob".to_string(),
+                secret: "t0ken".to_string(),
+            }
+        );
+        assert!(get(&doc, "docker", "docker.io").is_err());
+
+        let registries = list(&doc, "docker");
+        assert_eq!(
+            registries.get("https://ghcr.io").map(String::as_str),
+            Some("bob")
+        );
+        assert_eq!(registries.get("quay.io").map(String::as_str), Some(""));
+    }
+}
@@ -0,0 +1,280 @@
+use anyhow::Result;
+
+/// Interpreta un archivo `.env` y devuelve sus variables en orden.
+///
+/// Admite comentarios (`#`), el prefijo `export`, valores entre comillas
+/// simples (literales) o dobles (con escapes `\n`, `\t`, `\"`, `\\`…) que
+/// pueden ocupar varias líneas, y comentarios al final de los valores sin
+/// comillas. Las variables repetidas conservan el último valor.
+pub fn parse(content: &str) -> Result<Vec<(String, String)>> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    let mut parser = Parser {
+        chars: content.chars().collect(),
+        pos: 0,
+        line: 1,
+    };
+
+    while let Some((name, value)) = parser.next_var()? {
+        vars.retain(|(n, _)| *n != name);
+        vars.push((name, value));
+    }
+    Ok(vars)
+}
+
+/// Genera un archivo `.env` con los valores entre comillas cuando hace falta
+pub fn serialize(vars: &[(String, String)]) -> String {
+    let mut out = String::new();
+    for (name, value) in vars {
+        out.push_str(name);
+        out.push('=');
+        out.push_str(&quote(value));
+        out.push('\n');
+    }
+    out
+}
+
+/// Entrecomilla un valor: sin comillas si solo tiene caracteres seguros,
+/// comillas simples si es de una línea sin `'` y dobles con escapes si no.
+/// En las comillas dobles se escapan también `$` y `` ` ``.
+pub fn quote(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+,".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        return value.to_string();
+    }
+    if !value.contains(['\'', '\n', '\r']) {
+        return format!("'{}'", value);
+    }
+
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            // Evita la interpolación al cargar el archivo desde una shell
+            '$' => quoted.push_str("\\$"),
+            '`' => quoted.push_str("\\`"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Indica si un nombre es válido como variable de entorno
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn skip_blanks(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.bump();
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(c) = self.bump() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+
+    fn word(&mut self) -> String {
+        let mut word = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() || c == '=' || c == '#' {
+                break;
+            }
+            word.push(c);
+            self.bump();
+        }
+        word
+    }
+
+    fn next_var(&mut self) -> Result<Option<(String, String)>> {
+        loop {
+            self.skip_blanks();
+            match self.peek() {
+                None => return Ok(None),
+                Some('\n' | '\r') => {
+                    self.bump();
+                }
+                Some('#') => self.skip_line(),
+                Some(_) => break,
+            }
+        }
+
+        let line = self.line;
+        let mut name = self.word();
+        if name == "export" && matches!(self.peek(), Some(' ' | '\t')) {
+            self.skip_blanks();
+            name = self.word();
+        }
+        if !is_valid_name(&name) {
+            anyhow::bail!("Línea {}: nombre de variable no válido: '{}'", line, name);
+        }
+
+        self.skip_blanks();
+        if self.bump() != Some('=') {
+            anyhow::bail!("Línea {}: falta '=' después de '{}'", line, name);
+        }
+        self.skip_blanks();
+
+        let value = match self.peek() {
+            Some('\'') => self.quoted('\'', line)?,
+            Some('"') => self.quoted('"', line)?,
+            _ => self.unquoted(),
+        };
+
+        // Tras un valor entre comillas solo puede haber un comentario
+        self.skip_blanks();
+        match self.peek() {
+            None | Some('\n' | '\r') => {}
+            Some('#') => self.skip_line(),
+            Some(c) => anyhow::bail!(
+                "Línea {}: carácter inesperado '{}' después del valor de '{}'",
+                line,
+                c,
+                name
+            ),
+        }
+
+        Ok(Some((name, value)))
+    }
+
+    fn unquoted(&mut self) -> String {
+        let mut value = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\n' || c == '\r' {
+                break;
+            }
+            // `#` inicia un comentario solo si va precedido de un espacio
+            if c == '#' && value.ends_with([' ', '\t']) {
+                break;
+            }
+            value.push(c);
+            self.bump();
+        }
+        value.trim_end().to_string()
+    }
+
+    fn quoted(&mut self, quote: char, line: usize) -> Result<String> {
+        self.bump();
+        let mut value = String::new();
+        loop {
+            let Some(c) = self.bump() else {
+                anyhow::bail!("Línea {}: comillas {} sin cerrar", line, quote);
+            };
+            match c {
+                c if c == quote => return Ok(value),
+                '\\' if quote == '"' => match self.bump() {
+                    Some('n') => value.push('\n'),
+                    Some('r') => value.push('\r'),
+                    Some('t') => value.push('\t'),
+                    Some(c @ ('"' | '\\' | '$' | '`' | '\'')) => value.push(c),
+                    Some(c) => {
+                        value.push('\\');
+                        value.push(c);
+                    }
+                    None => anyhow::bail!("Línea {}: comillas {} sin cerrar", line, quote),
+                },
+                c => value.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(content: &str) -> Vec<(String, String)> {
+        parse(content).unwrap()
+    }
+
+    fn var(name: &str, value: &str) -> (String, String) {
+        (name.to_string(), value.to_string())
+    }
+
+    #[test]
+    fn test_parse_basic_and_comments() {
+        let content = "# comentario\n\nA=1\nexport B = dos # nota\nC=url#fragmento\nD=\nexport=x\n";
+        assert_eq!(
+            vars(content),
+            vec![
+                var("A", "1"),
+                var("B", "dos"),
+                var("C", "url#fragmento"),
+                var("D", ""),
+                var("export", "x"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_quotes_and_escapes() {
+        let content =
+            "S='sin $escapes \\n'\nD=\"a\\\"b\\\\c\\nd\" # nota\nM=\"línea 1\nlínea 2\"\n";
+        assert_eq!(
+            vars(content),
+            vec![
+                var("S", "sin $escapes \\n"),
+                var("D", "a\"b\\c\nd"),
+                var("M", "línea 1\nlínea 2"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse("A=\"sin cerrar\n").is_err());
+        assert!(parse("1A=x\n").is_err());
+        assert!(parse("A x\n").is_err());
+        assert!(parse("A='x' y\n").is_err());
+    }
+
+    #[test]
+    fn test_serialize_roundtrip() {
+        let original = vec![
+            var("PLAIN", "abc-123"),
+            var("SPACES", "con espacios $HOME"),
+            var("QUOTE", "it's \"quoted\" $HOME `id`"),
+            var("MULTI", "a\nb\\n"),
+            var("EMPTY", ""),
+        ];
+        let content = serialize(&original);
+        assert_eq!(
+            content,
+            "PLAIN=abc-123\nSPACES='con espacios $HOME'\nQUOTE=\"it's \\\"quoted\\\" \\$HOME \\`id\\`\"\n\
+             MULTI=\"a\\nb\\\\n\"\nEMPTY=''\n"
+        );
+        assert_eq!(vars(&content), original);
+    }
+}
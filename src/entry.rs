@@ -0,0 +1,347 @@
+use crate::tree;
+use anyhow::{Context, Result};
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, SubsecRound, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+/// Clave reservada con el valor de un secreto que tiene metadatos
+pub const VALUE_KEY: &str = "_value";
+
+/// Clave reservada con los metadatos de un secreto
+pub const META_KEY: &str = "_meta";
+
+/// Metadatos opcionales de un secreto
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<DateTime<Utc>>,
+    /// Usuario que modificó el valor por última vez
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_by: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Equipo propietario del secreto
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// Usuario de la cuenta a la que pertenece el secreto
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    /// Fecha a partir de la cual el secreto deja de ser válido
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<DateTime<Utc>>,
+    /// Cada cuánto hay que rotar el secreto (`90d`, `2w`, `12h`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotate_every: Option<String>,
+    /// Codificación del valor; sin ella el valor es texto
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<Encoding>,
+    /// Nombre visible del secreto en el Secret Service
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Atributos de búsqueda del Secret Service (`xdg:schema`, `server`…)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// Codificación de los valores que no son texto
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    /// Bytes arbitrarios codificados en base64
+    Base64,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Fecha en la que el secreto caduca o debe rotarse, la más próxima de
+    /// `expires` y la última modificación más `rotate_every`
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        let rotation = self
+            .rotate_every
+            .as_deref()
+            .and_then(|every| parse_duration(every).ok())
+            .and_then(|every| self.updated.or(self.created)?.checked_add_signed(every));
+        match (self.expires, rotation) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// Indica si el secreto ya ha caducado o debía haberse rotado
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.due().is_some_and(|due| due <= now)
+    }
+}
+
+/// Interpreta una duración como `14d`, `2w` o `12h`
+pub fn parse_duration(value: &str) -> Result<TimeDelta> {
+    let value = value.trim();
+    let invalid = || {
+        anyhow::anyhow!(
+            "Duración no válida: '{}'. Usa un número seguido de h, d o w (por ejemplo 90d)",
+            value
+        )
+    };
+
+    let (split, _) = value.char_indices().last().ok_or_else(invalid)?;
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    if amount < 0 {
+        return Err(invalid());
+    }
+    let delta = match unit {
+        "h" => TimeDelta::try_hours(amount),
+        "d" => TimeDelta::try_days(amount),
+        "w" => TimeDelta::try_weeks(amount),
+        _ => return Err(invalid()),
+    };
+    delta.ok_or_else(|| anyhow::anyhow!("Duración demasiado grande: '{}'", value))
+}
+
+/// Interpreta una fecha `AAAA-MM-DD` (medianoche UTC) o RFC 3339
+pub fn parse_date(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(Default::default()).and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|d| d.with_timezone(&Utc))
+        .with_context(|| format!("Fecha no válida: '{}'. Usa AAAA-MM-DD o RFC 3339", value))
+}
+
+/// Cambios a aplicar sobre los metadatos. Una cadena vacía borra el campo.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataUpdate {
+    pub note: Option<String>,
+    pub owner: Option<String>,
+    pub url: Option<String>,
+    pub username: Option<String>,
+    /// Fecha de caducidad (`AAAA-MM-DD` o RFC 3339)
+    pub expires: Option<String>,
+    /// Periodo de rotación (`90d`)
+    pub rotate_every: Option<String>,
+    pub add_tags: Vec<String>,
+    pub remove_tags: Vec<String>,
+}
+
+impl MetadataUpdate {
+    pub fn is_empty(&self) -> bool {
+        self.note.is_none()
+            && self.owner.is_none()
+            && self.url.is_none()
+            && self.username.is_none()
+            && self.expires.is_none()
+            && self.rotate_every.is_none()
+            && self.add_tags.is_empty()
+            && self.remove_tags.is_empty()
+    }
+
+    pub fn apply(&self, meta: &mut Metadata) -> Result<()> {
+        fn update(field: &mut Option<String>, value: &Option<String>) {
+            if let Some(value) = value {
+                *field = Some(value.clone()).filter(|v| !v.is_empty());
+            }
+        }
+
+        update(&mut meta.note, &self.note);
+        update(&mut meta.owner, &self.owner);
+        update(&mut meta.url, &self.url);
+        update(&mut meta.username, &self.username);
+
+        if let Some(expires) = &self.expires {
+            meta.expires = match expires.as_str() {
+                "" => None,
+                date => Some(parse_date(date)?),
+            };
+        }
+        if let Some(every) = &self.rotate_every {
+            if !every.is_empty() {
+                parse_duration(every)?;
+            }
+            update(&mut meta.rotate_every, &self.rotate_every);
+        }
+
+        for tag in &self.add_tags {
+            if !meta.tags.contains(tag) {
+                meta.tags.push(tag.clone());
+            }
+        }
+        meta.tags.retain(|t| !self.remove_tags.contains(t));
+        Ok(())
+    }
+}
+
+/// Un secreto con su valor y sus metadatos.
+///
+/// En el documento, un secreto sin metadatos es un valor simple
+/// (`API_KEY: valor`) y uno con metadatos es un mapping con las claves
+/// reservadas `_value` y `_meta`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub value: Value,
+    pub meta: Metadata,
+}
+
+impl Entry {
+    pub fn new(value: impl Into<Value>) -> Self {
+        Self {
+            value: value.into(),
+            meta: Metadata::default(),
+        }
+    }
+
+    /// Indica si un nodo del documento es un secreto y no un grupo
+    pub fn is_entry(node: &Value) -> bool {
+        match node.as_mapping() {
+            Some(map) => map.contains_key(VALUE_KEY),
+            None => true,
+        }
+    }
+
+    /// Interpreta un nodo del documento como secreto
+    pub fn from_node(node: &Value) -> Result<Self> {
+        let Some(map) = node.as_mapping() else {
+            return Ok(Self::new(node.clone()));
+        };
+
+        let value = map
+            .get(VALUE_KEY)
+            .cloned()
+            .context("El nodo no es un secreto")?;
+        let meta = match map.get(META_KEY) {
+            Some(meta) => serde_yaml::from_value(meta.clone())
+                .context("No se pudieron leer los metadatos del secreto")?,
+            None => Metadata::default(),
+        };
+        Ok(Self { value, meta })
+    }
+
+    /// Representación en el documento: valor simple si no hay metadatos.
+    /// Los objetos siempre se envuelven para no confundirlos con un grupo.
+    pub fn to_node(&self) -> Result<Value> {
+        if self.meta.is_empty() && !self.value.is_mapping() {
+            return Ok(self.value.clone());
+        }
+
+        let mut map = Mapping::new();
+        map.insert(VALUE_KEY.into(), self.value.clone());
+        map.insert(
+            META_KEY.into(),
+            serde_yaml::to_value(&self.meta).context("No se pudieron serializar los metadatos")?,
+        );
+        Ok(Value::Mapping(map))
+    }
+
+    /// Valor como texto, si es un escalar. Los valores binarios se devuelven
+    /// decodificados solo si son UTF-8 válido.
+    pub fn text(&self) -> Option<String> {
+        match self.meta.encoding {
+            Some(Encoding::Base64) => String::from_utf8(self.bytes().ok()?).ok(),
+            None => tree::scalar_to_string(&self.value),
+        }
+    }
+
+    /// Bytes exactos del valor
+    pub fn bytes(&self) -> Result<Vec<u8>> {
+        let text = tree::scalar_to_string(&self.value).context("El secreto no es un escalar")?;
+        match self.meta.encoding {
+            Some(Encoding::Base64) => base64::engine::general_purpose::STANDARD
+                .decode(text.trim())
+                .context("El valor binario no es base64 válido"),
+            None => Ok(text.into_bytes()),
+        }
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.meta.encoding.is_some()
+    }
+
+    /// Nuevo valor de texto para un secreto existente (o nuevo), conservando
+    /// sus metadatos y actualizando las marcas de tiempo
+    pub fn updated(previous: Option<Entry>, value: Value) -> Self {
+        let now = Utc::now().trunc_subsecs(0);
+        let mut meta = previous.map(|e| e.meta).unwrap_or_default();
+        meta.encoding = None;
+        meta.created.get_or_insert(now);
+        meta.updated = Some(now);
+        meta.updated_by = std::env::var("USER")
+            .or_else(|_| std::env::var("LOGNAME"))
+            .ok();
+        Self { value, meta }
+    }
+
+    /// Como [`Entry::updated`], pero con un valor binario codificado en base64
+    pub fn updated_binary(previous: Option<Entry>, bytes: &[u8]) -> Self {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        let mut entry = Self::updated(previous, Value::String(encoded));
+        entry.meta.encoding = Some(Encoding::Base64);
+        entry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90d").unwrap(), TimeDelta::days(90));
+        assert_eq!(parse_duration("2w").unwrap(), TimeDelta::weeks(2));
+        assert_eq!(parse_duration("12h").unwrap(), TimeDelta::hours(12));
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("3m").is_err());
+        assert!(parse_duration("1é").is_err());
+        assert!(parse_duration("é").is_err());
+        assert!(parse_duration("-5d").is_err());
+        assert!(parse_duration("9999999999999w").is_err());
+        assert!(parse_duration("99999999999999999999d").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        let date = parse_date("2025-06-01").unwrap();
+        assert_eq!(date.to_rfc3339(), "2025-06-01T00:00:00+00:00");
+        let date = parse_date("2025-06-01T12:00:00+02:00").unwrap();
+        assert_eq!(date.to_rfc3339(), "2025-06-01T10:00:00+00:00");
+        assert!(parse_date("mañana").is_err());
+    }
+
+    #[test]
+    fn test_due_is_the_earliest_date() {
+        let meta = Metadata {
+            updated: Some(parse_date("2025-01-01").unwrap()),
+            rotate_every: Some("30d".to_string()),
+            expires: Some(parse_date("2025-03-01").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(meta.due(), Some(parse_date("2025-01-31").unwrap()));
+        assert!(meta.is_expired(parse_date("2025-02-01").unwrap()));
+        assert!(!meta.is_expired(parse_date("2025-01-30").unwrap()));
+        assert_eq!(Metadata::default().due(), None);
+    }
+
+    #[test]
+    fn test_due_does_not_overflow() {
+        let meta = Metadata {
+            updated: Some(parse_date("2025-01-01").unwrap()),
+            rotate_every: Some("1000000000d".to_string()),
+            ..Default::default()
+        };
+        assert!(parse_duration("1000000000d").is_ok());
+        assert_eq!(meta.due(), None);
+        assert!(!meta.is_expired(parse_date("2025-02-01").unwrap()));
+    }
+}
@@ -0,0 +1,182 @@
+use crate::secrets;
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::process::Command;
+use tracing::{debug, info, warn};
+
+/// Variable de entorno a inyectar: `API_KEY` o `DB=DB_PASSWORD`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvSpec {
+    pub var: String,
+    pub key: String,
+}
+
+impl EnvSpec {
+    /// Interpreta `VAR=CLAVE`; sin `=` el nombre de la variable se deriva de la clave
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (var, key) = match spec.split_once('=') {
+            Some((var, key)) => (var.to_string(), key.to_string()),
+            None => (var_name(spec), spec.to_string()),
+        };
+        if var.is_empty() || key.is_empty() {
+            anyhow::bail!("Variable no válida: '{}'. Usa CLAVE o VAR=CLAVE", spec);
+        }
+        Ok(Self { var, key })
+    }
+}
+
+/// Nombre de variable de entorno para una clave: `prod/db-password` → `PROD_DB_PASSWORD`
+pub fn var_name(key: &str) -> String {
+    key.trim_matches(tree::SEPARATOR)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Resuelve las variables a inyectar desencriptando el almacén una sola vez.
+///
+/// Con `prefix`, se añaden todos los secretos bajo esa ruta sin el prefijo en
+/// el nombre (`prod/db/password` → `DB_PASSWORD`); `all` equivale a un prefijo
+/// vacío. Si el prefijo es un secreto, la variable toma su último segmento
+/// (`prod/api-key` → `API_KEY`). Los secretos binarios se omiten.
+pub fn collect(
+    doc: &Value,
+    specs: &[EnvSpec],
+    prefix: Option<&str>,
+) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+
+    if let Some(prefix) = prefix {
+        let base = tree::segments(prefix).join(&tree::SEPARATOR.to_string());
+        for key in tree::leaves(doc, &base) {
+            let relative = tree::relative(&key, &base);
+            match secrets::text(doc, &key) {
+                Ok(value) => vars.push((var_name(relative), value)),
+                Err(e) => warn!("Omitiendo '{}': {}", key, e),
+            }
+        }
+    }
+
+    // Las variables explícitas tienen prioridad sobre las del prefijo
+    for spec in specs {
+        let value = secrets::text(doc, &spec.key)?;
+        vars.retain(|(var, _)| *var != spec.var);
+        vars.push((spec.var.clone(), value));
+    }
+
+    Ok(vars)
+}
+
+/// Ejecuta `command` con los secretos como variables de entorno.
+///
+/// En Unix el proceso de crypta se reemplaza por el comando, de modo que las
+/// señales y el código de salida son los del propio comando. En otros sistemas
+/// se espera al hijo y se termina con su código de salida.
+pub fn exec(
+    store: &Store,
+    specs: &[EnvSpec],
+    prefix: Option<&str>,
+    command: &[String],
+) -> Result<()> {
+    let vars = collect(&store.load()?, specs, prefix)?;
+    if vars.is_empty() {
+        anyhow::bail!("No se seleccionó ningún secreto. Usa --env, --prefix o --all");
+    }
+    exec_with(vars, command)
+}
+
+/// Ejecuta `command` con `vars` añadidas al entorno; ver [`exec`]
+pub fn exec_with(vars: Vec<(String, String)>, command: &[String]) -> Result<()> {
+    let (program, args) = command
+        .split_first()
+        .context("No se indicó ningún comando a ejecutar")?;
+    info!("Ejecutando '{}' con secretos en el entorno", program);
+    debug!(
+        "Variables: {}",
+        vars.iter()
+            .map(|(var, _)| var.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+
+    let mut cmd = Command::new(program);
+    cmd.args(args).envs(vars);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        let err = cmd.exec();
+        Err(err).with_context(|| format!("No se pudo ejecutar '{}'", program))
+    }
+
+    #[cfg(not(unix))]
+    {
+        let status = cmd
+            .status()
+            .with_context(|| format!("No se pudo ejecutar '{}'", program))?;
+        std::process::exit(status.code().unwrap_or(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_var_name() {
+        assert_eq!(var_name("API_KEY"), "API_KEY");
+        assert_eq!(var_name("prod/db-password"), "PROD_DB_PASSWORD");
+        assert_eq!(var_name("/app.env/"), "APP_ENV");
+    }
+
+    #[test]
+    fn test_parse_spec() {
+        let spec = EnvSpec::parse("DB=prod/db/password").unwrap();
+        assert_eq!(spec.var, "DB");
+        assert_eq!(spec.key, "prod/db/password");
+        assert_eq!(EnvSpec::parse("prod/token").unwrap().var, "PROD_TOKEN");
+        assert!(EnvSpec::parse("=KEY").is_err());
+    }
+
+    #[test]
+    fn test_collect_explicit_overrides_prefix() {
+        let doc: Value =
+            serde_yaml::from_str("prod:\n  token: a\n  db:\n    password: b\nOTHER: c\n").unwrap();
+        let specs = vec![EnvSpec::parse("TOKEN=OTHER").unwrap()];
+        let vars = collect(&doc, &specs, Some("prod")).unwrap();
+        assert_eq!(
+            vars,
+            vec![
+                ("DB_PASSWORD".to_string(), "b".to_string()),
+                ("TOKEN".to_string(), "c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_prefix_naming_a_secret() {
+        let doc: Value = serde_yaml::from_str(
+            "prod:
+  api-key: a
+API_KEY: b
+",
+        )
+        .unwrap();
+        assert_eq!(
+            collect(&doc, &[], Some("prod/api-key")).unwrap(),
+            vec![("API_KEY".to_string(), "a".to_string())]
+        );
+        assert_eq!(
+            collect(&doc, &[], Some("API_KEY")).unwrap(),
+            vec![("API_KEY".to_string(), "b".to_string())]
+        );
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+/// Errores de crypta que el llamante puede distinguir sin comparar mensajes.
+///
+/// Las funciones de la librería devuelven `anyhow::Result`; estos errores
+/// viajan dentro del `anyhow::Error` y se recuperan con [`find`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// La clave no existe en el almacén
+    #[error("La clave '{0}' no existe")]
+    NotFound(String),
+
+    /// El archivo de secretos no existe
+    #[error(
+        "El archivo de secretos no existe: {}\n\nPrimero añade un secreto con: crypta add CLAVE valor",
+        .0.display()
+    )]
+    StoreNotFound(PathBuf),
+
+    /// El backend no pudo desencriptar el documento (clave incorrecta, formato no válido…)
+    #[error("Error al desencriptar: {0}")]
+    DecryptFailed(String),
+
+    /// El backend no pudo encriptar el documento
+    #[error("Error al encriptar: {0}")]
+    EncryptFailed(String),
+
+    /// El documento ha sido modificado o está corrupto (MAC no válido)
+    #[error("Error de integridad: el archivo de secretos ha sido modificado o está corrupto: {0}")]
+    IntegrityFailed(String),
+
+    /// Falta la herramienta externa que necesita el backend
+    #[error("El comando '{0}' no está instalado")]
+    BackendMissing(String),
+
+    /// El remoto Git rechazó las credenciales
+    #[error("Error de autenticación Git: {0}")]
+    GitAuth(String),
+
+    /// Conflicto al integrar los cambios del remoto
+    #[error("Conflicto: {0}")]
+    Conflict(String),
+
+    /// Hay secretos caducados o que deben rotarse pronto
+    #[error("{0} secreto(s) caducados o por caducar")]
+    Expiring(usize),
+}
+
+impl Error {
+    /// Código de salida del CLI asociado a cada error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::NotFound(_) => 2,
+            Error::StoreNotFound(_) => 3,
+            Error::DecryptFailed(_) => 4,
+            Error::EncryptFailed(_) => 5,
+            Error::IntegrityFailed(_) => 6,
+            Error::BackendMissing(_) => 7,
+            Error::GitAuth(_) => 8,
+            Error::Conflict(_) => 9,
+            Error::Expiring(_) => 10,
+        }
+    }
+}
+
+/// Busca un [`Error`] de crypta en la cadena de causas de un `anyhow::Error`
+pub fn find(err: &anyhow::Error) -> Option<&Error> {
+    err.chain().find_map(|cause| cause.downcast_ref::<Error>())
+}
+
+/// Código de salida del CLI para cualquier error (1 si no es un [`Error`] de crypta)
+pub fn exit_code(err: &anyhow::Error) -> i32 {
+    find(err).map(Error::exit_code).unwrap_or(1)
+}
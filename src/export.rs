@@ -0,0 +1,302 @@
+use crate::backend;
+use crate::dotenv;
+use crate::env;
+use crate::error::Error;
+use crate::materialize;
+use crate::secrets;
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use base64::Engine;
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::{debug, info, warn};
+
+/// Formatos a los que se pueden exportar secretos
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// Archivo `.env` (`CLAVE=valor`)
+    Dotenv,
+    /// Manifiesto `v1/Secret` de Kubernetes
+    K8s,
+    /// Manifiesto `v1/Secret` encriptado con SOPS para los mismos destinatarios
+    SopsK8s,
+    /// Un archivo por secreto con permisos 0400, para los `secrets` de Docker
+    DockerSecrets,
+}
+
+/// Opciones de [`render`] y [`export`]
+#[derive(Debug, Default, Clone)]
+pub struct ExportOptions {
+    /// Exporta solo los secretos bajo esta ruta, sin el prefijo en el nombre
+    pub prefix: Option<String>,
+    /// Patrones de clave (`prod/*`); sin patrones se exportan todas
+    pub keys: Vec<String>,
+    /// Nombre del `Secret` de Kubernetes
+    pub name: Option<String>,
+    /// Namespace del `Secret` de Kubernetes
+    pub namespace: Option<String>,
+}
+
+/// Indica si `key` coincide con `pattern`, donde `*` equivale a cualquier
+/// texto (incluidas las `/`)
+pub fn matches(pattern: &str, key: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = key.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+/// Parte fija de un patrón hasta la última `/` antes del primer `*`
+fn pattern_base(pattern: &str) -> &str {
+    let fixed = pattern.split('*').next().unwrap_or_default();
+    fixed
+        .rfind(tree::SEPARATOR)
+        .map(|i| &fixed[..i])
+        .unwrap_or_default()
+}
+
+/// Secretos a exportar con su ruta relativa: los de `prefix` que coinciden
+/// con algún patrón. La ruta se calcula sin el prefijo o, si no lo hay, sin
+/// la parte fija del patrón (`prod/*`: `prod/db/password` → `db/password`).
+/// Si el prefijo es un secreto, su ruta es su último segmento.
+fn select(doc: &Value, options: &ExportOptions) -> Vec<(String, String)> {
+    let prefix = options.prefix.as_deref().unwrap_or_default();
+    let base = tree::segments(prefix).join(&tree::SEPARATOR.to_string());
+
+    let mut selected = Vec::new();
+    for key in tree::leaves(doc, &base) {
+        let pattern = options.keys.iter().find(|p| matches(p, &key));
+        if !options.keys.is_empty() && pattern.is_none() {
+            continue;
+        }
+        let strip = match pattern {
+            Some(pattern) if base.is_empty() => pattern_base(pattern),
+            _ => base.as_str(),
+        };
+        selected.push((tree::relative(&key, strip).to_string(), key));
+    }
+    selected
+}
+
+/// Nombre con el que se exporta cada secreto seleccionado. Falla si dos
+/// claves dan el mismo nombre (`db/password` y `db-password` →
+/// `DB_PASSWORD`), porque una sobrescribiría a la otra.
+fn names(
+    selected: Vec<(String, String)>,
+    name: impl Fn(&str) -> String,
+) -> Result<Vec<(String, String)>> {
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut named = Vec::with_capacity(selected.len());
+    for (relative, key) in selected {
+        let name = name(&relative);
+        if let Some(other) = seen.get(&name) {
+            anyhow::bail!(
+                "'{}' y '{}' se exportarían con el mismo nombre {}; exporta solo una de ellas",
+                other,
+                key,
+                name
+            );
+        }
+        seen.insert(name.clone(), key.clone());
+        named.push((name, key));
+    }
+    Ok(named)
+}
+
+/// Genera la exportación de los secretos seleccionados del documento ya
+/// desencriptado. Los nombres son los mismos que usa `crypta exec --prefix`.
+/// Los valores estructurados se exportan como JSON en todos los formatos y
+/// los binarios, que no caben en un `.env`, se omiten allí con un aviso. Con
+/// `sops-k8s` se genera el manifiesto sin encriptar; [`export`] lo encripta.
+pub fn render(doc: &Value, format: ExportFormat, options: &ExportOptions) -> Result<String> {
+    let selected = select(doc, options);
+    match format {
+        ExportFormat::Dotenv => {
+            let mut vars = Vec::with_capacity(selected.len());
+            for (name, key) in names(selected, env::var_name)? {
+                match secrets::text(doc, &key) {
+                    Ok(value) => vars.push((name, value)),
+                    Err(e) => warn!("Omitiendo '{}': {}", key, e),
+                }
+            }
+            Ok(dotenv::serialize(&vars))
+        }
+        ExportFormat::K8s | ExportFormat::SopsK8s => {
+            let name = options
+                .name
+                .as_deref()
+                .context("Indica el nombre del Secret con --name")?;
+            let mut data = Mapping::new();
+            for (var, key) in names(selected, env::var_name)? {
+                // Los secretos binarios se exportan tal cual: `data` va en base64
+                let encoded = base64::engine::general_purpose::STANDARD.encode(bytes(doc, &key)?);
+                data.insert(var.into(), encoded.into());
+            }
+            manifest(name, options.namespace.as_deref(), data)
+        }
+        ExportFormat::DockerSecrets => anyhow::bail!(
+            "El formato docker-secrets genera un archivo por secreto; indica el directorio con -o"
+        ),
+    }
+}
+
+/// Archivos de `docker-secrets`: el nombre es la ruta relativa con `_` en
+/// lugar de `/` (`db/password` → `db_password`) y el contenido, el valor tal
+/// cual, también para los secretos binarios.
+pub fn files(doc: &Value, options: &ExportOptions) -> Result<Vec<(String, Vec<u8>)>> {
+    names(select(doc, options), |relative| {
+        relative.replace(tree::SEPARATOR, "_")
+    })?
+    .into_iter()
+    .map(|(name, key)| Ok((name, bytes(doc, &key)?)))
+    .collect()
+}
+
+/// Valor de un secreto para `data` o un archivo: los binarios tal cual y el
+/// resto como en `.env` (los valores estructurados, en JSON)
+fn bytes(doc: &Value, key: &str) -> Result<Vec<u8>> {
+    let entry = secrets::find(doc, key)?;
+    if entry.is_binary() {
+        return entry.bytes();
+    }
+    Ok(secrets::text(doc, key)?.into_bytes())
+}
+
+/// Manifiesto `v1/Secret` de tipo `Opaque`
+fn manifest(name: &str, namespace: Option<&str>, data: Mapping) -> Result<String> {
+    let mut metadata = Mapping::new();
+    metadata.insert("name".into(), name.into());
+    if let Some(namespace) = namespace {
+        metadata.insert("namespace".into(), namespace.into());
+    }
+
+    let mut secret = Mapping::new();
+    secret.insert("apiVersion".into(), "v1".into());
+    secret.insert("kind".into(), "Secret".into());
+    secret.insert("metadata".into(), metadata.into());
+    secret.insert("type".into(), "Opaque".into());
+    secret.insert("data".into(), data.into());
+    serde_yaml::to_string(&secret).context("No se pudo generar el manifiesto")
+}
+
+/// Encripta el manifiesto con `sops` para los destinatarios del almacén.
+/// Solo se encriptan `data` y `stringData`, para que el manifiesto siga
+/// siendo legible en un repositorio GitOps.
+fn encrypt_manifest(store: &Store, manifest: &str) -> Result<String> {
+    backend::verify_sops_installed()?;
+    let recipients = store.backend().recipients(store.file())?;
+    if recipients.is_empty() {
+        anyhow::bail!("El almacén no tiene destinatarios Age para encriptar el manifiesto");
+    }
+    debug!(
+        "Encriptando el manifiesto para {} destinatarios",
+        recipients.len()
+    );
+
+    let mut child = Command::new("sops")
+        .args(["--encrypt", "--input-type", "yaml", "--output-type", "yaml"])
+        .args(["--encrypted-regex", "^(data|stringData)$"])
+        .arg("--age")
+        .arg(recipients.join(","))
+        .arg("/dev/stdin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("No se pudo ejecutar sops")?;
+    child
+        .stdin
+        .take()
+        .context("No se pudo escribir en sops")?
+        .write_all(manifest.as_bytes())
+        .context("No se pudo escribir en sops")?;
+    let output = child
+        .wait_with_output()
+        .context("No se pudo ejecutar sops")?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::EncryptFailed(error).into());
+    }
+    String::from_utf8(output.stdout).context("sops ha devuelto un manifiesto que no es UTF-8")
+}
+
+/// Exporta los secretos a `output` (permisos 0600) o por stdout. Con
+/// `docker-secrets`, `output` es el directorio donde se escribe un archivo
+/// por secreto, registrado para `crypta cleanup`.
+pub fn export(
+    store: &Store,
+    format: ExportFormat,
+    options: &ExportOptions,
+    output: Option<&Path>,
+) -> Result<()> {
+    info!("Exportando secretos como {:?}", format);
+    let doc = store.load()?;
+    if format == ExportFormat::DockerSecrets {
+        let dir = output.context(
+            "Indica el directorio con -o, preferiblemente en un tmpfs (/run/user/$UID/…)",
+        )?;
+        let files = files(&doc, options)?;
+        let dir = materialize::write_dir(dir, &files)?;
+        println!(
+            "📦 {} secretos escritos en {} (elimínalos con 'crypta cleanup')",
+            files.len(),
+            dir.display()
+        );
+        return Ok(());
+    }
+
+    let mut content = render(&doc, format, options)?;
+    if format == ExportFormat::SopsK8s {
+        content = encrypt_manifest(store, &content)?;
+    }
+
+    match output {
+        Some(path) => {
+            secrets::write_private(path, content.as_bytes())?;
+            println!("📦 Secretos exportados en {}", path.display());
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("prod/*", "prod/db/password"));
+        assert!(matches("*", "api"));
+        assert!(matches("prod/*/password", "prod/db/password"));
+        assert!(matches("api", "api"));
+        assert!(!matches("api", "api2"));
+        assert!(!matches("prod/*", "dev/db"));
+        assert!(!matches("*-key", "prod/key"));
+        assert!(!matches("a*b*a", "ab"));
+    }
+
+    #[test]
+    fn test_pattern_base() {
+        assert_eq!(pattern_base("prod/*"), "prod");
+        assert_eq!(pattern_base("prod/db-*"), "prod");
+        assert_eq!(pattern_base("*"), "");
+        assert_eq!(pattern_base("api"), "");
+    }
+}
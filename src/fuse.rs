@@ -0,0 +1,851 @@
+//! Sistema de archivos FUSE que expone el almacén como archivos
+//! (`crypta mount`).
+//!
+//! Cada secreto es un archivo de solo lectura y cada grupo un directorio.
+//! El documento se desencripta al acceder (y otra vez cuando cambia el
+//! archivo del almacén) y solo vive en memoria: los archivos se abren con
+//! `direct_io`, así que su contenido ni siquiera pasa por la caché de páginas
+//! del kernel. Con escritura, el contenido de un archivo se vuelve a
+//! encriptar en el almacén al cerrarlo.
+//!
+//! El protocolo del kernel y el montaje (mount(2) o `fusermount3`) son cosa
+//! de `fuser`; aquí solo se traducen rutas del almacén a inodos y archivos.
+
+use crate::entry::Entry;
+use crate::secrets::{self, Format};
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use fuser::{
+    Config, Errno, FileAttr, FileHandle, FileType, FopenFlags, Generation, INodeNo, InitFlags,
+    KernelConfig, LockOwner, MountOption, OpenFlags, ReplyAttr, ReplyCreate, ReplyData,
+    ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyStatfs, ReplyWrite, Request,
+    SessionUnmounter, TimeOrNow, WriteFlags,
+};
+use serde_yaml::{Mapping, Value};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{debug, info, warn};
+
+const ROOT_INODE: u64 = INodeNo::ROOT.0;
+/// Tamaño máximo de un secreto escrito a través del montaje
+const MAX_FILE_LEN: u64 = 16 * 1024 * 1024;
+/// Tiempo durante el que el kernel puede reutilizar nombres y atributos
+const TTL: Duration = Duration::from_secs(1);
+
+/// Resultado de una operación: el valor a responder o un `errno`
+type Reply<T> = std::result::Result<T, Errno>;
+
+/// Un nodo del montaje: un grupo o un secreto con su contenido
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Dir,
+    File(Vec<u8>, Option<SystemTime>),
+}
+
+/// Archivo abierto. El contenido se desencripta al abrir y, si se modifica,
+/// se guarda al cerrar.
+struct Handle {
+    path: String,
+    data: Vec<u8>,
+    dirty: bool,
+}
+
+/// Contenido de un secreto como archivo: los bytes exactos de los binarios,
+/// el texto tal cual y los valores estructurados como JSON
+fn contents(entry: &Entry) -> Result<Vec<u8>> {
+    if entry.is_binary() {
+        return entry.bytes();
+    }
+    Ok(secrets::format_value(&entry.value, Format::Raw)?.into_bytes())
+}
+
+fn child_path(parent: &str, name: &OsStr) -> Reply<String> {
+    let name = name.to_str().ok_or(Errno::EINVAL)?;
+    Ok(if parent.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}{}{}", parent, tree::SEPARATOR, name)
+    })
+}
+
+/// Registra el error en el log y lo convierte en `EIO` para el kernel
+fn io_error(e: anyhow::Error) -> Errno {
+    warn!("{:#}", e);
+    Errno::EIO
+}
+
+/// Estado del sistema de archivos sobre un almacén
+pub struct Filesystem {
+    store: Store,
+    writable: bool,
+    uid: u32,
+    gid: u32,
+    /// Documento desencriptado y fecha de modificación del archivo del que
+    /// se leyó, para volver a leerlo si cambia
+    doc: Option<(Option<SystemTime>, Value)>,
+    /// Ruta de cada inodo (el inodo `n` está en la posición `n - 1`)
+    paths: Vec<String>,
+    inodes: HashMap<String, u64>,
+    handles: HashMap<u64, Handle>,
+    next_handle: u64,
+}
+
+impl Filesystem {
+    pub fn new(store: Store, writable: bool) -> Self {
+        // SAFETY: getuid y getgid no fallan ni tienen precondiciones
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        Self {
+            store,
+            writable,
+            uid,
+            gid,
+            doc: None,
+            paths: vec![String::new()],
+            inodes: HashMap::from([(String::new(), ROOT_INODE)]),
+            handles: HashMap::new(),
+            next_handle: 1,
+        }
+    }
+
+    fn store_modified(&self) -> Option<SystemTime> {
+        std::fs::metadata(self.store.file())
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    /// Documento desencriptado; solo se vuelve a desencriptar si el archivo
+    /// del almacén ha cambiado
+    fn doc(&mut self) -> Result<Value> {
+        let modified = self.store_modified();
+        match &self.doc {
+            Some((when, doc)) if when.is_some() && *when == modified => Ok(doc.clone()),
+            _ => {
+                debug!("Desencriptando {}", self.store.file().display());
+                let doc = if self.store.exists() {
+                    self.store.load()?
+                } else {
+                    Value::Mapping(Mapping::new())
+                };
+                self.doc = Some((modified, doc.clone()));
+                Ok(doc)
+            }
+        }
+    }
+
+    fn resolve(&mut self, path: &str) -> Result<Option<Node>> {
+        if path.is_empty() {
+            return Ok(Some(Node::Dir));
+        }
+        let doc = self.doc()?;
+        match tree::get(&doc, path) {
+            Some(node) if tree::is_leaf(node) => {
+                let entry = Entry::from_node(node)?;
+                let updated = entry.meta.updated.map(SystemTime::from);
+                Ok(Some(Node::File(contents(&entry)?, updated)))
+            }
+            _ if !tree::children(&doc, path).is_empty() => Ok(Some(Node::Dir)),
+            _ => Ok(None),
+        }
+    }
+
+    fn inode(&mut self, path: &str) -> u64 {
+        if let Some(ino) = self.inodes.get(path) {
+            return *ino;
+        }
+        self.paths.push(path.to_string());
+        let ino = self.paths.len() as u64;
+        self.inodes.insert(path.to_string(), ino);
+        ino
+    }
+
+    fn path(&self, ino: u64) -> Reply<String> {
+        ino.checked_sub(1)
+            .and_then(|index| self.paths.get(index as usize))
+            .cloned()
+            .ok_or(Errno::ENOENT)
+    }
+
+    fn attr(&self, ino: u64, node: &Node) -> FileAttr {
+        let (size, kind, perm, nlink, modified) = match node {
+            Node::Dir => (
+                0,
+                FileType::Directory,
+                if self.writable { 0o700 } else { 0o500 },
+                2,
+                None,
+            ),
+            Node::File(data, updated) => (
+                data.len() as u64,
+                FileType::RegularFile,
+                if self.writable { 0o600 } else { 0o400 },
+                1,
+                *updated,
+            ),
+        };
+        let modified = modified
+            .or_else(|| self.doc.as_ref().and_then(|(when, _)| *when))
+            .unwrap_or(UNIX_EPOCH);
+        FileAttr {
+            ino: INodeNo(ino),
+            size,
+            blocks: size.div_ceil(512),
+            atime: modified,
+            mtime: modified,
+            ctime: modified,
+            crtime: modified,
+            kind,
+            perm,
+            nlink,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        }
+    }
+
+    fn check_writable(&self) -> Reply<()> {
+        if self.writable {
+            Ok(())
+        } else {
+            Err(Errno::EROFS)
+        }
+    }
+
+    /// Guarda el contenido de un archivo en el almacén. Se parte siempre del
+    /// documento actual para no perder cambios hechos desde fuera.
+    fn save(&mut self, path: &str, data: &[u8]) -> Result<()> {
+        let mut doc = self.store.load_or_default()?;
+        let previous = tree::get(&doc, path)
+            .filter(|node| tree::is_leaf(node))
+            .map(Entry::from_node)
+            .transpose()?;
+        let structured = previous
+            .as_ref()
+            .is_some_and(|e| e.value.is_mapping() || e.value.is_sequence());
+        let entry = match std::str::from_utf8(data) {
+            Ok(text) if structured => Entry::updated(
+                previous,
+                serde_json::from_str(text)
+                    .with_context(|| format!("'{}' es un valor estructurado y no es JSON", path))?,
+            ),
+            Ok(text) => Entry::updated(previous, Value::String(text.to_string())),
+            Err(_) => Entry::updated_binary(previous, data),
+        };
+        info!("Guardando '{}' desde el montaje", path);
+        tree::set(&mut doc, path, entry.to_node()?)?;
+        self.store.save(&doc)?;
+        self.doc = None;
+        Ok(())
+    }
+
+    fn flush_handle(&mut self, fh: u64) -> Result<()> {
+        let Some(handle) = self.handles.get(&fh).filter(|h| h.dirty) else {
+            return Ok(());
+        };
+        let (path, data) = (handle.path.clone(), handle.data.clone());
+        self.save(&path, &data)?;
+        if let Some(handle) = self.handles.get_mut(&fh) {
+            handle.dirty = false;
+        }
+        Ok(())
+    }
+
+    fn lookup(&mut self, parent: u64, name: &OsStr) -> Reply<FileAttr> {
+        let path = child_path(&self.path(parent)?, name)?;
+        let node = self
+            .resolve(&path)
+            .map_err(io_error)?
+            .ok_or(Errno::ENOENT)?;
+        let ino = self.inode(&path);
+        Ok(self.attr(ino, &node))
+    }
+
+    fn getattr(&mut self, ino: u64) -> Reply<FileAttr> {
+        let path = self.path(ino)?;
+        let mut node = self
+            .resolve(&path)
+            .map_err(io_error)?
+            .ok_or(Errno::ENOENT)?;
+        // Un archivo abierto con cambios sin guardar tiene el tamaño de su contenido
+        if let (Node::File(data, _), Some(handle)) =
+            (&mut node, self.handles.values().find(|h| h.path == path))
+        {
+            data.clone_from(&handle.data);
+        }
+        Ok(self.attr(ino, &node))
+    }
+
+    fn setattr(&mut self, ino: u64, size: Option<u64>, fh: Option<u64>) -> Reply<FileAttr> {
+        if let Some(size) = size {
+            self.check_writable()?;
+            if size > MAX_FILE_LEN {
+                return Err(Errno::EFBIG);
+            }
+            let path = self.path(ino)?;
+            let handle = match fh {
+                Some(fh) => self.handles.get_mut(&fh),
+                None => self.handles.values_mut().find(|h| h.path == path),
+            };
+            match handle {
+                Some(handle) => {
+                    handle.data.resize(size as usize, 0);
+                    handle.dirty = true;
+                }
+                None => match self.resolve(&path).map_err(io_error)? {
+                    Some(Node::File(mut data, _)) => {
+                        data.resize(size as usize, 0);
+                        self.save(&path, &data).map_err(io_error)?;
+                    }
+                    Some(Node::Dir) => return Err(Errno::EISDIR),
+                    None => return Err(Errno::ENOENT),
+                },
+            }
+        }
+        // Permisos, propietario y fechas no se guardan
+        self.getattr(ino)
+    }
+
+    fn open(&mut self, ino: u64, flags: i32) -> Reply<u64> {
+        let path = self.path(ino)?;
+        let Some(Node::File(data, _)) = self.resolve(&path).map_err(io_error)? else {
+            return Err(Errno::EISDIR);
+        };
+        let write = flags & libc::O_ACCMODE != libc::O_RDONLY;
+        if write {
+            self.check_writable()?;
+        }
+        let truncate = write && flags & libc::O_TRUNC != 0;
+        let fh = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(
+            fh,
+            Handle {
+                path,
+                data: if truncate { Vec::new() } else { data },
+                dirty: truncate,
+            },
+        );
+        Ok(fh)
+    }
+
+    fn read(&mut self, fh: u64, offset: u64, size: u32) -> Reply<Vec<u8>> {
+        let handle = self.handles.get(&fh).ok_or(Errno::EBADF)?;
+        let start = (offset as usize).min(handle.data.len());
+        let end = (offset as usize)
+            .saturating_add(size as usize)
+            .min(handle.data.len());
+        Ok(handle.data[start..end].to_vec())
+    }
+
+    fn write(&mut self, fh: u64, offset: u64, data: &[u8]) -> Reply<u32> {
+        self.check_writable()?;
+        let end = offset.saturating_add(data.len() as u64);
+        if end > MAX_FILE_LEN {
+            return Err(Errno::EFBIG);
+        }
+        let handle = self.handles.get_mut(&fh).ok_or(Errno::EBADF)?;
+        if handle.data.len() < end as usize {
+            handle.data.resize(end as usize, 0);
+        }
+        handle.data[offset as usize..end as usize].copy_from_slice(data);
+        handle.dirty = true;
+        Ok(data.len() as u32)
+    }
+
+    /// `close` envía FLUSH: se guarda aquí para que el error llegue al
+    /// programa (por ejemplo, JSON no válido en un valor estructurado)
+    fn flush(&mut self, fh: u64) -> Reply<()> {
+        self.flush_handle(fh).map_err(io_error)
+    }
+
+    fn release(&mut self, fh: u64) {
+        if let Err(e) = self.flush_handle(fh) {
+            warn!("Se descartan los cambios sin guardar: {:#}", e);
+        }
+        self.handles.remove(&fh);
+    }
+
+    fn create(&mut self, parent: u64, name: &OsStr) -> Reply<(FileAttr, u64)> {
+        let path = child_path(&self.path(parent)?, name)?;
+        self.check_writable()?;
+        let data = match self.resolve(&path).map_err(io_error)? {
+            Some(Node::File(data, _)) => data,
+            Some(Node::Dir) => return Err(Errno::EISDIR),
+            None => {
+                self.save(&path, b"").map_err(io_error)?;
+                Vec::new()
+            }
+        };
+        let ino = self.inode(&path);
+        let attr = self.attr(ino, &Node::File(data.clone(), None));
+        let fh = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(
+            fh,
+            Handle {
+                path,
+                data,
+                dirty: false,
+            },
+        );
+        Ok((attr, fh))
+    }
+
+    fn unlink(&mut self, parent: u64, name: &OsStr) -> Reply<()> {
+        let path = child_path(&self.path(parent)?, name)?;
+        self.check_writable()?;
+        match self.resolve(&path).map_err(io_error)? {
+            Some(Node::File(..)) => {}
+            Some(Node::Dir) => return Err(Errno::EISDIR),
+            None => return Err(Errno::ENOENT),
+        }
+        let mut doc = self.store.load().map_err(io_error)?;
+        tree::remove(&mut doc, &path, false).map_err(io_error)?;
+        info!("Eliminando '{}' desde el montaje", path);
+        self.store.save(&doc).map_err(io_error)?;
+        self.doc = None;
+        Ok(())
+    }
+
+    /// Los grupos desaparecen con su último secreto: `rmdir` solo tiene
+    /// éxito si el grupo ya no existe
+    fn rmdir(&mut self, parent: u64, name: &OsStr) -> Reply<()> {
+        let path = child_path(&self.path(parent)?, name)?;
+        self.check_writable()?;
+        match self.resolve(&path).map_err(io_error)? {
+            None => Ok(()),
+            Some(Node::Dir) => Err(Errno::ENOTEMPTY),
+            Some(Node::File(..)) => Err(Errno::ENOTDIR),
+        }
+    }
+
+    fn opendir(&mut self, ino: u64) -> Reply<()> {
+        let path = self.path(ino)?;
+        match self.resolve(&path).map_err(io_error)? {
+            Some(Node::Dir) => Ok(()),
+            Some(Node::File(..)) => Err(Errno::ENOTDIR),
+            None => Err(Errno::ENOENT),
+        }
+    }
+
+    /// Entradas de un directorio, `.` y `..` incluidas
+    fn readdir(&mut self, ino: u64) -> Reply<Vec<(u64, String, FileType)>> {
+        let path = self.path(ino)?;
+        let doc = self.doc().map_err(io_error)?;
+
+        let parent = match path.rsplit_once(tree::SEPARATOR) {
+            Some((parent, _)) => self.inode(parent),
+            None => ROOT_INODE,
+        };
+        let mut entries = vec![
+            (ino, ".".to_string(), FileType::Directory),
+            (parent, "..".to_string(), FileType::Directory),
+        ];
+        for child in tree::children(&doc, &path) {
+            let (name, kind) = match child.strip_suffix(tree::SEPARATOR) {
+                Some(group) => (group.to_string(), FileType::Directory),
+                None => (child, FileType::RegularFile),
+            };
+            let child_ino = self.inode(&child_path(&path, OsStr::new(&name))?);
+            entries.push((child_ino, name, kind));
+        }
+        Ok(entries)
+    }
+}
+
+/// Adaptador para `fuser`, que atiende las operaciones con `&self`: el
+/// estado va detrás de un mutex y las peticiones se atienden de una en una
+struct Mounted(Mutex<Filesystem>);
+
+impl Mounted {
+    fn fs(&self) -> MutexGuard<'_, Filesystem> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl fuser::Filesystem for Mounted {
+    /// Con `O_TRUNC` atómico, `open` llega con la opción en lugar de
+    /// un `setattr` aparte
+    fn init(&mut self, _req: &Request, config: &mut KernelConfig) -> std::io::Result<()> {
+        if let Err(unsupported) = config.add_capabilities(InitFlags::FUSE_ATOMIC_O_TRUNC) {
+            debug!("El kernel no admite {:?}", unsupported);
+        }
+        Ok(())
+    }
+
+    fn lookup(&self, _req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEntry) {
+        match self.fs().lookup(parent.0, name) {
+            Ok(attr) => reply.entry(&TTL, &attr, Generation(0)),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn getattr(&self, _req: &Request, ino: INodeNo, _fh: Option<FileHandle>, reply: ReplyAttr) {
+        match self.fs().getattr(ino.0) {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn setattr(
+        &self,
+        _req: &Request,
+        ino: INodeNo,
+        _mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        _atime: Option<TimeOrNow>,
+        _mtime: Option<TimeOrNow>,
+        _ctime: Option<SystemTime>,
+        fh: Option<FileHandle>,
+        _crtime: Option<SystemTime>,
+        _chgtime: Option<SystemTime>,
+        _bkuptime: Option<SystemTime>,
+        _flags: Option<fuser::BsdFileFlags>,
+        reply: ReplyAttr,
+    ) {
+        match self.fs().setattr(ino.0, size, fh.map(|fh| fh.0)) {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn open(&self, _req: &Request, ino: INodeNo, flags: OpenFlags, reply: ReplyOpen) {
+        match self.fs().open(ino.0, flags.0) {
+            Ok(fh) => reply.opened(FileHandle(fh), FopenFlags::FOPEN_DIRECT_IO),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn read(
+        &self,
+        _req: &Request,
+        _ino: INodeNo,
+        fh: FileHandle,
+        offset: u64,
+        size: u32,
+        _flags: OpenFlags,
+        _lock_owner: Option<LockOwner>,
+        reply: ReplyData,
+    ) {
+        match self.fs().read(fh.0, offset, size) {
+            Ok(data) => reply.data(&data),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn write(
+        &self,
+        _req: &Request,
+        _ino: INodeNo,
+        fh: FileHandle,
+        offset: u64,
+        data: &[u8],
+        _write_flags: WriteFlags,
+        _flags: OpenFlags,
+        _lock_owner: Option<LockOwner>,
+        reply: ReplyWrite,
+    ) {
+        match self.fs().write(fh.0, offset, data) {
+            Ok(written) => reply.written(written),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn flush(
+        &self,
+        _req: &Request,
+        _ino: INodeNo,
+        fh: FileHandle,
+        _lock_owner: LockOwner,
+        reply: ReplyEmpty,
+    ) {
+        match self.fs().flush(fh.0) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn fsync(
+        &self,
+        _req: &Request,
+        _ino: INodeNo,
+        fh: FileHandle,
+        _datasync: bool,
+        reply: ReplyEmpty,
+    ) {
+        match self.fs().flush(fh.0) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn release(
+        &self,
+        _req: &Request,
+        _ino: INodeNo,
+        fh: FileHandle,
+        _flags: OpenFlags,
+        _lock_owner: Option<LockOwner>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.fs().release(fh.0);
+        reply.ok();
+    }
+
+    fn create(
+        &self,
+        _req: &Request,
+        parent: INodeNo,
+        name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        _flags: i32,
+        reply: ReplyCreate,
+    ) {
+        match self.fs().create(parent.0, name) {
+            Ok((attr, fh)) => reply.created(
+                &TTL,
+                &attr,
+                Generation(0),
+                FileHandle(fh),
+                FopenFlags::FOPEN_DIRECT_IO,
+            ),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn unlink(&self, _req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEmpty) {
+        match self.fs().unlink(parent.0, name) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn rmdir(&self, _req: &Request, parent: INodeNo, name: &OsStr, reply: ReplyEmpty) {
+        match self.fs().rmdir(parent.0, name) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn opendir(&self, _req: &Request, ino: INodeNo, _flags: OpenFlags, reply: ReplyOpen) {
+        match self.fs().opendir(ino.0) {
+            Ok(()) => reply.opened(FileHandle(0), FopenFlags::empty()),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn readdir(
+        &self,
+        _req: &Request,
+        ino: INodeNo,
+        _fh: FileHandle,
+        offset: u64,
+        mut reply: ReplyDirectory,
+    ) {
+        let entries = match self.fs().readdir(ino.0) {
+            Ok(entries) => entries,
+            Err(e) => return reply.error(e),
+        };
+        // El desplazamiento de cada entrada es el de la siguiente
+        for (index, (ino, name, kind)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(INodeNo(ino), index as u64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn statfs(&self, _req: &Request, _ino: INodeNo, reply: ReplyStatfs) {
+        reply.statfs(0, 0, 0, 0, 0, 4096, 255, 4096);
+    }
+
+    /// Los permisos los comprueba el kernel (`default_permissions`)
+    fn access(&self, _req: &Request, _ino: INodeNo, _mask: fuser::AccessFlags, reply: ReplyEmpty) {
+        reply.ok();
+    }
+}
+
+/// Desmonta al recibir SIGINT, SIGTERM o SIGHUP; al desmontar termina la
+/// sesión y el proceso sale con normalidad
+fn unmount_on_signal(mut unmounter: SessionUnmounter) -> Result<()> {
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+
+    let mut signals = signal_hook::iterator::Signals::new([SIGINT, SIGTERM, SIGHUP])
+        .context("No se pudieron capturar las señales")?;
+    std::thread::spawn(move || {
+        if signals.forever().next().is_some() {
+            info!("Desmontando");
+            if let Err(e) = unmounter.unmount() {
+                eprintln!("⚠️  No se pudo desmontar: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
+
+/// Monta el almacén en `dir` (que se crea si no existe) y atiende el
+/// sistema de archivos hasta que se desmonta
+pub fn mount(store: Store, dir: &Path, writable: bool) -> Result<()> {
+    if !dir.exists() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("No se pudo crear {}", dir.display()))?;
+    }
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("No se pudo resolver {}", dir.display()))?;
+    if !dir.is_dir() {
+        anyhow::bail!("{} no es un directorio", dir.display());
+    }
+
+    let mut config = Config::default();
+    config.mount_options = vec![
+        MountOption::FSName("crypta".to_string()),
+        MountOption::Subtype("crypta".to_string()),
+        MountOption::NoSuid,
+        MountOption::NoDev,
+        MountOption::DefaultPermissions,
+        if writable {
+            MountOption::RW
+        } else {
+            MountOption::RO
+        },
+    ];
+    let filesystem = Mounted(Mutex::new(Filesystem::new(store, writable)));
+    let mut session = fuser::Session::new(filesystem, &dir, &config).with_context(|| {
+        format!(
+            "No se pudo montar {} (¿está instalado fuse3?)",
+            dir.display()
+        )
+    })?;
+
+    println!(
+        "📂 Secretos montados en {} ({})",
+        dir.display(),
+        if writable {
+            "lectura y escritura"
+        } else {
+            "solo lectura"
+        }
+    );
+    println!(
+        "   Desmonta con Ctrl+C o `fusermount3 -u {}`",
+        dir.display()
+    );
+    unmount_on_signal(session.unmount_callable())?;
+    session
+        .run()
+        .with_context(|| format!("Error en el montaje de {}", dir.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::memory_store;
+    use tempfile::TempDir;
+
+    fn filesystem(yaml: &str, writable: bool) -> (TempDir, Filesystem) {
+        let (temp_dir, store) = memory_store(yaml);
+        (temp_dir, Filesystem::new(store, writable))
+    }
+
+    #[test]
+    fn test_resolve_files_and_groups() {
+        let (_temp_dir, mut fs) = filesystem(
+            "API_KEY: abc123\nprod:\n  db:\n    password: s3cret\nlegacy/token: t\nconfig:\n  _value:\n    port: 5432\n  _meta: {}\n",
+            false,
+        );
+        assert_eq!(
+            fs.resolve("API_KEY").unwrap(),
+            Some(Node::File(b"abc123".to_vec(), None))
+        );
+        assert_eq!(fs.resolve("prod").unwrap(), Some(Node::Dir));
+        assert_eq!(fs.resolve("prod/db").unwrap(), Some(Node::Dir));
+        assert_eq!(fs.resolve("legacy").unwrap(), Some(Node::Dir));
+        assert!(matches!(
+            fs.resolve("legacy/token").unwrap(),
+            Some(Node::File(..))
+        ));
+        assert_eq!(fs.resolve("nada").unwrap(), None);
+        let Some(Node::File(config, _)) = fs.resolve("config").unwrap() else {
+            panic!("config debería ser un archivo");
+        };
+        let config: serde_json::Value = serde_json::from_slice(&config).unwrap();
+        assert_eq!(config["port"], 5432);
+    }
+
+    #[test]
+    fn test_lookup_and_readdir() {
+        let (_temp_dir, mut fs) = filesystem("API_KEY: a\nprod:\n  db:\n    password: c\n", false);
+        let prod = fs.lookup(ROOT_INODE, OsStr::new("prod")).unwrap();
+        assert_eq!(prod.kind, FileType::Directory);
+        assert_eq!(fs.path(prod.ino.0).unwrap(), "prod");
+        assert_eq!(
+            fs.lookup(ROOT_INODE, OsStr::new("x")).unwrap_err(),
+            Errno::ENOENT
+        );
+
+        let entries = fs.readdir(prod.ino.0).unwrap();
+        let names: Vec<_> = entries.iter().map(|(_, name, _)| name.as_str()).collect();
+        assert_eq!(names, [".", "..", "db"]);
+        assert_eq!(entries[1].0, ROOT_INODE);
+        assert_eq!(entries[2].2, FileType::Directory);
+        assert_eq!(fs.path(entries[2].0).unwrap(), "prod/db");
+    }
+
+    #[test]
+    fn test_read_only_refuses_writes() {
+        let (_temp_dir, mut fs) = filesystem("API_KEY: abc123\n", false);
+        let key = fs.inode("API_KEY");
+        assert_eq!(fs.open(key, libc::O_WRONLY).unwrap_err(), Errno::EROFS);
+        assert_eq!(
+            fs.create(ROOT_INODE, OsStr::new("NEW")).unwrap_err(),
+            Errno::EROFS
+        );
+        let fh = fs.open(key, libc::O_RDONLY).unwrap();
+        assert_eq!(fs.read(fh, 3, 100).unwrap(), b"123");
+        assert_eq!(fs.getattr(key).unwrap().perm, 0o400);
+    }
+
+    #[test]
+    fn test_write_saves_on_flush() {
+        let (_temp_dir, mut fs) = filesystem(
+            "API_KEY: abc123\nconfig:\n  _value:\n    port: 5432\n  _meta: {}\n",
+            true,
+        );
+        let write = |fs: &mut Filesystem, key: &str, data: &[u8]| {
+            let ino = fs.inode(key);
+            let fh = fs.open(ino, libc::O_WRONLY | libc::O_TRUNC).unwrap();
+            assert_eq!(fs.write(fh, 0, data).unwrap(), data.len() as u32);
+            let result = fs.flush(fh);
+            fs.release(fh);
+            result
+        };
+
+        assert_eq!(write(&mut fs, "API_KEY", b"n3w"), Ok(()));
+        let doc = fs.store.load().unwrap();
+        assert_eq!(secrets::text(&doc, "API_KEY").unwrap(), "n3w");
+
+        // Los valores estructurados tienen que seguir siendo JSON
+        assert_eq!(write(&mut fs, "config", b"no es json"), Err(Errno::EIO));
+        assert_eq!(write(&mut fs, "config", br#"{"port": 6543}"#), Ok(()));
+        let doc = fs.store.load().unwrap();
+        let config = secrets::find(&doc, "config").unwrap();
+        assert_eq!(config.value["port"], Value::from(6543));
+
+        // Lo que no es UTF-8 se guarda como binario
+        assert_eq!(write(&mut fs, "API_KEY", &[0xff, 0x00]), Ok(()));
+        let doc = fs.store.load().unwrap();
+        let entry = secrets::find(&doc, "API_KEY").unwrap();
+        assert!(entry.is_binary());
+        assert_eq!(entry.bytes().unwrap(), vec![0xff, 0x00]);
+    }
+}
@@ -1,10 +1,15 @@
+use crate::config::GitConfig;
+use crate::error::Error;
 use anyhow::{anyhow, Context, Result};
-use git2::{IndexAddOption, PushOptions, RemoteCallbacks, Repository, Signature};
+use git2::{
+    ErrorClass, ErrorCode, IndexAddOption, PushOptions, RemoteCallbacks, Repository, Signature,
+};
+use std::path::Path;
 use tracing::{debug, info};
 
-pub fn sync(secrets_dir: &str, message: Option<&str>) -> Result<()> {
+pub fn sync(secrets_dir: &Path, message: Option<&str>, git: &GitConfig) -> Result<()> {
     info!("Iniciando sincronización Git");
-    debug!("Directorio: {}", secrets_dir);
+    debug!("Directorio: {}", secrets_dir.display());
     println!("🔄 Sincronizando con el remoto...");
 
     let repo = Repository::open(secrets_dir).context("No se pudo abrir el repositorio git")?;
@@ -38,11 +43,11 @@ pub fn sync(secrets_dir: &str, message: Option<&str>) -> Result<()> {
 
     // Pull con rebase
     info!("Ejecutando pull con rebase");
-    pull_rebase(&repo).context("Error durante pull/rebase. Verifica que tu clave SSH esté disponible y que el agente SSH esté corriendo (comprueba `SSH_AUTH_SOCK`). Si usas un puerto no estándar, asegúrate de que la URL remota y el host sean resolvibles.")?;
+    pull_rebase(&repo, git).context("Error durante pull/rebase. Verifica que tu clave SSH esté disponible y que el agente SSH esté corriendo (comprueba `SSH_AUTH_SOCK`). Si usas un puerto no estándar, asegúrate de que la URL remota y el host sean resolvibles.")?;
 
     // Push
     info!("Realizando push al remoto");
-    push(&repo).context("Error al hacer push. Si ves errores de autenticación, añade tu clave al agente (`ssh-add`) o usa la URL HTTPS del remoto. Comprueba también que `SSH_AUTH_SOCK` esté exportada en el entorno donde ejecutas `crypta`.")?;
+    push(&repo, git).context("Error al hacer push. Si ves errores de autenticación, añade tu clave al agente (`ssh-add`) o usa la URL HTTPS del remoto. Comprueba también que `SSH_AUTH_SOCK` esté exportada en el entorno donde ejecutas `crypta`.")?;
 
     println!("🚀 Sincronización completada.");
     info!("Sincronización completada exitosamente");
@@ -50,7 +55,7 @@ pub fn sync(secrets_dir: &str, message: Option<&str>) -> Result<()> {
     Ok(())
 }
 
-fn pull_rebase(repo: &Repository) -> Result<()> {
+fn pull_rebase(repo: &Repository, git: &GitConfig) -> Result<()> {
     debug!("Iniciando pull con rebase");
 
     // Opt-in: if CRYPTA_USE_SYSTEM_GIT is set to 1 or true, prefer system git
@@ -64,7 +69,14 @@ fn pull_rebase(repo: &Repository) -> Result<()> {
         let workdir_str = workdir.to_str().unwrap_or(".");
         use std::process::Command;
         let status = Command::new("git")
-            .args(["-C", workdir_str, "pull", "--rebase", "origin", "main"])
+            .args([
+                "-C",
+                workdir_str,
+                "pull",
+                "--rebase",
+                &git.remote,
+                &git.branch,
+            ])
             .status()
             .context("Failed to execute system git for pull --rebase")?;
 
@@ -76,8 +88,8 @@ fn pull_rebase(repo: &Repository) -> Result<()> {
         }
     }
 
-    // Fetch desde origin con callbacks SSH
-    let mut remote = repo.find_remote("origin")?;
+    // Fetch desde el remoto con callbacks SSH
+    let mut remote = repo.find_remote(&git.remote)?;
     let mut callbacks = RemoteCallbacks::new();
 
     // Configurar autenticación SSH
@@ -108,7 +120,7 @@ fn pull_rebase(repo: &Repository) -> Result<()> {
 
     debug!("Fetching desde origin");
     let fetch_rebase_res: Result<(), git2::Error> = (|| {
-        remote.fetch(&["main"], Some(&mut fetch_options), None)?;
+        remote.fetch(&[&git.branch], Some(&mut fetch_options), None)?;
 
         // Obtener referencias
         let fetch_head = repo.find_reference("FETCH_HEAD")?;
@@ -143,7 +155,14 @@ fn pull_rebase(repo: &Repository) -> Result<()> {
 
         use std::process::Command;
         match Command::new("git")
-            .args(["-C", workdir_str, "pull", "--rebase", "origin", "main"])
+            .args([
+                "-C",
+                workdir_str,
+                "pull",
+                "--rebase",
+                &git.remote,
+                &git.branch,
+            ])
             .status()
         {
             Ok(s) if s.success() => {
@@ -151,10 +170,12 @@ fn pull_rebase(repo: &Repository) -> Result<()> {
                 info!("Rebase completado exitosamente (fallback)");
             }
             Ok(s) => {
-                return Err(anyhow!("Fallback git pull failed with exit code: {}", s));
+                return Err(classify_git_error(e)
+                    .context(format!("Fallback git pull failed with exit code: {}", s)));
             }
             Err(err) => {
-                return Err(anyhow!("Failed to execute fallback git: {}", err));
+                return Err(classify_git_error(e)
+                    .context(format!("Failed to execute fallback git: {}", err)));
             }
         }
     } else {
@@ -167,22 +188,26 @@ fn pull_rebase(repo: &Repository) -> Result<()> {
 
     // Rebase
     debug!("Ejecutando rebase");
-    let mut rebase = repo.rebase(None, Some(&fetch_commit), None, None)?;
+    let mut rebase = repo
+        .rebase(None, Some(&fetch_commit), None, None)
+        .map_err(classify_git_error)?;
 
     let mut ops = 0;
     while let Some(_op) = rebase.next() {
         ops += 1;
-        rebase.commit(None, &Signature::now("crypta", "crypta@local")?, None)?;
+        rebase
+            .commit(None, &Signature::now("crypta", "crypta@local")?, None)
+            .map_err(classify_git_error)?;
     }
 
     debug!("Aplicadas {} operaciones de rebase", ops);
-    rebase.finish(None)?;
+    rebase.finish(None).map_err(classify_git_error)?;
     info!("Rebase completado exitosamente");
 
     Ok(())
 }
 
-fn push(repo: &Repository) -> Result<()> {
+fn push(repo: &Repository, git: &GitConfig) -> Result<()> {
     // Opt-in: if CRYPTA_USE_SYSTEM_GIT is set to 1 or true, prefer system git for push
     if std::env::var("CRYPTA_USE_SYSTEM_GIT")
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
@@ -197,7 +222,7 @@ fn push(repo: &Repository) -> Result<()> {
         );
         use std::process::Command;
         let status = Command::new("git")
-            .args(["-C", workdir_str, "push", "origin", "main"])
+            .args(["-C", workdir_str, "push", &git.remote, &git.branch])
             .status()
             .context("Failed to execute system git for push")?;
 
@@ -208,7 +233,7 @@ fn push(repo: &Repository) -> Result<()> {
             return Err(anyhow!("System git push failed with exit code: {}", status));
         }
     }
-    let mut remote = repo.find_remote("origin")?;
+    let mut remote = repo.find_remote(&git.remote)?;
     let mut callbacks = RemoteCallbacks::new();
 
     // Configurar autenticación SSH
@@ -237,10 +262,8 @@ fn push(repo: &Repository) -> Result<()> {
     let mut push_options = PushOptions::new();
     push_options.remote_callbacks(callbacks);
 
-    let result = remote.push(
-        &["refs/heads/main:refs/heads/main"],
-        Some(&mut push_options),
-    );
+    let refspec = format!("refs/heads/{0}:refs/heads/{0}", git.branch);
+    let result = remote.push(&[&refspec], Some(&mut push_options));
 
     if let Err(e) = result {
         debug!("Libgit2 push failed: {}", e);
@@ -254,7 +277,7 @@ fn push(repo: &Repository) -> Result<()> {
 
         use std::process::Command;
         match Command::new("git")
-            .args(["-C", workdir_str, "push", "origin", "main"])
+            .args(["-C", workdir_str, "push", &git.remote, &git.branch])
             .status()
         {
             Ok(s) if s.success() => {
@@ -262,10 +285,12 @@ fn push(repo: &Repository) -> Result<()> {
                 return Ok(());
             }
             Ok(s) => {
-                return Err(anyhow!("Fallback git push failed with exit code: {}", s));
+                return Err(classify_git_error(e)
+                    .context(format!("Fallback git push failed with exit code: {}", s)));
             }
             Err(err) => {
-                return Err(anyhow!("Failed to execute fallback git: {}", err));
+                return Err(classify_git_error(e)
+                    .context(format!("Failed to execute fallback git: {}", err)));
             }
         }
     }
@@ -273,8 +298,26 @@ fn push(repo: &Repository) -> Result<()> {
     Ok(())
 }
 
+/// Traduce los errores de libgit2 de autenticación y conflicto a [`Error`]
+fn classify_git_error(err: git2::Error) -> anyhow::Error {
+    match (err.code(), err.class()) {
+        (ErrorCode::Auth, _) | (_, ErrorClass::Ssh) => {
+            Error::GitAuth(err.message().to_string()).into()
+        }
+        (
+            ErrorCode::Conflict
+            | ErrorCode::MergeConflict
+            | ErrorCode::Unmerged
+            | ErrorCode::NotFastForward,
+            _,
+        ) => Error::Conflict(err.message().to_string()).into(),
+        _ => err.into(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
     use git2::{Repository, Signature};
     use std::fs;
     use tempfile::TempDir;
@@ -310,6 +353,24 @@ mod tests {
         assert!(!statuses.is_empty());
     }
 
+    #[test]
+    fn test_classify_git_error() {
+        let auth = git2::Error::new(ErrorCode::Auth, ErrorClass::Net, "denied");
+        assert!(matches!(
+            crate::error::find(&classify_git_error(auth)),
+            Some(Error::GitAuth(_))
+        ));
+
+        let conflict = git2::Error::new(ErrorCode::Unmerged, ErrorClass::Rebase, "conflict");
+        assert!(matches!(
+            crate::error::find(&classify_git_error(conflict)),
+            Some(Error::Conflict(_))
+        ));
+
+        let other = git2::Error::new(ErrorCode::GenericError, ErrorClass::Os, "boom");
+        assert!(crate::error::find(&classify_git_error(other)).is_none());
+    }
+
     #[test]
     fn test_git_index() {
         let temp_dir = TempDir::new().unwrap();
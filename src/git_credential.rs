@@ -0,0 +1,297 @@
+use crate::import::{self, ConflictPolicy, Record};
+use crate::secrets;
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::io::{self, Read, Write};
+use tracing::{debug, info};
+
+/// Ruta bajo la que se guardan las credenciales de git por defecto
+pub const DEFAULT_NAMESPACE: &str = "git";
+
+/// Operaciones del protocolo de `git credential`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Operation {
+    /// Devuelve el usuario y la contraseña guardados
+    Get,
+    /// Guarda la credencial que git ha usado con éxito
+    Store,
+    /// Elimina una credencial rechazada
+    Erase,
+}
+
+/// Atributos de una credencial en el protocolo de `git credential`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Credential {
+    pub protocol: Option<String>,
+    pub host: Option<String>,
+    pub path: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Credential {
+    /// Interpreta las líneas `atributo=valor` que envía git, hasta una línea
+    /// vacía. Los atributos desconocidos se ignoran; `url` se descompone en
+    /// protocolo, usuario, host y ruta.
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut credential = Self::default();
+        for line in input.lines() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                break;
+            }
+            let (name, value) = line
+                .split_once('=')
+                .with_context(|| format!("Línea no válida en la credencial: '{}'", line))?;
+            let value = Some(value.to_string()).filter(|v| !v.is_empty());
+            match name {
+                "protocol" => credential.protocol = value,
+                "host" => credential.host = value,
+                "path" => credential.path = value,
+                "username" => credential.username = value,
+                "password" => credential.password = value,
+                "url" => {
+                    if let Some(url) = value {
+                        credential.set_url(&url);
+                    }
+                }
+                other => debug!("Atributo de credencial ignorado: {}", other),
+            }
+        }
+        Ok(credential)
+    }
+
+    fn set_url(&mut self, url: &str) {
+        let (protocol, rest) = match url.split_once("://") {
+            Some((protocol, rest)) => (Some(protocol.to_string()), rest),
+            None => (None, url),
+        };
+        let (authority, path) = match rest.split_once('/') {
+            Some((authority, path)) => (authority, Some(path.to_string())),
+            None => (rest, None),
+        };
+        let (username, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user.to_string()), host),
+            None => (None, authority),
+        };
+        self.protocol = protocol.or(self.protocol.take());
+        self.host = Some(host.to_string()).filter(|h| !h.is_empty());
+        self.path = path.filter(|p| !p.is_empty()).or(self.path.take());
+        self.username = username.or(self.username.take());
+    }
+
+    /// Respuesta para git: `username=…` y `password=…`
+    pub fn to_output(&self) -> String {
+        let mut out = String::new();
+        for (name, value) in [("username", &self.username), ("password", &self.password)] {
+            if let Some(value) = value {
+                out.push_str(&format!("{}={}\n", name, value));
+            }
+        }
+        out
+    }
+
+    fn host(&self) -> Result<&str> {
+        self.host
+            .as_deref()
+            .context("git no ha indicado el host de la credencial")
+    }
+}
+
+/// Ruta con las credenciales de un host: `git/github.com`
+fn host_path(namespace: &str, host: &str) -> String {
+    let namespace = namespace.trim_matches(tree::SEPARATOR);
+    if namespace.is_empty() {
+        host.to_string()
+    } else {
+        format!("{}{}{}", namespace, tree::SEPARATOR, host)
+    }
+}
+
+/// Clave del secreto de una credencial: `git/github.com/bob`
+pub fn key(namespace: &str, host: &str, username: &str) -> String {
+    format!(
+        "{}{}{}",
+        host_path(namespace, host),
+        tree::SEPARATOR,
+        username.replace(tree::SEPARATOR, "-")
+    )
+}
+
+/// Busca la credencial guardada para el host (y el usuario, si git lo
+/// indica). Sin usuario se usa la primera credencial del host.
+pub fn find(
+    doc: &Value,
+    namespace: &str,
+    query: &Credential,
+) -> Result<Option<(String, Credential)>> {
+    let host = query.host()?;
+    let candidates: Vec<String> = match &query.username {
+        Some(username) => vec![key(namespace, host, username)],
+        None => {
+            let base = host_path(namespace, host);
+            tree::leaves(doc, &base)
+                .into_iter()
+                .filter(|k| {
+                    k.strip_prefix(&base)
+                        .and_then(|rest| rest.strip_prefix(tree::SEPARATOR))
+                        .is_some_and(|name| !name.contains(tree::SEPARATOR))
+                })
+                .collect()
+        }
+    };
+
+    for candidate in candidates {
+        let Ok(entry) = secrets::find(doc, &candidate) else {
+            continue;
+        };
+        let username = entry
+            .meta
+            .username
+            .clone()
+            .or_else(|| candidate.rsplit(tree::SEPARATOR).next().map(str::to_string));
+        let password = entry.text();
+        return Ok(Some((
+            candidate,
+            Credential {
+                username,
+                password,
+                ..query.clone()
+            },
+        )));
+    }
+    Ok(None)
+}
+
+/// Lee la credencial de stdin, atiende la operación y escribe la respuesta
+/// en stdout
+pub fn serve(store: &Store, namespace: &str, operation: Operation) -> Result<()> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    let output = run(store, namespace, operation, &input)?;
+    io::stdout().write_all(output.as_bytes())?;
+    Ok(())
+}
+
+/// Atiende una operación del protocolo con la credencial que envía git y
+/// devuelve la respuesta que hay que escribir en stdout
+pub fn run(store: &Store, namespace: &str, operation: Operation, input: &str) -> Result<String> {
+    let credential = Credential::parse(input)?;
+    match operation {
+        Operation::Get => {
+            if !store.exists() {
+                return Ok(String::new());
+            }
+            let doc = store.load()?;
+            Ok(find(&doc, namespace, &credential)?
+                .map(|(_, found)| found.to_output())
+                .unwrap_or_default())
+        }
+        Operation::Store => {
+            let (Some(username), Some(password)) = (&credential.username, &credential.password)
+            else {
+                debug!("Credencial sin usuario o contraseña; no se guarda");
+                return Ok(String::new());
+            };
+            let host = credential.host()?;
+            let key = key(namespace, host, username);
+            info!("Guardando la credencial de git '{}'", key);
+
+            let mut doc = store.load_or_default()?;
+            if secrets::text(&doc, &key).ok().as_ref() == Some(password) {
+                return Ok(String::new());
+            }
+            let mut record = Record::new(key, password.as_str());
+            record.meta.username = Some(username.clone());
+            record.meta.url = credential
+                .protocol
+                .as_ref()
+                .map(|protocol| format!("{}://{}", protocol, host));
+            import::apply(&mut doc, vec![record], ConflictPolicy::Overwrite)?;
+            store.save(&doc)?;
+            Ok(String::new())
+        }
+        Operation::Erase => {
+            if !store.exists() {
+                return Ok(String::new());
+            }
+            let mut doc = store.load()?;
+            let Some((key, found)) = find(&doc, namespace, &credential)? else {
+                return Ok(String::new());
+            };
+            // git envía la contraseña rechazada: si ya se ha cambiado, se conserva
+            if credential.password.is_some() && credential.password != found.password {
+                debug!("La contraseña de '{}' ya no es la rechazada", key);
+                return Ok(String::new());
+            }
+            info!("Eliminando la credencial de git '{}'", key);
+            tree::remove(&mut doc, &key, false)?;
+            store.save(&doc)?;
+            Ok(String::new())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_credential() {
+        let input =
+            "protocol=https\nhost=github.com\nusername=bob\nwwwauth[]=Basic\n\nhost=ignorado\n";
+        assert_eq!(
+            Credential::parse(input).unwrap(),
+            Credential {
+                protocol: Some("https".to_string()),
+                host: Some("github.com".to_string()),
+                username: Some("bob".to_string()),
+                ..Credential::default()
+            }
+        );
+        assert!(Credential::parse("host\n").is_err());
+    }
+
+    #[test]
+    fn test_parse_url() {
+        let credential =
+            Credential::parse("url=https://bob@git.example.com:8443/org/repo.git\n").unwrap();
+        assert_eq!(credential.protocol.as_deref(), Some("https"));
+        assert_eq!(credential.host.as_deref(), Some("git.example.com:8443"));
+        assert_eq!(credential.path.as_deref(), Some("org/repo.git"));
+        assert_eq!(credential.username.as_deref(), Some("bob"));
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(key("git", "github.com", "bob"), "git/github.com/bob");
+        assert_eq!(
+            key("/creds/git/", "github.com", "a/b"),
+            "creds/git/github.com/a-b"
+        );
+    }
+
+    #[test]
+    fn test_find_with_and_without_username() {
+        let doc: Value = serde_yaml::from_str(
+            "git:\n  github.com:\n    bob: t0ken\n  gitlab.com:\n    alice: gl\n",
+        )
+        .unwrap();
+        let query = |input: &str| Credential::parse(input).unwrap();
+
+        let (key, found) = find(&doc, "git", &query("host=github.com\n"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(key, "git/github.com/bob");
+        assert_eq!(found.to_output(), "username=bob\npassword=t0ken\n");
+
+        assert!(find(&doc, "git", &query("host=github.com\nusername=eve\n"))
+            .unwrap()
+            .is_none());
+        assert!(find(&doc, "git", &query("host=example.com\n"))
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,318 @@
+use crate::bitwarden;
+use crate::dotenv;
+use crate::entry::MetadataUpdate;
+use crate::keepass;
+use crate::login::{self, Columns};
+use crate::pass;
+use crate::secrets;
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Origen especial que indica el almacén de `pass`
+pub const PASS_SOURCE: &str = "pass";
+
+/// Formatos de los que se pueden importar secretos
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ImportFormat {
+    /// Archivo `.env` (`CLAVE=valor`)
+    Dotenv,
+    /// Almacén de `pass` (`~/.password-store`), desencriptado con `gpg`
+    Pass,
+    /// Base de datos de KeePass (`.kdbx`), abierta con `keepassxc-cli`
+    Keepass,
+    /// Exportación JSON sin cifrar de Bitwarden
+    Bitwarden,
+    /// CSV con cabecera (Bitwarden, 1Password, KeePassXC, LastPass…)
+    Csv,
+}
+
+/// Qué hacer cuando una clave importada ya existe
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ConflictPolicy {
+    /// Conserva el secreto existente
+    Skip,
+    /// Sustituye el valor (los metadatos existentes se conservan)
+    #[default]
+    Overwrite,
+    /// Importa con otro nombre (`clave-2`, `clave-3`…)
+    Rename,
+}
+
+/// Opciones de [`import`]
+#[derive(Debug, Default, Clone)]
+pub struct ImportOptions {
+    /// Formato del origen (por defecto se deduce del nombre)
+    pub format: Option<ImportFormat>,
+    /// Importa solo las entradas bajo esta ruta del origen (un subdirectorio
+    /// en `pass`), sin el prefijo en la clave
+    pub prefix: Option<String>,
+    /// Ruta bajo la que se guardan los secretos
+    pub into: Option<String>,
+    /// Solo muestra qué se importaría
+    pub dry_run: bool,
+    pub on_conflict: ConflictPolicy,
+    /// Asignaciones `campo=Columna` para los CSV
+    pub columns: Vec<String>,
+}
+
+impl ImportFormat {
+    /// Deduce el formato a partir del nombre del origen
+    pub fn detect(source: &Path) -> Option<Self> {
+        if source == Path::new(PASS_SOURCE) {
+            return Some(Self::Pass);
+        }
+        let name = source.file_name()?.to_str()?;
+        if name == ".env" || name.starts_with(".env.") || name.ends_with(".env") {
+            return Some(Self::Dotenv);
+        }
+        match source.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "kdbx" => Some(Self::Keepass),
+            "json" => Some(Self::Bitwarden),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// Un secreto leído del origen, con los metadatos que aporte
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub key: String,
+    pub value: Value,
+    pub meta: MetadataUpdate,
+}
+
+impl Record {
+    pub fn new(key: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            meta: MetadataUpdate::default(),
+        }
+    }
+}
+
+/// Resultado de una importación
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Summary {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub skipped: Vec<String>,
+    /// Claves existentes y el nombre con el que se han importado
+    pub renamed: Vec<(String, String)>,
+}
+
+/// Une el prefijo (`app/`) y el nombre importado en una clave con ruta
+pub fn join_key(prefix: Option<&str>, name: &str) -> String {
+    match prefix.map(|p| p.trim_matches(tree::SEPARATOR)) {
+        Some(prefix) if !prefix.is_empty() => format!("{}{}{}", prefix, tree::SEPARATOR, name),
+        _ => name.to_string(),
+    }
+}
+
+/// Añade los secretos al documento ya desencriptado, resolviendo las
+/// claves que ya existen según `policy`
+pub fn apply(doc: &mut Value, records: Vec<Record>, policy: ConflictPolicy) -> Result<Summary> {
+    let mut summary = Summary::default();
+    for mut record in records {
+        let exists = tree::get(doc, &record.key).is_some();
+        if exists {
+            match policy {
+                ConflictPolicy::Skip => {
+                    summary.skipped.push(record.key);
+                    continue;
+                }
+                ConflictPolicy::Rename => {
+                    let key = free_key(doc, &record.key);
+                    summary.renamed.push((record.key, key.clone()));
+                    record.key = key;
+                }
+                ConflictPolicy::Overwrite => {}
+            }
+        }
+
+        secrets::insert(doc, &record.key, record.value)?;
+        if !record.meta.is_empty() {
+            let mut entry = secrets::find(doc, &record.key)?;
+            record.meta.apply(&mut entry.meta)?;
+            tree::set(doc, &record.key, entry.to_node()?)?;
+        }
+        match (exists, policy) {
+            (true, ConflictPolicy::Overwrite) => summary.updated.push(record.key),
+            (true, _) => {}
+            (false, _) => summary.added.push(record.key),
+        }
+    }
+    Ok(summary)
+}
+
+/// Primera variante libre de `key`: `key-2`, `key-3`…
+fn free_key(doc: &Value, key: &str) -> String {
+    (2..)
+        .map(|n| format!("{}-{}", key, n))
+        .find(|candidate| tree::get(doc, candidate).is_none())
+        .expect("siempre hay una clave libre")
+}
+
+/// Lee los secretos de `source` según su formato, solo los que están bajo
+/// `prefix` y con la clave sin él. Con `dry_run` no se desencripta el
+/// almacén de `pass`: los valores quedan vacíos. `columns` solo se usa con
+/// los CSV.
+pub fn read(
+    format: ImportFormat,
+    source: &Path,
+    prefix: Option<&str>,
+    dry_run: bool,
+    columns: &Columns,
+) -> Result<Vec<Record>> {
+    let base = tree::segments(prefix.unwrap_or_default());
+    let logins = match format {
+        ImportFormat::Dotenv => {
+            let vars = dotenv::parse(&read_source(source)?)
+                .with_context(|| format!("Archivo .env no válido: {}", source.display()))?;
+            return Ok(under(
+                &base,
+                vars.into_iter()
+                    .map(|(name, value)| Record::new(name, value))
+                    .collect(),
+            ));
+        }
+        ImportFormat::Pass => {
+            // Solo se recorre (y desencripta) el subdirectorio del prefijo
+            let dir = base.iter().fold(pass_dir(source)?, |dir, s| dir.join(s));
+            let entries = pass::entries(&dir)?;
+            if !dry_run && !entries.is_empty() {
+                pass::check_gpg()?;
+            }
+
+            let mut records = Vec::with_capacity(entries.len());
+            for (key, path) in entries {
+                if dry_run {
+                    records.push(Record::new(key, Value::Null));
+                    continue;
+                }
+                let (value, note) = pass::split(&pass::decrypt(&path)?);
+                let mut record = Record::new(key, value);
+                record.meta.note = note;
+                records.push(record);
+            }
+            return Ok(records);
+        }
+        ImportFormat::Keepass => keepass::read(source)?,
+        ImportFormat::Bitwarden => bitwarden::parse(&read_source(source)?)
+            .with_context(|| format!("No se pudo importar {}", source.display()))?,
+        ImportFormat::Csv => login::from_csv(&read_source(source)?, columns)
+            .with_context(|| format!("No se pudo importar {}", source.display()))?,
+    };
+    Ok(under(
+        &base,
+        logins.into_iter().map(login::Login::into_record).collect(),
+    ))
+}
+
+/// Registros cuya clave está bajo la ruta `base`, con la clave sin ella
+fn under(base: &[&str], records: Vec<Record>) -> Vec<Record> {
+    if base.is_empty() {
+        return records;
+    }
+    records
+        .into_iter()
+        .filter_map(|record| {
+            let segments = tree::segments(&record.key);
+            if segments.len() <= base.len() || !segments.starts_with(base) {
+                return None;
+            }
+            let key = segments[base.len()..].join(&tree::SEPARATOR.to_string());
+            Some(Record { key, ..record })
+        })
+        .collect()
+}
+
+fn read_source(source: &Path) -> Result<String> {
+    fs::read_to_string(source).with_context(|| format!("No se pudo leer {}", source.display()))
+}
+
+/// Directorio de `pass`: el de por defecto si el origen es `pass`
+fn pass_dir(source: &Path) -> Result<PathBuf> {
+    if source == Path::new(PASS_SOURCE) {
+        pass::store_dir()
+    } else {
+        Ok(source.to_path_buf())
+    }
+}
+
+/// Importa los secretos de `source` con un único ciclo de desencriptado y
+/// encriptado: los de `options.prefix` si se indica, guardados bajo
+/// `options.into`. Con `dry_run`
+/// solo muestra qué claves se añadirían, actualizarían u omitirían.
+pub fn import(store: &Store, source: &Path, options: &ImportOptions) -> Result<()> {
+    let dry_run = options.dry_run;
+    let columns = Columns::parse(&options.columns)?;
+    let format = options
+        .format
+        .or_else(|| ImportFormat::detect(source))
+        .with_context(|| {
+            format!(
+                "No se pudo deducir el formato de {}; usa --format",
+                source.display()
+            )
+        })?;
+    info!("Importando {} como {:?}", source.display(), format);
+
+    let records: Vec<Record> = read(format, source, options.prefix.as_deref(), dry_run, &columns)?
+        .into_iter()
+        .map(|record| Record {
+            key: join_key(options.into.as_deref(), &record.key),
+            ..record
+        })
+        .collect();
+    if records.is_empty() {
+        println!("ℹ️  No hay secretos que importar en {}", source.display());
+        return Ok(());
+    }
+
+    let mut doc = store.load_or_default()?;
+    let summary = apply(&mut doc, records, options.on_conflict)?;
+    if !dry_run {
+        store.save(&doc)?;
+    }
+
+    for key in &summary.added {
+        println!("  + {}", key);
+    }
+    for key in &summary.updated {
+        println!("  ~ {}", key);
+    }
+    for (key, renamed) in &summary.renamed {
+        println!("  + {} (ya existía {})", renamed, key);
+    }
+    for key in &summary.skipped {
+        println!("  = {} (ya existe, se omite)", key);
+    }
+    let total = summary.added.len() + summary.updated.len() + summary.renamed.len();
+    let mut detail = format!(
+        "{} nuevos, {} actualizados",
+        summary.added.len() + summary.renamed.len(),
+        summary.updated.len()
+    );
+    if !summary.renamed.is_empty() {
+        detail.push_str(&format!(", {} renombrados", summary.renamed.len()));
+    }
+    if !summary.skipped.is_empty() {
+        detail.push_str(&format!(", {} omitidos", summary.skipped.len()));
+    }
+    if dry_run {
+        println!(
+            "🔍 Simulación: se importarían {} secretos ({}). No se ha modificado nada.",
+            total, detail
+        );
+    } else {
+        println!("✅ Importados {} secretos ({}).", total, detail);
+    }
+    Ok(())
+}
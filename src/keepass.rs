@@ -0,0 +1,67 @@
+use crate::error::Error;
+use crate::login::{self, Columns, Login};
+use anyhow::{Context, Result};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tracing::debug;
+
+/// Programa con el que se desencripta la base de datos
+const KEEPASSXC_CLI: &str = "keepassxc-cli";
+
+/// Lee una base de datos KDBX con `keepassxc-cli export`, que pide la
+/// contraseña maestra en la terminal. Los grupos se convierten en carpetas
+/// sin el grupo raíz.
+pub fn read(path: &Path) -> Result<Vec<Login>> {
+    debug!("Exportando {} con {}", path.display(), KEEPASSXC_CLI);
+    let output = Command::new(KEEPASSXC_CLI)
+        .args(["export", "--format", "csv"])
+        .arg(path)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output();
+    let output = match output {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(anyhow::Error::new(Error::BackendMissing(
+                KEEPASSXC_CLI.to_string(),
+            )))
+            .context("Instala KeePassXC para importar bases de datos KDBX");
+        }
+        Err(e) => return Err(e).context("No se pudo ejecutar keepassxc-cli"),
+    };
+    if !output.status.success() {
+        return Err(Error::DecryptFailed(format!(
+            "{}: keepassxc-cli no pudo abrir la base de datos",
+            path.display()
+        ))
+        .into());
+    }
+
+    let content = String::from_utf8(output.stdout)
+        .context("keepassxc-cli ha devuelto un CSV que no es UTF-8")?;
+    let mut logins = login::from_csv(&content, &Columns::default())?;
+    for login in &mut logins {
+        login.folder = login.folder.take().and_then(|f| strip_root(&f));
+    }
+    Ok(logins)
+}
+
+/// Quita el grupo raíz (`Root/Internet` → `Internet`)
+fn strip_root(group: &str) -> Option<String> {
+    group
+        .split_once('/')
+        .map(|(_, rest)| rest.to_string())
+        .filter(|rest| !rest.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_root() {
+        assert_eq!(strip_root("Root"), None);
+        assert_eq!(strip_root("Root/Internet"), Some("Internet".to_string()));
+        assert_eq!(strip_root("Base/Web/Git"), Some("Web/Git".to_string()));
+    }
+}
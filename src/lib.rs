@@ -1,2 +1,31 @@
-pub mod secrets;
+pub mod backend;
+pub mod bitwarden;
+pub mod clipboard;
+pub mod config;
+pub mod docker_credential;
+pub mod dotenv;
+pub mod entry;
+pub mod env;
+pub mod error;
+pub mod export;
+#[cfg(target_os = "linux")]
+pub mod fuse;
 pub mod git;
+pub mod git_credential;
+pub mod import;
+pub mod keepass;
+pub mod login;
+pub mod materialize;
+pub mod pass;
+pub mod secret_service;
+pub mod secrets;
+#[cfg(unix)]
+pub mod ssh_agent;
+pub mod store;
+pub mod systemd_creds;
+pub mod template;
+pub mod tree;
+pub mod vault;
+pub mod vault_compat;
+
+pub use error::Error;
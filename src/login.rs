@@ -0,0 +1,223 @@
+use crate::import::Record;
+use crate::tree;
+use anyhow::{Context, Result};
+use tracing::warn;
+
+/// Una entrada de otro gestor de contraseñas: la contraseña y los datos de
+/// la cuenta, dentro de una carpeta opcional
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Login {
+    /// Carpeta o grupo, con `/` para los niveles (`Trabajo/Cloud`)
+    pub folder: Option<String>,
+    pub name: String,
+    pub password: String,
+    pub username: Option<String>,
+    pub url: Option<String>,
+    pub notes: Option<String>,
+}
+
+impl Login {
+    /// Clave del secreto: la ruta de la carpeta seguida del nombre. Las `/`
+    /// del nombre se sustituyen por `-` para no crear niveles de más.
+    pub fn key(&self) -> String {
+        let mut segments: Vec<String> = self
+            .folder
+            .iter()
+            .flat_map(|f| f.split(tree::SEPARATOR))
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .collect();
+        segments.push(self.name.trim().replace(tree::SEPARATOR, "-"));
+        segments.join(&tree::SEPARATOR.to_string())
+    }
+
+    /// Convierte la entrada en un secreto con el usuario, la URL y las notas
+    /// como metadatos. Si no hay contraseña (notas seguras), el valor son
+    /// las notas.
+    pub fn into_record(self) -> Record {
+        let key = self.key();
+        let (value, note) = if self.password.is_empty() && self.notes.is_some() {
+            (self.notes.unwrap_or_default(), None)
+        } else {
+            (self.password, self.notes)
+        };
+        let mut record = Record::new(key, value);
+        record.meta.username = self.username;
+        record.meta.url = self.url;
+        record.meta.note = note;
+        record
+    }
+}
+
+/// Campos de [`Login`] que se pueden leer de una columna del CSV
+const FIELDS: [&str; 6] = ["key", "value", "folder", "username", "url", "notes"];
+
+/// Correspondencia entre los campos de una entrada y las columnas de un CSV.
+/// Los campos sin columna explícita se buscan por los nombres habituales de
+/// Bitwarden, 1Password, KeePassXC y LastPass.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Columns {
+    mapping: Vec<(&'static str, String)>,
+}
+
+impl Columns {
+    /// Interpreta asignaciones `campo=Columna` (`key=Title`, `value=Password`…)
+    pub fn parse<S: AsRef<str>>(specs: &[S]) -> Result<Self> {
+        let mut columns = Self::default();
+        for spec in specs {
+            let spec = spec.as_ref();
+            let (field, column) = spec
+                .split_once('=')
+                .with_context(|| format!("Columna no válida '{}': usa campo=Columna", spec))?;
+            let field = FIELDS
+                .iter()
+                .find(|f| f.eq_ignore_ascii_case(field.trim()))
+                .with_context(|| {
+                    format!(
+                        "Campo desconocido '{}': usa uno de {}",
+                        field,
+                        FIELDS.join(", ")
+                    )
+                })?;
+            columns.mapping.retain(|(f, _)| f != field);
+            columns.mapping.push((field, column.trim().to_string()));
+        }
+        Ok(columns)
+    }
+
+    fn candidates(&self, field: &str) -> Vec<&str> {
+        if let Some((_, column)) = self.mapping.iter().find(|(f, _)| *f == field) {
+            return vec![column.as_str()];
+        }
+        match field {
+            "key" => vec!["name", "title"],
+            "value" => vec!["password", "login_password", "value"],
+            "folder" => vec!["folder", "group", "grouping"],
+            "username" => vec!["username", "login_username", "login"],
+            "url" => vec!["url", "login_uri", "website"],
+            "notes" => vec!["notes", "extra", "note"],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Posición de la columna de `field` en la cabecera
+    fn position(&self, headers: &csv::StringRecord, field: &str) -> Option<usize> {
+        self.candidates(field).into_iter().find_map(|candidate| {
+            headers
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(candidate))
+        })
+    }
+}
+
+/// Lee las entradas de un CSV con cabecera. Las filas sin nombre se omiten
+/// con un aviso; las columnas vacías no generan metadatos.
+pub fn from_csv(content: &str, columns: &Columns) -> Result<Vec<Login>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers = reader
+        .headers()
+        .context("No se pudo leer la cabecera del CSV")?
+        .clone();
+
+    let required = |field: &str| {
+        columns.position(&headers, field).with_context(|| {
+            format!(
+                "El CSV no tiene columna para '{}'; indícala con --column {}=COLUMNA",
+                field, field
+            )
+        })
+    };
+    let key = required("key")?;
+    let value = required("value")?;
+    let folder = columns.position(&headers, "folder");
+    let username = columns.position(&headers, "username");
+    let url = columns.position(&headers, "url");
+    let notes = columns.position(&headers, "notes");
+
+    let mut logins = Vec::new();
+    for (i, row) in reader.records().enumerate() {
+        let row = row.with_context(|| format!("Fila {} del CSV no válida", i + 2))?;
+        let cell = |index: Option<usize>| {
+            index
+                .and_then(|i| row.get(i))
+                .map(str::trim)
+                .filter(|c| !c.is_empty())
+                .map(str::to_string)
+        };
+        let Some(name) = cell(Some(key)) else {
+            warn!("Fila {} del CSV sin nombre; se omite", i + 2);
+            continue;
+        };
+        logins.push(Login {
+            folder: cell(folder),
+            name,
+            password: row.get(value).unwrap_or_default().to_string(),
+            username: cell(username),
+            url: cell(url),
+            notes: cell(notes),
+        });
+    }
+    Ok(logins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_from_folder_and_name() {
+        let login = Login {
+            folder: Some(" Trabajo//Cloud ".to_string()),
+            name: "AWS/prod".to_string(),
+            ..Login::default()
+        };
+        assert_eq!(login.key(), "Trabajo/Cloud/AWS-prod");
+        assert_eq!(
+            Login {
+                name: "github".to_string(),
+                ..Login::default()
+            }
+            .key(),
+            "github"
+        );
+    }
+
+    #[test]
+    fn test_from_csv_default_columns() {
+        let content = "folder,favorite,type,name,notes,login_uri,login_username,login_password\n\
+                       Web,,login,GitHub,,https://github.com,bob,gh-p4ss\n\
+                       ,,note,Alarma,1234,,,\n\
+                       ,,login,,,,,sin-nombre\n";
+        let logins = from_csv(content, &Columns::default()).unwrap();
+        assert_eq!(logins.len(), 2);
+        assert_eq!(
+            logins[0],
+            Login {
+                folder: Some("Web".to_string()),
+                name: "GitHub".to_string(),
+                password: "gh-p4ss".to_string(),
+                username: Some("bob".to_string()),
+                url: Some("https://github.com".to_string()),
+                notes: None,
+            }
+        );
+        assert_eq!(logins[1].folder, None);
+        assert_eq!(logins[1].notes.as_deref(), Some("1234"));
+    }
+
+    #[test]
+    fn test_from_csv_custom_columns() {
+        let columns = Columns::parse(&["key=Servicio", "VALUE = Clave"]).unwrap();
+        let content = "Servicio,Clave,Password\nsmtp,s3cret,ignorada\n";
+        let logins = from_csv(content, &columns).unwrap();
+        assert_eq!(logins[0].name, "smtp");
+        assert_eq!(logins[0].password, "s3cret");
+
+        assert!(Columns::parse(&["titulo=Title"]).is_err());
+        assert!(Columns::parse(&["key"]).is_err());
+        assert!(from_csv("Servicio,Clave\nx,y\n", &Columns::default()).is_err());
+    }
+}
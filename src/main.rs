@@ -1,6 +1,19 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use crypta::{git, secrets};
+use crypta::config::Config;
+use crypta::entry::{parse_duration, MetadataUpdate};
+use crypta::env::{self, EnvSpec};
+use crypta::export::{self, ExportFormat, ExportOptions};
+use crypta::import::{self, ConflictPolicy, ImportFormat, ImportOptions};
+use crypta::secrets::Format;
+use crypta::store::Store;
+use crypta::vault::{self, Vault};
+use crypta::{
+    clipboard, docker_credential, git, git_credential, materialize, secret_service, secrets,
+    systemd_creds, template, vault_compat,
+};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
@@ -8,6 +21,15 @@ use tracing_subscriber::EnvFilter;
 #[command(name = "crypta")]
 #[command(about = "Gestor de secretos con SOPS y Git", long_about = None)]
 struct Cli {
+    /// Vault a usar (por defecto el vault principal)
+    #[arg(long, global = true, env = "CRYPTA_VAULT")]
+    vault: Option<String>,
+    /// Directorio raíz de los secretos (por defecto ~/.secrets)
+    #[arg(long, global = true)]
+    dir: Option<PathBuf>,
+    /// Archivo de secretos, relativo al directorio del vault (por defecto secrets.yml)
+    #[arg(long, global = true)]
+    file: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -19,6 +41,18 @@ enum Commands {
     Store {
         /// Clave del secreto (o usa variable de entorno SECRET_ID)
         key: Option<String>,
+        /// Lee el valor de un archivo y lo guarda byte a byte (base64)
+        #[arg(long, value_name = "ARCHIVO", conflicts_with_all = ["binary", "raw"])]
+        from_file: Option<PathBuf>,
+        /// Guarda stdin byte a byte (base64), para certificados o keystores
+        #[arg(long, conflicts_with = "raw")]
+        binary: bool,
+        /// No elimina los espacios ni saltos de línea del principio y el final
+        #[arg(long)]
+        raw: bool,
+        /// Interpreta stdin como JSON y lo guarda como valor estructurado
+        #[arg(long, conflicts_with_all = ["binary", "from_file"])]
+        json: bool,
     },
     /// Almacena o actualiza un secreto
     #[command(alias = "se")]
@@ -29,27 +63,260 @@ enum Commands {
         /// Valor del secreto
         #[arg(short, long)]
         value: String,
+        /// Interpreta el valor como JSON y lo guarda como valor estructurado
+        #[arg(long)]
+        json: bool,
     },
     /// Obtiene un valor y lo copia al portapapeles
     #[command(alias = "g")]
     Get {
         /// Clave del secreto (o usa variable de entorno SECRET_ID)
         key: Option<String>,
+        /// Segundos hasta borrar el portapapeles (por defecto `clipboard_timeout`
+        /// de la configuración, 45); 0 no lo borra. Con OSC 52 nunca se borra
+        #[arg(short, long, value_name = "SEGUNDOS")]
+        timeout: Option<u64>,
+        /// Dónde copiar: portapapeles, selección PRIMARY o terminal (OSC 52)
+        #[arg(long, value_enum, default_value_t)]
+        target: clipboard::Target,
     },
     /// Muestra un valor por stdout
     #[command(alias = "l")]
     Lookup {
         /// Clave del secreto (o usa variable de entorno SECRET_ID)
         key: Option<String>,
+        /// Escribe el valor en un archivo con permisos 0600
+        #[arg(short, long, value_name = "ARCHIVO")]
+        output: Option<PathBuf>,
+        /// Campo a extraer de un valor estructurado (por ejemplo `password` o `hosts.0`)
+        #[arg(long)]
+        field: Option<String>,
+        /// Formato de salida
+        #[arg(long, value_enum, default_value_t = Format::Raw)]
+        format: Format,
     },
     /// Lista todas las claves
     #[command(alias = "ls")]
-    List,
+    List {
+        /// Lista solo las claves bajo esta ruta (por ejemplo `prod/`)
+        prefix: Option<String>,
+        /// Muestra también los metadatos (fecha, propietario, etiquetas, nota)
+        #[arg(short, long)]
+        long: bool,
+    },
     /// Elimina una clave
     #[command(alias = "rm")]
     Delete {
         /// Clave del secreto (o usa variable de entorno SECRET_ID)
         key: Option<String>,
+        /// Elimina un grupo de secretos completo (por ejemplo `prod/db`)
+        #[arg(short, long)]
+        recursive: bool,
+    },
+    /// Ejecuta un comando con secretos como variables de entorno
+    #[command(alias = "x")]
+    Exec {
+        /// Secreto a inyectar: CLAVE o VAR=CLAVE (se puede repetir)
+        #[arg(short, long = "env", value_name = "[VAR=]CLAVE")]
+        env: Vec<String>,
+        /// Inyecta todos los secretos bajo esta ruta (por ejemplo `prod/`)
+        #[arg(long, conflicts_with = "all")]
+        prefix: Option<String>,
+        /// Inyecta todos los secretos del vault
+        #[arg(long)]
+        all: bool,
+        /// Comando y argumentos
+        #[arg(last = true, required = true, value_name = "COMANDO")]
+        command: Vec<String>,
+    },
+    /// Renderiza una plantilla sustituyendo {{ secret "CLAVE" }} por su valor
+    Render {
+        /// Archivo de plantilla
+        template: PathBuf,
+        /// Archivo de salida, creado con permisos 0600 (por defecto stdout)
+        #[arg(short, long, value_name = "ARCHIVO")]
+        output: Option<PathBuf>,
+        /// Solo comprueba que todas las claves referenciadas existen
+        #[arg(long, conflicts_with = "output")]
+        check: bool,
+    },
+    /// Importa secretos de otro formato (.env, pass…) en una sola operación
+    Import {
+        /// Archivo de origen (.env, .kdbx, .json, .csv), o `pass` para el almacén de pass
+        source: PathBuf,
+        /// Formato del origen (por defecto se deduce del nombre)
+        #[arg(long, value_enum)]
+        format: Option<ImportFormat>,
+        /// Importa solo las entradas bajo esta ruta del origen (en pass, un
+        /// subdirectorio como `web/`), sin el prefijo en la clave
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Guarda los secretos bajo esta ruta (por ejemplo `app/`)
+        #[arg(long, value_name = "RUTA")]
+        into: Option<String>,
+        /// Muestra las claves que se importarían sin modificar nada
+        #[arg(long)]
+        dry_run: bool,
+        /// Qué hacer si una clave ya existe
+        #[arg(long, value_enum, default_value_t)]
+        on_conflict: ConflictPolicy,
+        /// Columna del CSV para un campo (`key`, `value`, `folder`, `username`, `url`, `notes`)
+        #[arg(long = "column", value_name = "CAMPO=COLUMNA")]
+        columns: Vec<String>,
+    },
+    /// Exporta secretos a otro formato
+    Export {
+        /// Formato de salida
+        #[arg(long, value_enum)]
+        format: ExportFormat,
+        /// Exporta solo los secretos bajo esta ruta, sin el prefijo en el nombre
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Exporta solo las claves que coinciden con el patrón (`prod/*`)
+        #[arg(long = "keys", value_name = "PATRÓN", num_args = 1..)]
+        keys: Vec<String>,
+        /// Nombre del Secret (`k8s`, `sops-k8s`)
+        #[arg(long)]
+        name: Option<String>,
+        /// Namespace del Secret (`k8s`, `sops-k8s`)
+        #[arg(long)]
+        namespace: Option<String>,
+        /// Archivo de salida, creado con permisos 0600 (por defecto stdout);
+        /// con `docker-secrets`, el directorio donde escribir los secretos
+        #[arg(short, long, value_name = "RUTA")]
+        output: Option<PathBuf>,
+    },
+    /// Helper de credenciales de git (también como `git-credential-crypta`)
+    GitCredential {
+        /// Operación que pide git
+        #[arg(value_enum)]
+        operation: git_credential::Operation,
+        /// Ruta del almacén con las credenciales (`git/HOST/USUARIO`)
+        #[arg(long, default_value = git_credential::DEFAULT_NAMESPACE)]
+        namespace: String,
+    },
+    /// Helper de credenciales de Docker (también como `docker-credential-crypta`)
+    DockerCredential {
+        /// Operación que pide Docker
+        #[arg(value_enum)]
+        operation: docker_credential::Operation,
+        /// Ruta del almacén con las credenciales (`docker/REGISTRO`)
+        #[arg(
+            long,
+            env = "CRYPTA_DOCKER_NAMESPACE",
+            default_value = docker_credential::DEFAULT_NAMESPACE
+        )]
+        namespace: String,
+    },
+    /// Ofrece el vault como Secret Service (`org.freedesktop.secrets`) en el bus de sesión
+    SecretService {
+        /// Ruta del almacén donde se guardan los secretos que crean los clientes
+        #[arg(long, default_value = secret_service::DEFAULT_NAMESPACE)]
+        namespace: String,
+        /// Expone todos los secretos del vault, no solo los de --namespace
+        #[arg(long)]
+        whole_vault: bool,
+        /// Sustituye al proveedor que ya tenga el nombre en el bus (gnome-keyring, KWallet…)
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Agente SSH con claves privadas del almacén, solo en memoria
+    SshAgent {
+        /// Clave privada a cargar (se puede repetir)
+        #[arg(short, long = "key", value_name = "CLAVE")]
+        keys: Vec<String>,
+        /// Clave privada que pide confirmación con SSH_ASKPASS en cada uso
+        #[arg(short, long = "confirm", value_name = "CLAVE")]
+        confirm: Vec<String>,
+        /// Carga todas las claves privadas bajo esta ruta (por ejemplo `ssh/`)
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Ruta del socket (por defecto $XDG_RUNTIME_DIR/crypta/ssh-agent.sock)
+        #[arg(short = 'a', long, value_name = "SOCKET")]
+        socket: Option<PathBuf>,
+    },
+    /// Monta el almacén como sistema de archivos FUSE (un archivo por secreto)
+    Mount {
+        /// Directorio donde montar (se crea si no existe)
+        #[arg(value_name = "DIR")]
+        mountpoint: PathBuf,
+        /// Permite modificar, crear y borrar secretos; se encriptan al cerrar el archivo
+        #[arg(short, long)]
+        write: bool,
+    },
+    /// Sirve el almacén por HTTP con una API compatible (por ahora, Vault KV v2)
+    Serve {
+        /// Expone el motor KV v2 de HashiCorp Vault (`/v1/MONTAJE/data/RUTA`)
+        #[arg(long, required = true)]
+        vault_compat: bool,
+        /// Dirección y puerto donde escuchar
+        #[arg(long, default_value = vault_compat::DEFAULT_LISTEN)]
+        listen: String,
+        /// Punto de montaje del motor KV
+        #[arg(long, default_value = vault_compat::DEFAULT_MOUNT)]
+        mount: String,
+        /// Ruta del almacén que se expone como raíz del motor KV
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Archivo con el token de acceso; si no existe se genera
+        /// (por defecto $XDG_RUNTIME_DIR/crypta/vault-token)
+        #[arg(long, value_name = "ARCHIVO")]
+        token_file: Option<PathBuf>,
+    },
+    /// Proceso auxiliar de `get` que mantiene y después borra el portapapeles
+    #[command(name = clipboard::HELPER_COMMAND, hide = true)]
+    ClipboardHelper {
+        #[arg(long)]
+        timeout: u64,
+        #[arg(long, value_enum, default_value_t)]
+        target: clipboard::Target,
+    },
+    /// Credenciales de systemd para servicios (`LoadCredential=`, `systemd-creds`)
+    SystemdCreds {
+        #[command(subcommand)]
+        action: SystemdCredsCommands,
+    },
+    /// Elimina los directorios de secretos escritos por `export --format docker-secrets`
+    /// o `systemd-creds export`
+    Cleanup {
+        /// Directorios a eliminar (por defecto todos los registrados)
+        dirs: Vec<PathBuf>,
+    },
+    /// Muestra o modifica los metadatos de un secreto
+    #[command(alias = "m")]
+    Meta {
+        /// Clave del secreto (o usa variable de entorno SECRET_ID)
+        key: Option<String>,
+        /// Nota libre (cadena vacía para borrarla)
+        #[arg(long)]
+        note: Option<String>,
+        /// Equipo propietario (cadena vacía para borrarlo)
+        #[arg(long)]
+        owner: Option<String>,
+        /// URL asociada (cadena vacía para borrarla)
+        #[arg(long)]
+        url: Option<String>,
+        /// Usuario de la cuenta (cadena vacía para borrarlo)
+        #[arg(long)]
+        username: Option<String>,
+        /// Añade una etiqueta
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        /// Quita una etiqueta
+        #[arg(long = "untag")]
+        untags: Vec<String>,
+        /// Fecha de caducidad, AAAA-MM-DD o RFC 3339 (cadena vacía para borrarla)
+        #[arg(long)]
+        expires: Option<String>,
+        /// Periodo de rotación, por ejemplo 90d, 2w o 12h (cadena vacía para borrarlo)
+        #[arg(long)]
+        rotate_every: Option<String>,
+    },
+    /// Lista los secretos caducados o que caducan pronto (sale con error si hay alguno)
+    Expiring {
+        /// Margen de aviso, por ejemplo 14d, 2w o 12h
+        #[arg(short, long, default_value = "14d")]
+        within: String,
     },
     /// Inicializa el directorio y archivo de secretos
     #[command(alias = "i")]
@@ -67,6 +334,84 @@ enum Commands {
         #[arg(long, default_value_t = false)]
         special: bool,
     },
+    /// Gestiona los vaults (almacenes de secretos con nombre)
+    #[command(alias = "v")]
+    Vault {
+        #[command(subcommand)]
+        action: VaultCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum SystemdCredsCommands {
+    /// Escribe secretos como credenciales de una unidad y muestra el drop-in
+    Export {
+        /// Unidad de systemd (`myapp` equivale a `myapp.service`)
+        unit: String,
+        /// Exporta las claves que coinciden con el patrón (`prod/*`)
+        #[arg(
+            long = "keys",
+            value_name = "PATRÓN",
+            num_args = 1..,
+            required_unless_present = "prefix"
+        )]
+        keys: Vec<String>,
+        /// Exporta los secretos bajo esta ruta, sin el prefijo en el nombre
+        #[arg(long)]
+        prefix: Option<String>,
+        /// Encripta las credenciales con `systemd-creds encrypt`
+        #[arg(long)]
+        encrypt: bool,
+        /// Clave de `systemd-creds encrypt` (`host`, `tpm2`, `auto`…)
+        #[arg(long, value_name = "CLAVE", requires = "encrypt")]
+        with_key: Option<String>,
+        /// Directorio de las credenciales (por defecto /run/credstore/UNIDAD);
+        /// con `--encrypt`, sin él las credenciales van en el drop-in
+        #[arg(short, long, value_name = "DIR")]
+        output: Option<PathBuf>,
+    },
+    /// Ejecuta un comando con las credenciales de la unidad como variables de entorno
+    Exec {
+        /// Credencial a inyectar: NOMBRE o VAR=NOMBRE (se puede repetir)
+        #[arg(short, long = "env", value_name = "[VAR=]NOMBRE")]
+        env: Vec<String>,
+        /// Inyecta todas las credenciales
+        #[arg(long)]
+        all: bool,
+        /// Directorio de las credenciales
+        #[arg(long, env = "CREDENTIALS_DIRECTORY", value_name = "DIR")]
+        credentials_dir: PathBuf,
+        /// Comando y argumentos
+        #[arg(last = true, required = true, value_name = "COMANDO")]
+        command: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum VaultCommands {
+    /// Crea un vault con su propio archivo y configuración SOPS
+    Create {
+        /// Nombre del vault
+        name: String,
+        /// Destinatario Age (por defecto los del vault principal)
+        #[arg(short, long = "recipient")]
+        recipients: Vec<String>,
+        /// URL del remoto git para dar al vault su propio repositorio
+        #[arg(long)]
+        remote: Option<String>,
+    },
+    /// Lista los vaults disponibles
+    #[command(alias = "ls")]
+    List,
+    /// Elimina un vault y sus secretos
+    #[command(alias = "rm")]
+    Remove {
+        /// Nombre del vault
+        name: String,
+        /// Elimina el vault aunque contenga secretos
+        #[arg(short, long)]
+        force: bool,
+    },
 }
 
 fn main() {
@@ -76,24 +421,84 @@ fn main() {
             EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("error")),
         )
         .with_target(false)
+        // stdout es de los datos (lookup, helpers de credenciales…)
+        .with_writer(std::io::stderr)
         .init();
 
-    let home = std::env::var("HOME").expect("No se pudo encontrar $HOME");
-    let secrets_dir = format!("{}/.secrets", home);
-    let secrets_file = format!("{}/secrets.yml", secrets_dir);
     let cli = Cli::parse();
 
     info!("Crypta iniciado");
 
-    if let Err(e) = run_command(&cli.command, &secrets_dir, &secrets_file) {
+    if let Err(e) = run(&cli) {
         error!("Error ejecutando comando: {}", e);
         eprintln!("❌ Error: {}", e);
-        std::process::exit(1);
+        std::process::exit(crypta::error::exit_code(&e));
     }
 
     info!("Comando ejecutado exitosamente");
 }
 
+/// Carga la configuración, resuelve el vault y ejecuta el comando
+fn run(cli: &Cli) -> Result<()> {
+    // Comandos que no abren el vault: funcionan aunque no exista
+    match &cli.command {
+        Commands::ClipboardHelper { timeout, target } => {
+            return clipboard::helper(*target, Duration::from_secs(*timeout));
+        }
+        Commands::Password { length, special } => {
+            return secrets::generate_password(*length, *special);
+        }
+        Commands::Cleanup { dirs } => return cleanup(dirs),
+        _ => {}
+    }
+
+    // Dentro del servicio no hay almacén: las credenciales ya están en disco
+    if let Commands::SystemdCreds {
+        action:
+            SystemdCredsCommands::Exec {
+                env: vars,
+                all,
+                credentials_dir,
+                command,
+            },
+    } = &cli.command
+    {
+        let specs = vars
+            .iter()
+            .map(|spec| EnvSpec::parse(spec))
+            .collect::<Result<Vec<_>>>()?;
+        return systemd_creds::exec(credentials_dir, &specs, *all, command);
+    }
+
+    let config = Config::load()?;
+
+    if let Commands::Vault { action } = &cli.command {
+        let secrets_dir = config.secrets_dir(cli.dir.as_deref())?;
+        return run_vault_command(action, &secrets_dir, config.vault(cli.vault.as_deref()));
+    }
+
+    let vault = Vault::open(
+        &config,
+        cli.dir.as_deref(),
+        cli.vault.as_deref(),
+        cli.file.as_deref(),
+    )?;
+    let store = vault.store(&config)?;
+    run_command(&cli.command, &config, &vault, &store)
+}
+
+/// Elimina los directorios de secretos materializados que queden
+fn cleanup(dirs: &[PathBuf]) -> Result<()> {
+    let removed = materialize::cleanup(dirs)?;
+    for dir in &removed {
+        println!("🧹 Eliminado {}", dir.display());
+    }
+    if removed.is_empty() {
+        println!("ℹ️  No hay directorios de secretos que limpiar");
+    }
+    Ok(())
+}
+
 /// Resuelve la clave del secreto desde parámetro o variable de entorno SECRET_ID
 fn resolve_key(key_param: Option<String>) -> Result<String> {
     match key_param {
@@ -107,36 +512,307 @@ fn resolve_key(key_param: Option<String>) -> Result<String> {
     }
 }
 
-fn run_command(command: &Commands, secrets_dir: &str, secrets_file: &str) -> Result<()> {
-    match command {
-        Commands::Store { key } => {
+fn run_command(command: &Commands, config: &Config, vault: &Vault, store: &Store) -> Result<()> {
+    let result = match command {
+        Commands::Store {
+            key,
+            from_file,
+            binary,
+            raw,
+            json,
+        } => {
+            let key = resolve_key(key.clone())?;
+            // Leer valor desde el archivo o desde stdin
+            let bytes = match from_file {
+                Some(path) => std::fs::read(path)
+                    .with_context(|| format!("No se pudo leer {}", path.display()))?,
+                None => {
+                    use std::io::{self, Read};
+                    let mut bytes = Vec::new();
+                    io::stdin().read_to_end(&mut bytes)?;
+                    bytes
+                }
+            };
+            if *binary || from_file.is_some() {
+                secrets::add_bytes(store, &key, &bytes)
+            } else {
+                let value = String::from_utf8(bytes).context(
+                    "La entrada no es texto UTF-8; usa --binary o --from-file para datos binarios",
+                )?;
+                // Remover whitespace al final salvo con --raw
+                let value = if *raw { value.as_str() } else { value.trim() };
+                if *json {
+                    secrets::add_json(store, &key, value)
+                } else {
+                    secrets::add(store, &key, value)
+                }
+            }
+        }
+        Commands::Set { key, value, json } => {
             let key = resolve_key(key.clone())?;
-            // Leer valor desde stdin
-            use std::io::{self, Read};
-            let mut value = String::new();
-            io::stdin().read_to_string(&mut value)?;
-            let value = value.trim(); // Remover whitespace al final
-            secrets::add(secrets_dir, secrets_file, &key, value)
-        }
-        Commands::Set { key, value } => {
+            if *json {
+                secrets::add_json(store, &key, value)
+            } else {
+                secrets::add(store, &key, value)
+            }
+        }
+        Commands::Get {
+            key,
+            timeout,
+            target,
+        } => {
             let key = resolve_key(key.clone())?;
-            secrets::add(secrets_dir, secrets_file, &key, value)
+            let timeout = timeout.unwrap_or(config.clipboard_timeout);
+            secrets::get(store, &key, *target, Duration::from_secs(timeout))
         }
-        Commands::Get { key } => {
+        Commands::Lookup {
+            key,
+            output,
+            field,
+            format,
+        } => {
             let key = resolve_key(key.clone())?;
-            secrets::get(secrets_file, &key)
+            match output {
+                Some(path) => secrets::save_to_file(store, &key, field.as_deref(), *format, path),
+                None => secrets::show(store, &key, field.as_deref(), *format),
+            }
         }
-        Commands::Lookup { key } => {
+        Commands::List { prefix, long } => secrets::list(store, prefix.as_deref(), *long),
+        Commands::Delete { key, recursive } => {
             let key = resolve_key(key.clone())?;
-            secrets::show(secrets_file, &key)
+            secrets::remove(store, &key, *recursive)
         }
-        Commands::List => secrets::list(secrets_file),
-        Commands::Delete { key } => {
+        Commands::Meta {
+            key,
+            note,
+            owner,
+            url,
+            username,
+            tags,
+            untags,
+            expires,
+            rotate_every,
+        } => {
             let key = resolve_key(key.clone())?;
-            secrets::remove(secrets_file, &key)
+            let update = MetadataUpdate {
+                note: note.clone(),
+                owner: owner.clone(),
+                url: url.clone(),
+                username: username.clone(),
+                expires: expires.clone(),
+                rotate_every: rotate_every.clone(),
+                add_tags: tags.clone(),
+                remove_tags: untags.clone(),
+            };
+            secrets::meta(store, &key, &update)
+        }
+        Commands::Exec {
+            env: vars,
+            prefix,
+            all,
+            command,
+        } => {
+            let specs = vars
+                .iter()
+                .map(|spec| EnvSpec::parse(spec))
+                .collect::<Result<Vec<_>>>()?;
+            let prefix = if *all { Some("") } else { prefix.as_deref() };
+            env::exec(store, &specs, prefix, command)
+        }
+        Commands::Render {
+            template: path,
+            output,
+            check,
+        } => template::render_file(store, path, output.as_deref(), *check),
+        Commands::Import {
+            source,
+            format,
+            prefix,
+            into,
+            dry_run,
+            on_conflict,
+            columns,
+        } => import::import(
+            store,
+            source,
+            &ImportOptions {
+                format: *format,
+                prefix: prefix.clone(),
+                into: into.clone(),
+                dry_run: *dry_run,
+                on_conflict: *on_conflict,
+                columns: columns.clone(),
+            },
+        ),
+        Commands::Export {
+            format,
+            prefix,
+            keys,
+            name,
+            namespace,
+            output,
+        } => export::export(
+            store,
+            *format,
+            &ExportOptions {
+                prefix: prefix.clone(),
+                keys: keys.clone(),
+                name: name.clone(),
+                namespace: namespace.clone(),
+            },
+            output.as_deref(),
+        ),
+        Commands::GitCredential {
+            operation,
+            namespace,
+        } => git_credential::serve(store, namespace, *operation),
+        Commands::DockerCredential {
+            operation,
+            namespace,
+        } => docker_credential::serve(store, namespace, *operation),
+        Commands::SecretService {
+            namespace,
+            whole_vault,
+            replace,
+        } => {
+            // El servicio comparte el almacén entre hilos y necesita el suyo propio
+            let store = vault.service_store(config)?;
+            secret_service::serve(store, vault.name(), namespace, *whole_vault, *replace)
+        }
+        Commands::SshAgent {
+            keys,
+            confirm,
+            prefix,
+            socket,
+        } => {
+            #[cfg(unix)]
+            {
+                use crypta::ssh_agent::{self, Agent};
+                let identities = ssh_agent::load(store, keys, confirm, prefix.as_deref())?;
+                let socket = socket.clone().unwrap_or_else(ssh_agent::default_socket);
+                ssh_agent::serve(Agent::new(identities), &socket)
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = (keys, confirm, prefix, socket);
+                anyhow::bail!("ssh-agent solo está disponible en sistemas Unix")
+            }
+        }
+        Commands::Mount { mountpoint, write } => {
+            #[cfg(target_os = "linux")]
+            {
+                // El sistema de archivos necesita un almacén propio
+                let store = vault.service_store(config)?;
+                crypta::fuse::mount(store, mountpoint, *write)
+            }
+            #[cfg(not(target_os = "linux"))]
+            {
+                let _ = (mountpoint, write);
+                anyhow::bail!("mount solo está disponible en Linux")
+            }
         }
-        Commands::Init => secrets::init(secrets_dir, secrets_file),
-        Commands::Sync { message } => git::sync(secrets_dir, message.as_deref()),
-        Commands::Password { length, special } => secrets::generate_password(*length, *special),
+        Commands::Serve {
+            vault_compat: _,
+            listen,
+            mount,
+            prefix,
+            token_file,
+        } => {
+            let token_file = token_file
+                .clone()
+                .unwrap_or_else(vault_compat::default_token_file);
+            let token = vault_compat::load_token(&token_file)?;
+            let store = vault.service_store(config)?;
+            let api = vault_compat::Api::new(store, mount, prefix.as_deref(), token);
+            vault_compat::serve(api, listen)
+        }
+        Commands::SystemdCreds { action } => match action {
+            SystemdCredsCommands::Export {
+                unit,
+                keys,
+                prefix,
+                encrypt,
+                with_key,
+                output,
+            } => systemd_creds::export(
+                store,
+                unit,
+                &ExportOptions {
+                    prefix: prefix.clone(),
+                    keys: keys.clone(),
+                    ..Default::default()
+                },
+                &systemd_creds::CredsOptions {
+                    encrypt: *encrypt,
+                    with_key: with_key.clone(),
+                    output: output.clone(),
+                },
+            ),
+            SystemdCredsCommands::Exec { .. } => {
+                unreachable!("systemd-creds exec no usa un almacén")
+            }
+        },
+        Commands::ClipboardHelper { .. } => {
+            unreachable!("el proceso del portapapeles no usa un almacén")
+        }
+        Commands::Cleanup { .. } | Commands::Password { .. } => {
+            unreachable!("cleanup y password no usan un almacén")
+        }
+        Commands::Expiring { within } => secrets::expiring(store, parse_duration(within)?),
+        Commands::Init => {
+            if !vault.is_default() {
+                anyhow::bail!(
+                    "'init' solo inicializa el vault principal; usa 'crypta vault create {}'",
+                    vault.name()
+                );
+            }
+            secrets::init(store)
+        }
+        Commands::Sync { message } => git::sync(
+            vault.git_dir(),
+            Some(message.as_deref().unwrap_or(&config.sync.message)),
+            &config.git,
+        ),
+        Commands::Vault { .. } => unreachable!("los comandos de vault no usan un almacén"),
+    };
+    result?;
+
+    // Sincronización automática tras modificar el almacén
+    let modifies_store = matches!(
+        command,
+        Commands::Store { .. }
+            | Commands::Set { .. }
+            | Commands::Delete { .. }
+            | Commands::Meta { .. }
+            | Commands::Import { dry_run: false, .. }
+            | Commands::GitCredential {
+                operation: git_credential::Operation::Store | git_credential::Operation::Erase,
+                ..
+            }
+            | Commands::DockerCredential {
+                operation: docker_credential::Operation::Store
+                    | docker_credential::Operation::Erase,
+                ..
+            }
+    );
+    if modifies_store {
+        vault.auto_sync(config)?;
+    }
+    Ok(())
+}
+
+fn run_vault_command(
+    action: &VaultCommands,
+    secrets_dir: &Path,
+    current: Option<&str>,
+) -> Result<()> {
+    match action {
+        VaultCommands::Create {
+            name,
+            recipients,
+            remote,
+        } => vault::create(secrets_dir, name, recipients, remote.as_deref()),
+        VaultCommands::List => vault::list(secrets_dir, current.unwrap_or(vault::DEFAULT_VAULT)),
+        VaultCommands::Remove { name, force } => vault::remove(secrets_dir, name, *force),
     }
 }
@@ -0,0 +1,231 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, warn};
+
+/// Directorios con secretos en claro escritos por crypta, pendientes de
+/// `crypta cleanup`. Se guardan en `CRYPTA_STATE`,
+/// `$XDG_STATE_HOME/crypta/state.toml` o `~/.local/state/crypta/state.toml`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct State {
+    pub materialized: Vec<PathBuf>,
+}
+
+impl State {
+    /// Ruta del archivo de estado
+    pub fn path() -> Result<PathBuf> {
+        if let Some(path) = env_var("CRYPTA_STATE") {
+            return Ok(PathBuf::from(path));
+        }
+        if let Some(xdg) = env_var("XDG_STATE_HOME") {
+            return Ok(Path::new(&xdg).join("crypta/state.toml"));
+        }
+        let home = env_var("HOME")
+            .context("No se pudo determinar el archivo de estado: $HOME no está definida")?;
+        Ok(Path::new(&home).join(".local/state/crypta/state.toml"))
+    }
+
+    /// Lee el estado; si el archivo no existe devuelve un estado vacío
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("No se pudo leer el estado: {}", path.display()))?;
+        toml::from_str(&content)
+            .with_context(|| format!("Archivo de estado no válido: {}", path.display()))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("No se pudo crear {}", parent.display()))?;
+        }
+        let content = toml::to_string(self).context("No se pudo serializar el estado")?;
+        fs::write(&path, content)
+            .with_context(|| format!("No se pudo guardar el estado: {}", path.display()))
+    }
+
+    pub fn contains(&self, dir: &Path) -> bool {
+        self.materialized.iter().any(|d| d == dir)
+    }
+}
+
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// Directorio privado para sockets y archivos efímeros de los servicios de
+/// crypta: `$XDG_RUNTIME_DIR/crypta` o, si no está definida,
+/// `crypta-$USER` en el directorio temporal
+pub fn runtime_dir() -> PathBuf {
+    match env_var("XDG_RUNTIME_DIR") {
+        Some(dir) => Path::new(&dir).join("crypta"),
+        None => std::env::temp_dir().join(format!(
+            "crypta-{}",
+            env_var("USER").unwrap_or_else(|| "user".to_string())
+        )),
+    }
+}
+
+/// Escribe un archivo por secreto en `dir` (permisos 0700) con permisos
+/// 0400 y registra el directorio para `crypta cleanup`.
+///
+/// Si `dir` ya lo había creado crypta se sustituye su contenido; si es un
+/// directorio ajeno con archivos, se rechaza para no mezclar secretos con
+/// otros datos.
+pub fn write_dir(dir: &Path, files: &[(String, Vec<u8>)]) -> Result<PathBuf> {
+    let dir = absolute(dir)?;
+    let mut state = State::load()?;
+
+    if dir.exists() {
+        if state.contains(&dir) {
+            debug!("Sustituyendo el contenido de {}", dir.display());
+            fs::remove_dir_all(&dir)
+                .with_context(|| format!("No se pudo vaciar {}", dir.display()))?;
+        } else if fs::read_dir(&dir)
+            .with_context(|| format!("No se pudo leer {}", dir.display()))?
+            .next()
+            .is_some()
+        {
+            anyhow::bail!(
+                "{} no está vacío y no lo ha creado crypta; usa otro directorio",
+                dir.display()
+            );
+        }
+    }
+    fs::create_dir_all(&dir).with_context(|| format!("No se pudo crear {}", dir.display()))?;
+    set_mode(&dir, 0o700)?;
+
+    if !is_tmpfs(&dir) {
+        warn!(
+            "{} no está en un tmpfs: los secretos quedarán en disco hasta 'crypta cleanup'",
+            dir.display()
+        );
+    }
+
+    // Se registra antes de escribir para que `cleanup` lo encuentre aunque falle
+    if !state.contains(&dir) {
+        state.materialized.push(dir.clone());
+        state.save()?;
+    }
+    for (name, contents) in files {
+        let path = dir.join(name);
+        fs::write(&path, contents)
+            .with_context(|| format!("No se pudo escribir {}", path.display()))?;
+        set_mode(&path, 0o400)?;
+    }
+    Ok(dir)
+}
+
+/// Elimina los directorios registrados (o solo `dirs` si se indican) y los
+/// quita del estado. Devuelve los directorios eliminados.
+pub fn cleanup(dirs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut state = State::load()?;
+    let targets: Vec<PathBuf> = if dirs.is_empty() {
+        state.materialized.clone()
+    } else {
+        let mut targets = Vec::with_capacity(dirs.len());
+        for dir in dirs {
+            let dir = absolute(dir)?;
+            if !state.contains(&dir) {
+                anyhow::bail!("{} no es un directorio creado por crypta", dir.display());
+            }
+            targets.push(dir);
+        }
+        targets
+    };
+
+    for dir in &targets {
+        if dir.exists() {
+            fs::remove_dir_all(dir)
+                .with_context(|| format!("No se pudo eliminar {}", dir.display()))?;
+        } else {
+            debug!("{} ya no existe", dir.display());
+        }
+        state.materialized.retain(|d| d != dir);
+    }
+    state.save()?;
+    Ok(targets)
+}
+
+fn absolute(dir: &Path) -> Result<PathBuf> {
+    let dir = if dir.is_absolute() {
+        dir.to_path_buf()
+    } else {
+        std::env::current_dir()
+            .context("No se pudo obtener el directorio actual")?
+            .join(dir)
+    };
+    // Sin `..` ni `.`, para que el estado identifique cada directorio una vez
+    Ok(dir.components().fold(PathBuf::new(), |mut path, c| {
+        match c {
+            std::path::Component::ParentDir => {
+                path.pop();
+            }
+            std::path::Component::CurDir => {}
+            c => path.push(c),
+        }
+        path
+    }))
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+        .with_context(|| format!("No se pudieron cambiar los permisos de {}", path.display()))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Indica si `dir` está en un sistema de archivos en memoria (`tmpfs`,
+/// `ramfs`). Fuera de Linux no se puede comprobar y se asume que no.
+fn is_tmpfs(dir: &Path) -> bool {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return false;
+    };
+    let dir = dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf());
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let _device = fields.next()?;
+            let mount_point = fields.next()?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            dir.starts_with(&mount_point)
+                .then(|| (PathBuf::from(mount_point), fs_type.to_string()))
+        })
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .is_some_and(|(_, fs_type)| fs_type == "tmpfs" || fs_type == "ramfs")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absolute_normalizes_components() {
+        assert_eq!(
+            absolute(Path::new("/run/user/1000/./crypta/../crypta/app")).unwrap(),
+            PathBuf::from("/run/user/1000/crypta/app")
+        );
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let state = State {
+            materialized: vec![PathBuf::from("/run/user/1000/crypta/app")],
+        };
+        let content = toml::to_string(&state).unwrap();
+        assert_eq!(toml::from_str::<State>(&content).unwrap(), state);
+        assert_eq!(toml::from_str::<State>("").unwrap(), State::default());
+    }
+}
@@ -0,0 +1,119 @@
+use crate::error::Error;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tracing::debug;
+
+/// Directorio de `pass`: `PASSWORD_STORE_DIR` o `~/.password-store`
+pub fn store_dir() -> Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("PASSWORD_STORE_DIR").filter(|d| !d.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let home = std::env::var_os("HOME")
+        .filter(|h| !h.is_empty())
+        .context("No se pudo encontrar el directorio de pass: $HOME no está definida")?;
+    Ok(Path::new(&home).join(".password-store"))
+}
+
+/// Entradas del almacén de `pass`: su clave (ruta relativa sin `.gpg`) y su
+/// archivo, ordenadas por clave. Se omiten los directorios ocultos (`.git`).
+pub fn entries(dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    if !dir.is_dir() {
+        anyhow::bail!("No existe el almacén de pass: {}", dir.display());
+    }
+    let mut found = Vec::new();
+    walk(dir, dir, &mut found)?;
+    found.sort();
+    Ok(found)
+}
+
+fn walk(root: &Path, dir: &Path, found: &mut Vec<(String, PathBuf)>) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("No se pudo leer {}", dir.display()))? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            walk(root, &path, found)?;
+        } else if path.extension().is_some_and(|e| e == "gpg") {
+            let relative = path.strip_prefix(root).unwrap_or(&path).with_extension("");
+            let key = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/");
+            found.push((key, path));
+        }
+    }
+    Ok(())
+}
+
+/// Comprueba que `gpg` está instalado
+pub fn check_gpg() -> Result<()> {
+    match Command::new("gpg").arg("--version").output() {
+        Ok(output) if output.status.success() => Ok(()),
+        _ => Err(anyhow::Error::new(Error::BackendMissing("gpg".to_string())))
+            .context("Instala GnuPG para importar desde pass"),
+    }
+}
+
+/// Desencripta una entrada con el `gpg` local
+pub fn decrypt(path: &Path) -> Result<String> {
+    debug!("Desencriptando {}", path.display());
+    let output = Command::new("gpg")
+        .args(["--quiet", "--batch", "--decrypt"])
+        .arg(path)
+        .output()
+        .context("No se pudo ejecutar gpg")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(Error::DecryptFailed(format!("{}: {}", path.display(), stderr.trim())).into());
+    }
+    String::from_utf8(output.stdout)
+        .with_context(|| format!("{} no contiene texto UTF-8", path.display()))
+}
+
+/// Separa una entrada de `pass` en la contraseña (primera línea) y el resto
+/// de líneas, que se guardan como nota
+pub fn split(content: &str) -> (String, Option<String>) {
+    let (first, rest) = content.split_once('\n').unwrap_or((content, ""));
+    let first = first.trim_end_matches('\r').to_string();
+    let rest = rest.trim();
+    (first, Some(rest.to_string()).filter(|r| !r.is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split() {
+        assert_eq!(split("s3cret\n"), ("s3cret".to_string(), None));
+        assert_eq!(
+            split("s3cret\r\nuser: bob\nurl: x\n"),
+            ("s3cret".to_string(), Some("user: bob\nurl: x".to_string()))
+        );
+        assert_eq!(split(""), (String::new(), None));
+    }
+
+    #[test]
+    fn test_entries_map_directories_to_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("web/github")).unwrap();
+        fs::create_dir_all(root.join(".git")).unwrap();
+        fs::write(root.join("email.gpg"), "").unwrap();
+        fs::write(root.join("web/github/token.gpg"), "").unwrap();
+        fs::write(root.join(".gpg-id"), "").unwrap();
+        fs::write(root.join(".git/config.gpg"), "").unwrap();
+        fs::write(root.join("web/readme.txt"), "").unwrap();
+
+        let keys: Vec<String> = entries(root).unwrap().into_iter().map(|(k, _)| k).collect();
+        assert_eq!(keys, vec!["email", "web/github/token"]);
+    }
+}
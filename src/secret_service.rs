@@ -0,0 +1,845 @@
+//! Proveedor de la API `org.freedesktop.secrets` (Secret Service) sobre un
+//! vault de crypta, para que navegadores, NetworkManager y demás clientes de
+//! libsecret guarden y lean sus contraseñas en el almacén.
+//!
+//! El vault es la única colección (también como alias `default`). Cualquier
+//! proceso del bus de sesión puede leer y modificar los elementos, así que
+//! solo se exponen los secretos bajo el espacio de nombres donde escriben
+//! los clientes, salvo que se pida el vault entero. Los atributos de
+//! búsqueda y la etiqueta se guardan en los metadatos del secreto; además
+//! cada elemento expone su clave en el atributo `crypta:key`.
+//!
+//! No hay bloqueo: la colección siempre está desbloqueada, porque sin un
+//! diálogo que pida confirmación `Lock` no protegería nada.
+
+use crate::entry::Entry;
+use crate::error::Error;
+use crate::import::{self, ConflictPolicy, Record};
+use crate::secrets;
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use tracing::{info, warn};
+use zbus::message::{Header, Message};
+use zbus::names::ErrorName;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Type};
+use zbus::{fdo, interface, Connection, DBusError, ObjectServer};
+
+/// Nombre del servicio en el bus de sesión
+pub const BUS_NAME: &str = "org.freedesktop.secrets";
+
+/// Ruta bajo la que se guardan por defecto los secretos creados por clientes
+pub const DEFAULT_NAMESPACE: &str = "secret-service";
+
+/// Atributo con la clave del secreto en el almacén
+pub const KEY_ATTRIBUTE: &str = "crypta:key";
+
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection";
+const SESSION_PATH: &str = "/org/freedesktop/secrets/session";
+const DEFAULT_ALIAS_PATH: &str = "/org/freedesktop/secrets/aliases/default";
+
+const LABEL_PROPERTY: &str = "org.freedesktop.Secret.Item.Label";
+const ATTRIBUTES_PROPERTY: &str = "org.freedesktop.Secret.Item.Attributes";
+
+const TEXT_CONTENT_TYPE: &str = "text/plain; charset=utf8";
+const BINARY_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Secreto tal y como viaja por D-Bus: sesión, parámetros del cifrado,
+/// valor y tipo de contenido. Solo se admiten sesiones `plain`, así que los
+/// parámetros siempre están vacíos.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct Secret {
+    pub session: OwnedObjectPath,
+    pub parameters: Vec<u8>,
+    pub value: Vec<u8>,
+    pub content_type: String,
+}
+
+/// Errores de la API con los nombres que esperan los clientes
+#[derive(Debug)]
+enum ServiceError {
+    NoSession(String),
+    NoSuchObject(String),
+    /// Errores estándar de D-Bus (`NotSupported`, `Failed`…)
+    Fdo(fdo::Error),
+}
+
+impl DBusError for ServiceError {
+    fn create_reply(&self, call: &Header<'_>) -> zbus::Result<Message> {
+        match self {
+            Self::NoSession(message) | Self::NoSuchObject(message) => {
+                Message::error(call, self.name())?.build(&(message,))
+            }
+            Self::Fdo(e) => e.create_reply(call),
+        }
+    }
+
+    fn name(&self) -> ErrorName<'_> {
+        let name = match self {
+            Self::NoSession(_) => "org.freedesktop.Secret.Error.NoSession",
+            Self::NoSuchObject(_) => "org.freedesktop.Secret.Error.NoSuchObject",
+            Self::Fdo(e) => return e.name(),
+        };
+        ErrorName::from_static_str_unchecked(name)
+    }
+
+    fn description(&self) -> Option<&str> {
+        match self {
+            Self::NoSession(message) | Self::NoSuchObject(message) => Some(message),
+            Self::Fdo(e) => e.description(),
+        }
+    }
+}
+
+impl From<zbus::Error> for ServiceError {
+    fn from(e: zbus::Error) -> Self {
+        Self::Fdo(e.into())
+    }
+}
+
+impl From<anyhow::Error> for ServiceError {
+    fn from(e: anyhow::Error) -> Self {
+        match crate::error::find(&e) {
+            Some(Error::NotFound(key)) => {
+                Self::NoSuchObject(format!("No existe el secreto '{}'", key))
+            }
+            _ => Self::Fdo(failed(e)),
+        }
+    }
+}
+
+fn not_supported(message: &str) -> ServiceError {
+    ServiceError::Fdo(fdo::Error::NotSupported(message.to_string()))
+}
+
+fn failed(e: anyhow::Error) -> fdo::Error {
+    fdo::Error::Failed(format!("{:#}", e))
+}
+
+/// Codifica una clave como elemento de una ruta de objeto D-Bus, que solo
+/// admite `[A-Za-z0-9_]`: el resto de bytes se escriben como `_XX`
+pub fn encode_path_element(key: &str) -> String {
+    key.bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() {
+                (b as char).to_string()
+            } else {
+                format!("_{:02x}", b)
+            }
+        })
+        .collect()
+}
+
+/// Inversa de [`encode_path_element`]
+pub fn decode_path_element(element: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(element.len());
+    let mut rest = element.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        if b == b'_' {
+            let hex = std::str::from_utf8(tail.get(..2)?).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(b);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+/// Atributos de un secreto: los guardados más su clave
+pub fn attributes(key: &str, entry: &Entry) -> HashMap<String, String> {
+    let mut attributes: HashMap<_, _> = entry.meta.attributes.clone().into_iter().collect();
+    attributes.insert(KEY_ATTRIBUTE.to_string(), key.to_string());
+    attributes
+}
+
+/// Claves de los secretos bajo `prefix` (todos si está vacío) que tienen
+/// todos los atributos pedidos
+pub fn search(doc: &Value, prefix: &str, query: &HashMap<String, String>) -> Vec<String> {
+    tree::leaves(doc, prefix)
+        .into_iter()
+        .filter(|key| {
+            let Ok(entry) = secrets::find(doc, key) else {
+                return false;
+            };
+            let attributes = attributes(key, &entry);
+            query
+                .iter()
+                .all(|(name, value)| attributes.get(name) == Some(value))
+        })
+        .collect()
+}
+
+/// Clave para un elemento nuevo: la etiqueta bajo `namespace`, sin `/`
+fn item_key(namespace: &str, label: &str) -> String {
+    let name = label.trim().replace(tree::SEPARATOR, "-");
+    let name = if name.is_empty() { "item" } else { &name };
+    import::join_key(Some(namespace), name)
+}
+
+/// Guarda el valor de un secreto recibido por D-Bus: como texto si lo es,
+/// o byte a byte si no
+fn set_value(doc: &mut Value, key: &str, secret: &Secret) -> Result<()> {
+    let is_text = secret.content_type.is_empty() || secret.content_type.starts_with("text/");
+    match std::str::from_utf8(&secret.value) {
+        Ok(text) if is_text => secrets::insert(doc, key, Value::String(text.to_string())),
+        _ => {
+            let previous = match tree::get(doc, key) {
+                Some(node) if tree::is_leaf(node) => Some(Entry::from_node(node)?),
+                _ => None,
+            };
+            let entry = Entry::updated_binary(previous, &secret.value);
+            tree::set(doc, key, entry.to_node()?)
+        }
+    }
+}
+
+fn timestamp(date: Option<chrono::DateTime<chrono::Utc>>) -> u64 {
+    date.map_or(0, |d| d.timestamp().max(0) as u64)
+}
+
+fn root() -> OwnedObjectPath {
+    ObjectPath::from_static_str_unchecked("/").into()
+}
+
+/// Estado compartido por todos los objetos del servicio
+struct State {
+    store: Store,
+    label: String,
+    namespace: String,
+    /// Subárbol expuesto: el espacio de nombres o, vacío, el vault entero
+    scope: String,
+    collection: OwnedObjectPath,
+    sessions: Mutex<HashSet<OwnedObjectPath>>,
+    next_session: AtomicU64,
+    /// Serializa las escrituras para no perder cambios concurrentes
+    writer: Mutex<()>,
+}
+
+impl State {
+    fn load(&self) -> Result<Value> {
+        if self.store.exists() {
+            self.store.load()
+        } else {
+            Ok(Value::Null)
+        }
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut Value) -> Result<T>) -> Result<T> {
+        let _guard = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        let mut doc = self.store.load_or_default()?;
+        let result = change(&mut doc)?;
+        self.store.save(&doc)?;
+        Ok(result)
+    }
+
+    /// Indica si `key` es visible para los clientes
+    fn in_scope(&self, key: &str) -> bool {
+        self.scope.is_empty()
+            || key
+                .strip_prefix(self.scope.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(tree::SEPARATOR))
+    }
+
+    /// Secretos visibles con los atributos pedidos
+    fn search(&self, query: &HashMap<String, String>) -> Result<Vec<String>> {
+        Ok(search(&self.load()?, &self.scope, query))
+    }
+
+    fn entry(&self, key: &str) -> Result<Entry> {
+        secrets::find(&self.load()?, key)
+    }
+
+    fn item_path(&self, key: &str) -> OwnedObjectPath {
+        let path = format!("{}/{}", self.collection.as_str(), encode_path_element(key));
+        ObjectPath::try_from(path)
+            .expect("la clave codificada es una ruta válida")
+            .into()
+    }
+
+    fn item_key(&self, path: &ObjectPath<'_>) -> Option<String> {
+        let element = path
+            .as_str()
+            .strip_prefix(self.collection.as_str())?
+            .strip_prefix('/')?;
+        decode_path_element(element).filter(|key| self.in_scope(key))
+    }
+
+    fn check_session(&self, session: &ObjectPath<'_>) -> std::result::Result<(), ServiceError> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        if !sessions.iter().any(|s| s.as_ref() == *session) {
+            return Err(ServiceError::NoSession(format!(
+                "La sesión '{}' no existe",
+                session
+            )));
+        }
+        Ok(())
+    }
+
+    fn secret(
+        &self,
+        key: &str,
+        session: &ObjectPath<'_>,
+    ) -> std::result::Result<Secret, ServiceError> {
+        self.check_session(session)?;
+        let entry = self.entry(key)?;
+        let content_type = if entry.is_binary() {
+            BINARY_CONTENT_TYPE
+        } else {
+            TEXT_CONTENT_TYPE
+        };
+        Ok(Secret {
+            session: session.to_owned().into(),
+            parameters: Vec::new(),
+            value: entry.bytes()?,
+            content_type: content_type.to_string(),
+        })
+    }
+
+    /// Registra en el bus los elementos de las claves y devuelve sus rutas
+    async fn publish(
+        self: &Arc<Self>,
+        server: &ObjectServer,
+        keys: Vec<String>,
+    ) -> zbus::Result<Vec<OwnedObjectPath>> {
+        let mut paths = Vec::with_capacity(keys.len());
+        for key in keys {
+            let path = self.item_path(&key);
+            let item = Item {
+                key,
+                state: Arc::clone(self),
+            };
+            server.at(&path, item).await?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+
+    /// Fecha de la última modificación del almacén
+    fn modified(&self) -> u64 {
+        std::fs::metadata(self.store.file())
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs())
+    }
+}
+
+/// `org.freedesktop.Secret.Service` en `/org/freedesktop/secrets`
+struct Service {
+    state: Arc<State>,
+}
+
+#[interface(name = "org.freedesktop.Secret.Service")]
+impl Service {
+    /// Abre una sesión. Solo se admite `plain`: los clientes de libsecret
+    /// recurren a ella cuando el algoritmo que piden no está disponible.
+    async fn open_session(
+        &self,
+        algorithm: &str,
+        _input: OwnedValue,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> std::result::Result<(OwnedValue, OwnedObjectPath), ServiceError> {
+        if algorithm != "plain" {
+            return Err(not_supported(&format!(
+                "Algoritmo no soportado: '{}'; usa 'plain'",
+                algorithm
+            )));
+        }
+        let id = self.state.next_session.fetch_add(1, Ordering::SeqCst);
+        let path: OwnedObjectPath = ObjectPath::try_from(format!("{}/s{}", SESSION_PATH, id))
+            .map_err(zbus::Error::from)?
+            .into();
+        server
+            .at(
+                &path,
+                Session {
+                    path: path.clone(),
+                    state: Arc::clone(&self.state),
+                },
+            )
+            .await?;
+        self.state
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(path.clone());
+        let output =
+            OwnedValue::try_from(zbus::zvariant::Value::from("")).map_err(zbus::Error::from)?;
+        Ok((output, path))
+    }
+
+    /// Solo hay una colección, la del vault; se devuelve en lugar de crear otra
+    fn create_collection(
+        &self,
+        _properties: HashMap<String, OwnedValue>,
+        _alias: &str,
+    ) -> (OwnedObjectPath, OwnedObjectPath) {
+        (self.state.collection.clone(), root())
+    }
+
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> std::result::Result<(Vec<OwnedObjectPath>, Vec<OwnedObjectPath>), ServiceError> {
+        let keys = self.state.search(&attributes)?;
+        Ok((self.state.publish(server, keys).await?, Vec::new()))
+    }
+
+    /// La colección nunca está bloqueada: todos los objetos ya lo están
+    fn unlock(&self, objects: Vec<OwnedObjectPath>) -> (Vec<OwnedObjectPath>, OwnedObjectPath) {
+        (objects, root())
+    }
+
+    /// No se bloquea nada: sin un diálogo para desbloquear, el bloqueo no
+    /// protegería los secretos de otros procesos del bus
+    fn lock(&self, _objects: Vec<OwnedObjectPath>) -> (Vec<OwnedObjectPath>, OwnedObjectPath) {
+        (Vec::new(), root())
+    }
+
+    fn get_secrets(
+        &self,
+        items: Vec<OwnedObjectPath>,
+        session: ObjectPath<'_>,
+    ) -> std::result::Result<HashMap<OwnedObjectPath, Secret>, ServiceError> {
+        let mut secrets = HashMap::new();
+        for item in items {
+            let key = self
+                .state
+                .item_key(&item)
+                .ok_or_else(|| ServiceError::NoSuchObject(format!("'{}' no existe", item)))?;
+            let secret = self.state.secret(&key, &session)?;
+            secrets.insert(item, secret);
+        }
+        Ok(secrets)
+    }
+
+    fn read_alias(&self, name: &str) -> OwnedObjectPath {
+        if name == "default" || name == self.state.label {
+            self.state.collection.clone()
+        } else {
+            root()
+        }
+    }
+
+    fn set_alias(
+        &self,
+        name: &str,
+        collection: ObjectPath<'_>,
+    ) -> std::result::Result<(), ServiceError> {
+        if name == "default" && collection == self.state.collection.as_ref() {
+            return Ok(());
+        }
+        Err(not_supported(
+            "El único alias disponible es 'default' para la colección del vault",
+        ))
+    }
+
+    #[zbus(property)]
+    fn collections(&self) -> Vec<OwnedObjectPath> {
+        vec![self.state.collection.clone()]
+    }
+}
+
+/// `org.freedesktop.Secret.Collection`: el vault servido
+struct Collection {
+    state: Arc<State>,
+}
+
+#[interface(name = "org.freedesktop.Secret.Collection")]
+impl Collection {
+    fn delete(&self) -> std::result::Result<OwnedObjectPath, ServiceError> {
+        Err(not_supported(
+            "No se puede eliminar el vault desde el Secret Service; usa 'crypta vault remove'",
+        ))
+    }
+
+    async fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> std::result::Result<Vec<OwnedObjectPath>, ServiceError> {
+        let keys = self.state.search(&attributes)?;
+        Ok(self.state.publish(server, keys).await?)
+    }
+
+    /// Crea un secreto bajo el espacio de nombres con la etiqueta como
+    /// nombre. Con `replace` se actualiza el que tenga los mismos atributos.
+    async fn create_item(
+        &self,
+        properties: HashMap<String, OwnedValue>,
+        secret: Secret,
+        replace: bool,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> std::result::Result<(OwnedObjectPath, OwnedObjectPath), ServiceError> {
+        self.state.check_session(&secret.session)?;
+
+        let label = match properties.get(LABEL_PROPERTY) {
+            Some(value) => String::try_from(value.try_clone().map_err(zbus::Error::from)?)
+                .map_err(zbus::Error::from)?,
+            None => String::new(),
+        };
+        let attributes: BTreeMap<String, String> = match properties.get(ATTRIBUTES_PROPERTY) {
+            Some(value) => {
+                HashMap::<String, String>::try_from(value.try_clone().map_err(zbus::Error::from)?)
+                    .map_err(zbus::Error::from)?
+                    .into_iter()
+                    .filter(|(name, _)| name != KEY_ATTRIBUTE)
+                    .collect()
+            }
+            None => BTreeMap::new(),
+        };
+
+        let key = self.state.update(|doc| {
+            let query = attributes.clone().into_iter().collect();
+            let existing = search(doc, &self.state.scope, &query)
+                .into_iter()
+                .find(|key| {
+                    secrets::find(doc, key).is_ok_and(|entry| entry.meta.attributes == attributes)
+                });
+            let key = match existing {
+                Some(key) if replace && !attributes.is_empty() => key,
+                _ => {
+                    // Reserva una clave libre con un valor provisional
+                    let record = Record::new(item_key(&self.state.namespace, &label), "");
+                    let summary = import::apply(doc, vec![record], ConflictPolicy::Rename)?;
+                    summary
+                        .added
+                        .into_iter()
+                        .chain(summary.renamed.into_iter().map(|(_, key)| key))
+                        .next()
+                        .context("No se pudo crear el secreto")?
+                }
+            };
+            set_value(doc, &key, &secret)?;
+            let mut entry = secrets::find(doc, &key)?;
+            entry.meta.label = Some(label.clone()).filter(|l| !l.is_empty());
+            entry.meta.attributes = attributes.clone();
+            tree::set(doc, &key, entry.to_node()?)?;
+            Ok(key)
+        })?;
+        info!("Secreto '{}' guardado desde el Secret Service", key);
+
+        let path = self.state.publish(server, vec![key]).await?.remove(0);
+        Self::item_created(&emitter, path.as_ref()).await?;
+        Ok((path, root()))
+    }
+
+    #[zbus(signal)]
+    async fn item_created(emitter: &SignalEmitter<'_>, item: ObjectPath<'_>) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn item_deleted(emitter: &SignalEmitter<'_>, item: ObjectPath<'_>) -> zbus::Result<()>;
+
+    /// Los elementos se registran en una tarea aparte: mientras se lee una
+    /// propiedad el servidor de objetos está bloqueado y registrarlos aquí
+    /// lo dejaría esperando para siempre
+    #[zbus(property)]
+    async fn items(
+        &self,
+        #[zbus(connection)] connection: &Connection,
+    ) -> fdo::Result<Vec<OwnedObjectPath>> {
+        let keys = self.state.search(&HashMap::new()).map_err(failed)?;
+        let paths = keys.iter().map(|key| self.state.item_path(key)).collect();
+        let state = Arc::clone(&self.state);
+        let task_connection = connection.clone();
+        connection
+            .executor()
+            .spawn(
+                async move {
+                    let server = task_connection.object_server();
+                    if let Err(e) = state.publish(server, keys).await {
+                        warn!("No se pudieron registrar los elementos: {}", e);
+                    }
+                },
+                "publish items",
+            )
+            .detach();
+        Ok(paths)
+    }
+
+    #[zbus(property)]
+    fn label(&self) -> String {
+        self.state.label.clone()
+    }
+
+    #[zbus(property)]
+    fn locked(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn created(&self) -> u64 {
+        0
+    }
+
+    #[zbus(property)]
+    fn modified(&self) -> u64 {
+        self.state.modified()
+    }
+}
+
+/// `org.freedesktop.Secret.Item`: un secreto del almacén
+struct Item {
+    key: String,
+    state: Arc<State>,
+}
+
+#[interface(name = "org.freedesktop.Secret.Item")]
+impl Item {
+    async fn delete(
+        &self,
+        #[zbus(object_server)] server: &ObjectServer,
+        #[zbus(connection)] connection: &Connection,
+    ) -> std::result::Result<OwnedObjectPath, ServiceError> {
+        self.state.update(|doc| {
+            tree::remove(doc, &self.key, false)?
+                .ok_or_else(|| Error::NotFound(self.key.clone()))?;
+            Ok(())
+        })?;
+        info!("Secreto '{}' eliminado desde el Secret Service", self.key);
+
+        let path = self.state.item_path(&self.key);
+        let emitter = SignalEmitter::new(connection, self.state.collection.clone())?;
+        Collection::item_deleted(&emitter, path.as_ref()).await?;
+        server.remove::<Item, _>(&path).await?;
+        Ok(root())
+    }
+
+    fn get_secret(&self, session: ObjectPath<'_>) -> std::result::Result<Secret, ServiceError> {
+        self.state.secret(&self.key, &session)
+    }
+
+    fn set_secret(&self, secret: Secret) -> std::result::Result<(), ServiceError> {
+        self.state.check_session(&secret.session)?;
+        self.state.update(|doc| {
+            secrets::find(doc, &self.key)?;
+            set_value(doc, &self.key, &secret)
+        })?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn locked(&self) -> bool {
+        false
+    }
+
+    #[zbus(property)]
+    fn attributes(&self) -> fdo::Result<HashMap<String, String>> {
+        let entry = self.state.entry(&self.key).map_err(failed)?;
+        Ok(attributes(&self.key, &entry))
+    }
+
+    #[zbus(property)]
+    fn set_attributes(&mut self, attributes: HashMap<String, String>) -> fdo::Result<()> {
+        self.update_meta(|meta| {
+            meta.attributes = attributes
+                .into_iter()
+                .filter(|(name, _)| name != KEY_ATTRIBUTE)
+                .collect();
+        })
+    }
+
+    #[zbus(property)]
+    fn label(&self) -> fdo::Result<String> {
+        let entry = self.state.entry(&self.key).map_err(failed)?;
+        Ok(entry.meta.label.unwrap_or_else(|| self.key.clone()))
+    }
+
+    #[zbus(property)]
+    fn set_label(&mut self, label: String) -> fdo::Result<()> {
+        self.update_meta(|meta| meta.label = Some(label).filter(|l| !l.is_empty()))
+    }
+
+    #[zbus(property)]
+    fn created(&self) -> fdo::Result<u64> {
+        let entry = self.state.entry(&self.key).map_err(failed)?;
+        Ok(timestamp(entry.meta.created))
+    }
+
+    #[zbus(property)]
+    fn modified(&self) -> fdo::Result<u64> {
+        let entry = self.state.entry(&self.key).map_err(failed)?;
+        Ok(timestamp(entry.meta.updated.or(entry.meta.created)))
+    }
+}
+
+impl Item {
+    fn update_meta(&self, change: impl FnOnce(&mut crate::entry::Metadata)) -> fdo::Result<()> {
+        self.state
+            .update(|doc| {
+                let mut entry = secrets::find(doc, &self.key)?;
+                change(&mut entry.meta);
+                tree::set(doc, &self.key, entry.to_node()?)
+            })
+            .map_err(failed)
+    }
+}
+
+/// `org.freedesktop.Secret.Session`: una sesión `plain` abierta por un cliente
+struct Session {
+    path: OwnedObjectPath,
+    state: Arc<State>,
+}
+
+#[interface(name = "org.freedesktop.Secret.Session")]
+impl Session {
+    async fn close(&self, #[zbus(object_server)] server: &ObjectServer) -> fdo::Result<()> {
+        self.state
+            .sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.path);
+        server.remove::<Session, _>(&self.path).await?;
+        Ok(())
+    }
+}
+
+/// Ofrece el vault como Secret Service en el bus de sesión hasta que se
+/// termine el proceso. Los secretos que crean los clientes se guardan bajo
+/// `namespace` y solo esos son visibles, salvo con `whole_vault`. Con
+/// `replace` se sustituye a otro proveedor (por ejemplo gnome-keyring) si
+/// ya tiene el nombre.
+pub fn serve(
+    store: Store,
+    vault: &str,
+    namespace: &str,
+    whole_vault: bool,
+    replace: bool,
+) -> Result<()> {
+    let scope = if whole_vault {
+        String::new()
+    } else {
+        tree::segments(namespace).join(&tree::SEPARATOR.to_string())
+    };
+    if scope.is_empty() && !whole_vault {
+        anyhow::bail!("El espacio de nombres no puede estar vacío; usa --whole-vault");
+    }
+    let collection: OwnedObjectPath = ObjectPath::try_from(format!(
+        "{}/{}",
+        COLLECTION_PATH,
+        encode_path_element(vault)
+    ))?
+    .into();
+    let state = Arc::new(State {
+        store,
+        label: vault.to_string(),
+        namespace: namespace.to_string(),
+        scope,
+        collection: collection.clone(),
+        sessions: Mutex::new(HashSet::new()),
+        next_session: AtomicU64::new(1),
+        writer: Mutex::new(()),
+    });
+    let collection_for = |state: &Arc<State>| Collection {
+        state: Arc::clone(state),
+    };
+
+    let _connection = zbus::blocking::connection::Builder::session()
+        .context("No se pudo conectar al bus de sesión (¿DBUS_SESSION_BUS_ADDRESS?)")?
+        .serve_at(
+            SERVICE_PATH,
+            Service {
+                state: Arc::clone(&state),
+            },
+        )?
+        .serve_at(&collection, collection_for(&state))?
+        .serve_at(DEFAULT_ALIAS_PATH, collection_for(&state))?
+        .name(BUS_NAME)?
+        .allow_name_replacements(true)
+        .replace_existing_names(replace)
+        .build()
+        .map_err(|e| match e {
+            zbus::Error::NameTaken => anyhow::anyhow!(
+                "Otro proceso ya ofrece {} en el bus; usa --replace para sustituirlo",
+                BUS_NAME
+            ),
+            e => anyhow::Error::new(e).context("No se pudo iniciar el Secret Service"),
+        })?;
+
+    println!(
+        "🔐 Secret Service activo con el vault '{}' ({})",
+        vault, BUS_NAME
+    );
+    if whole_vault {
+        eprintln!("⚠️  Todos los secretos del vault son accesibles desde el bus de sesión");
+    }
+    loop {
+        std::thread::park();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_element_round_trip() {
+        for key in ["API_KEY", "prod/db.password", "ñandú", "a_2f"] {
+            let element = encode_path_element(key);
+            assert!(element
+                .bytes()
+                .all(|b| b.is_ascii_alphanumeric() || b == b'_'));
+            assert_eq!(decode_path_element(&element).as_deref(), Some(key));
+        }
+        assert_eq!(encode_path_element("prod/db"), "prod_2fdb");
+        assert_eq!(decode_path_element("bad_z"), None);
+    }
+
+    #[test]
+    fn test_search_by_attributes() {
+        let doc: Value = serde_yaml::from_str(
+            "web:\n  _value: s3cret\n  _meta:\n    attributes:\n      server: example.com\n      user: bob\nAPI_KEY: abc\n",
+        )
+        .unwrap();
+        let query = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        assert_eq!(
+            search(&doc, "", &query(&[("server", "example.com")])),
+            ["web"]
+        );
+        assert_eq!(
+            search(&doc, "", &query(&[(KEY_ATTRIBUTE, "API_KEY")])),
+            ["API_KEY"]
+        );
+        assert!(search(
+            &doc,
+            "",
+            &query(&[("server", "example.com"), ("user", "eve")])
+        )
+        .is_empty());
+        assert_eq!(search(&doc, "", &HashMap::new()).len(), 2);
+        // Fuera del espacio de nombres no se encuentra nada
+        assert_eq!(search(&doc, "web", &HashMap::new()), ["web"]);
+        assert!(search(
+            &doc,
+            "secret-service",
+            &query(&[(KEY_ATTRIBUTE, "API_KEY")])
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_item_key() {
+        assert_eq!(
+            item_key("secret-service", "Contraseña de example.com/app"),
+            "secret-service/Contraseña de example.com-app"
+        );
+        assert_eq!(item_key("secret-service", "  "), "secret-service/item");
+    }
+}
@@ -1,312 +1,500 @@
+use crate::clipboard;
+use crate::entry::{Entry, MetadataUpdate};
+use crate::error::Error;
+use crate::store::Store;
+use crate::tree;
 use anyhow::{Context, Result};
-use arboard::Clipboard;
+use chrono::{DateTime, TimeDelta, Utc};
+use rand::prelude::*;
 use serde_yaml::Value;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
 use std::process::Command;
-use tracing::{debug, info};
-use rand::prelude::*;
+use tracing::{debug, info, warn};
 
-pub fn add(secrets_dir: &str, secrets_file: &str, key: &str, value: &str) -> Result<()> {
+pub fn add(store: &Store, key: &str, value: &str) -> Result<()> {
     info!("Añadiendo secreto '{}'", key);
-    debug!("Directorio: {}, Archivo: {}", secrets_dir, secrets_file);
+    debug!(
+        "Directorio: {}, Archivo: {}",
+        store.dir().display(),
+        store.file().display()
+    );
 
-    verify_sops_installed()?;
+    // Desencriptar archivo existente o crear estructura YAML vacía
+    let mut data = store.load_or_default()?;
 
-    // Crear directorio si no existe
-    fs::create_dir_all(secrets_dir).context("No se pudo crear el directorio de secretos")?;
+    insert(&mut data, key, Value::String(value.to_string()))?;
 
-    let decrypted_content = if !Path::new(secrets_file).exists() {
-        // Si no existe, crear estructura YAML vacía
-        info!("Creando nuevo archivo de secretos");
-        String::new()
-    } else {
-        // Desencriptar archivo existente
-        info!("Actualizando secreto existente '{}'", key);
-        debug!("Desencriptando con sops...");
+    store.save(&data)?;
 
-        let output = Command::new("sops")
-            .arg("-d")
-            .arg(secrets_file)
-            .output()
-            .context("No se pudo ejecutar sops")?;
-
-        if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Error al desencriptar: {}", error);
-        }
+    println!("✅ Secreto '{}' añadido.", key);
+    Ok(())
+}
 
-        String::from_utf8(output.stdout).context("El contenido desencriptado no es UTF-8 válido")?
-    };
+/// Almacena un valor estructurado (objeto, lista…) a partir de JSON
+pub fn add_json(store: &Store, key: &str, json: &str) -> Result<()> {
+    info!("Añadiendo secreto estructurado '{}'", key);
+    let value: Value = serde_json::from_str(json).context("El valor no es JSON válido")?;
 
-    // Parsear YAML y actualizar
-    let mut data: Value = if decrypted_content.is_empty() {
-        Value::Mapping(serde_yaml::Mapping::new())
-    } else {
-        serde_yaml::from_str(&decrypted_content).context("No se pudo parsear el contenido YAML")?
-    };
+    let mut data = store.load_or_default()?;
+    insert(&mut data, key, value)?;
+    store.save(&data)?;
 
-    if let Value::Mapping(ref mut map) = data {
-        map.insert(
-            Value::String(key.to_string()),
-            Value::String(value.to_string()),
-        );
-    }
+    println!("✅ Secreto '{}' añadido.", key);
+    Ok(())
+}
 
-    let updated_yaml = serde_yaml::to_string(&data).context("No se pudo serializar el YAML")?;
-    debug!("YAML actualizado");
+/// Almacena bytes arbitrarios (certificados, keystores…) sin alterarlos
+pub fn add_bytes(store: &Store, key: &str, bytes: &[u8]) -> Result<()> {
+    info!(
+        "Añadiendo secreto binario '{}' ({} bytes)",
+        key,
+        bytes.len()
+    );
+    let mut data = store.load_or_default()?;
 
-    // Encriptar con sops
-    let encrypted_content = encrypt_with_sops(&updated_yaml, secrets_file)?;
+    let entry = Entry::updated_binary(previous(&data, key)?, bytes);
+    tree::set(&mut data, key, entry.to_node()?)?;
 
-    fs::write(secrets_file, encrypted_content)
-        .context("No se pudo escribir el archivo de secretos")?;
-    debug!("Archivo encriptado y guardado");
+    store.save(&data)?;
 
-    println!("✅ Secreto '{}' añadido.", key);
+    println!("✅ Secreto '{}' añadido ({} bytes).", key, bytes.len());
     Ok(())
 }
 
-pub fn get(secrets_file: &str, key: &str) -> Result<()> {
-    info!("Obteniendo secreto '{}'", key);
-    debug!("Archivo: {}", secrets_file);
+/// Inserta o actualiza un valor en el documento ya desencriptado, conservando
+/// los metadatos del secreto y actualizando sus marcas de tiempo
+pub fn insert(doc: &mut Value, key: &str, value: Value) -> Result<()> {
+    let entry = Entry::updated(previous(doc, key)?, value);
+    tree::set(doc, key, entry.to_node()?)
+}
+
+/// Secreto existente en `key`, si lo hay (los grupos no cuentan)
+fn previous(doc: &Value, key: &str) -> Result<Option<Entry>> {
+    match tree::get(doc, key) {
+        Some(node) if tree::is_leaf(node) => Ok(Some(Entry::from_node(node)?)),
+        _ => Ok(None),
+    }
+}
 
-    if !Path::new(secrets_file).exists() {
+/// Busca un secreto en el documento ya desencriptado
+pub fn find(doc: &Value, key: &str) -> Result<Entry> {
+    let node = tree::get(doc, key).ok_or_else(|| Error::NotFound(key.to_string()))?;
+    if !tree::is_leaf(node) {
         anyhow::bail!(
-            "El archivo de secretos no existe: {}\n\nPrimero añade un secreto con: crypta add CLAVE valor",
-            secrets_file
+            "'{}' es un grupo de secretos; usa 'crypta list {}/' para ver su contenido",
+            key,
+            key
         );
     }
+    Entry::from_node(node)
+}
 
-    verify_sops_installed()?;
-
-    debug!("Desencriptando con sops...");
-    let output = Command::new("sops")
-        .arg("-d")
-        .arg(secrets_file)
-        .output()
-        .context("No se pudo ejecutar sops")?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Error al desencriptar: {}", error);
-    }
+/// Formato de salida de `lookup`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum Format {
+    /// Texto tal cual; los valores estructurados se muestran como JSON
+    #[default]
+    Raw,
+    Json,
+    Yaml,
+}
 
-    let decrypted_content = String::from_utf8(output.stdout)
-        .context("El contenido desencriptado no es UTF-8 válido")?;
+/// Devuelve el valor en claro de una clave
+pub fn read(store: &Store, key: &str) -> Result<String> {
+    debug!("Archivo: {}", store.file().display());
+    text(&store.load()?, key)
+}
 
-    let yaml: Value =
-        serde_yaml::from_str(&decrypted_content).context("No se pudo parsear el contenido YAML")?;
+/// Valor en claro de una clave (o de `SECRETO.campo`) del documento ya desencriptado
+pub fn text(doc: &Value, key: &str) -> Result<String> {
+    let (secret, entry, field) = resolve(doc, key, None)?;
+    format_value(&extract(secret, entry, field)?, Format::Raw)
+}
 
-    let val = yaml
-        .get(key)
-        .and_then(|v| v.as_str())
-        .context(format!("La clave '{}' no existe", key))?;
+/// Devuelve el valor de una clave o de uno de sus campos.
+///
+/// Sin `field`, una clave como `DB_CONFIG.password` que no existe se
+/// interpreta como el campo `password` del secreto `DB_CONFIG`.
+pub fn read_value(store: &Store, key: &str, field: Option<&str>) -> Result<Value> {
+    debug!("Archivo: {}", store.file().display());
 
-    // Copiar al portapapeles
-    debug!("Copiando al portapapeles");
-    let mut clipboard = Clipboard::new().context("No se pudo acceder al portapapeles")?;
-    clipboard
-        .set_text(val)
-        .context("No se pudo copiar al portapapeles")?;
-    info!("Secreto copiado al portapapeles exitosamente");
+    let yaml = store.load()?;
+    let (secret, entry, field) = resolve(&yaml, key, field)?;
+    extract(secret, entry, field)
+}
 
-    println!("📋 Secreto '{}' copiado al portapapeles.", key);
-    Ok(())
+/// Devuelve los bytes exactos de una clave (texto o binario)
+pub fn read_bytes(store: &Store, key: &str) -> Result<Vec<u8>> {
+    let yaml = store.load()?;
+    let (secret, entry, field) = resolve(&yaml, key, None)?;
+    if is_exact(&entry, field, Format::Raw) {
+        return entry.bytes();
+    }
+    Ok(format_value(&extract(secret, entry, field)?, Format::Raw)?.into_bytes())
 }
 
-pub fn show(secrets_file: &str, key: &str) -> Result<()> {
-    info!("Mostrando secreto '{}'", key);
-    debug!("Archivo: {}", secrets_file);
+/// Convierte un valor al formato de salida indicado
+pub fn format_value(value: &Value, format: Format) -> Result<String> {
+    match format {
+        Format::Raw => match tree::scalar_to_string(value) {
+            Some(text) => Ok(text),
+            None if value.is_null() => Ok(String::new()),
+            None => format_value(value, Format::Json),
+        },
+        Format::Json => {
+            serde_json::to_string_pretty(value).context("El valor no se puede convertir a JSON")
+        }
+        Format::Yaml => Ok(serde_yaml::to_string(value)
+            .context("El valor no se puede convertir a YAML")?
+            .trim_end()
+            .to_string()),
+    }
+}
 
-    if !Path::new(secrets_file).exists() {
-        anyhow::bail!(
-            "El archivo de secretos no existe: {}\n\nPrimero añade un secreto con: crypta add CLAVE valor",
-            secrets_file
+/// Busca el secreto de `key`, separando el campo si lo hay, y avisa por
+/// stderr si ha caducado
+fn resolve<'a>(
+    doc: &Value,
+    key: &'a str,
+    field: Option<&'a str>,
+) -> Result<(&'a str, Entry, Option<&'a str>)> {
+    let (secret, field) = match field {
+        Some(field) => (key, Some(field)),
+        None => split_field(doc, key),
+    };
+    let entry = find(doc, secret)?;
+
+    if let Some(due) = entry
+        .meta
+        .due()
+        .filter(|_| entry.meta.is_expired(Utc::now()))
+    {
+        warn!("Secreto '{}' caducado desde {}", secret, due);
+        eprintln!(
+            "⚠️  El secreto '{}' caducó o debía rotarse el {}",
+            secret,
+            due.format("%Y-%m-%d %H:%M")
         );
     }
 
-    verify_sops_installed()?;
-
-    debug!("Desencriptando con sops...");
-    let output = Command::new("sops")
-        .arg("-d")
-        .arg(secrets_file)
-        .output()
-        .context("No se pudo ejecutar sops")?;
+    Ok((secret, entry, field))
+}
 
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Error al desencriptar: {}", error);
+/// Divide `SECRETO.campo` en el secreto más largo que existe y el resto
+fn split_field<'a>(doc: &Value, key: &'a str) -> (&'a str, Option<&'a str>) {
+    if tree::get(doc, key).is_none() {
+        for (i, _) in key.rmatch_indices(tree::FIELD_SEPARATOR) {
+            if tree::get(doc, &key[..i]).is_some_and(tree::is_leaf) {
+                return (&key[..i], Some(&key[i + 1..]));
+            }
+        }
     }
+    (key, None)
+}
 
-    let decrypted_content = String::from_utf8(output.stdout)
-        .context("El contenido desencriptado no es UTF-8 válido")?;
-
-    let yaml: Value =
-        serde_yaml::from_str(&decrypted_content).context("No se pudo parsear el contenido YAML")?;
+/// Valor de un secreto o de uno de sus campos
+fn extract(secret: &str, entry: Entry, field: Option<&str>) -> Result<Value> {
+    let value = if entry.is_binary() {
+        Value::String(entry.text().with_context(|| {
+            format!(
+                "El secreto '{}' es binario; usa 'crypta lookup {} --output ARCHIVO'",
+                secret, secret
+            )
+        })?)
+    } else {
+        entry.value
+    };
 
-    let val = yaml
-        .get(key)
-        .and_then(|v| v.as_str())
-        .context(format!("La clave '{}' no existe", key))?;
+    match field {
+        None => Ok(value),
+        Some(path) => tree::field(&value, path)
+            .cloned()
+            .ok_or_else(|| Error::NotFound(format!("{}.{}", secret, path)).into()),
+    }
+}
 
-    // Imprimir el valor por stdout
-    println!("{}", val);
-    Ok(())
+/// Indica si el secreto se escribe byte a byte (binarios sin campo ni formato)
+fn is_exact(entry: &Entry, field: Option<&str>, format: Format) -> bool {
+    entry.is_binary() && field.is_none() && format == Format::Raw
 }
 
-pub fn list(secrets_file: &str) -> Result<()> {
-    info!("Listando secretos");
-    debug!("Archivo: {}", secrets_file);
+/// Copia el secreto en `target` y lo borra pasado `timeout` si sigue allí;
+/// con cero se queda en el portapapeles
+pub fn get(
+    store: &Store,
+    key: &str,
+    target: clipboard::Target,
+    timeout: std::time::Duration,
+) -> Result<()> {
+    info!("Obteniendo secreto '{}'", key);
+    let val = read(store, key)?;
 
-    if !Path::new(secrets_file).exists() {
-        anyhow::bail!(
-            "El archivo de secretos no existe: {}\n\nPrimero añade un secreto con: crypta add CLAVE valor",
-            secrets_file
+    // Copiar al portapapeles
+    debug!("Copiando al portapapeles ({:?})", target);
+    clipboard::copy(&val, target, timeout)?;
+    info!("Secreto copiado al portapapeles exitosamente");
+
+    let destination = match target {
+        clipboard::Target::Clipboard => "al portapapeles",
+        clipboard::Target::Primary => "a la selección PRIMARY",
+        clipboard::Target::Osc52 => {
+            println!(
+                "📋 Secreto '{}' enviado al portapapeles de la terminal (no se borra automáticamente).",
+                key
+            );
+            return Ok(());
+        }
+    };
+    if timeout.is_zero() {
+        println!("📋 Secreto '{}' copiado {}.", key, destination);
+    } else {
+        println!(
+            "📋 Secreto '{}' copiado {} (se borrará en {} s).",
+            key,
+            destination,
+            timeout.as_secs()
         );
     }
+    Ok(())
+}
 
-    verify_sops_installed()?;
-
-    debug!("Desencriptando con sops...");
-    let output = Command::new("sops")
-        .arg("-d")
-        .arg(secrets_file)
-        .output()
-        .context("No se pudo ejecutar sops")?;
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Error al desencriptar: {}", error);
+pub fn show(store: &Store, key: &str, field: Option<&str>, format: Format) -> Result<()> {
+    info!("Mostrando secreto '{}'", key);
+    let yaml = store.load()?;
+    let (secret, entry, field) = resolve(&yaml, key, field)?;
+
+    // Los binarios se escriben tal cual, sin salto de línea final
+    let mut stdout = std::io::stdout().lock();
+    if is_exact(&entry, field, format) {
+        stdout.write_all(&entry.bytes()?)?;
+        stdout.flush()?;
+        return Ok(());
     }
 
-    let decrypted_content = String::from_utf8(output.stdout)
-        .context("El contenido desencriptado no es UTF-8 válido")?;
-
-    let yaml: Value =
-        serde_yaml::from_str(&decrypted_content).context("No se pudo parsear el contenido YAML")?;
+    // Imprimir el valor por stdout
+    let val = format_value(&extract(secret, entry, field)?, format)?;
+    writeln!(stdout, "{}", val)?;
+    Ok(())
+}
 
-    println!("🔑 Claves en {}:", secrets_file);
+/// Restaura un secreto (o uno de sus campos) en un archivo con permisos 0600
+pub fn save_to_file(
+    store: &Store,
+    key: &str,
+    field: Option<&str>,
+    format: Format,
+    path: &Path,
+) -> Result<()> {
+    info!("Guardando secreto '{}' en {}", key, path.display());
+    let yaml = store.load()?;
+    let (secret, entry, field) = resolve(&yaml, key, field)?;
+
+    let contents = if is_exact(&entry, field, format) {
+        entry.bytes()?
+    } else {
+        let mut text = format_value(&extract(secret, entry, field)?, format)?;
+        if format != Format::Raw {
+            text.push('\n');
+        }
+        text.into_bytes()
+    };
+    write_private(path, &contents)?;
+    println!("💾 Secreto '{}' guardado en {}", key, path.display());
+    Ok(())
+}
 
-    if let Value::Mapping(map) = yaml {
-        for key in map.keys() {
-            if let Some(key_str) = key.as_str() {
-                println!("{}", key_str);
-            }
+/// Escribe un archivo legible solo por el usuario actual
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // `mode` solo se aplica al crear el archivo
+        if path.exists() {
+            fs::set_permissions(path, fs::Permissions::from_mode(0o600)).with_context(|| {
+                format!("No se pudieron cambiar los permisos de {}", path.display())
+            })?;
         }
     }
 
-    Ok(())
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("No se pudo crear el archivo {}", path.display()))?;
+    file.write_all(contents)
+        .with_context(|| format!("No se pudo escribir el archivo {}", path.display()))
 }
 
-pub fn remove(secrets_file: &str, key: &str) -> Result<()> {
-    info!("Eliminando secreto '{}'", key);
-    debug!("Archivo: {}", secrets_file);
+/// Devuelve las claves del almacén bajo `prefix` (todas si está vacío)
+pub fn keys(store: &Store, prefix: &str) -> Result<Vec<String>> {
+    let yaml = store.load()?;
+    Ok(tree::leaves(&yaml, prefix))
+}
 
-    if !Path::new(secrets_file).exists() {
-        anyhow::bail!("El archivo de secretos no existe: {}", secrets_file);
-    }
+pub fn list(store: &Store, prefix: Option<&str>, long: bool) -> Result<()> {
+    info!("Listando secretos");
+    debug!("Archivo: {}", store.file().display());
 
-    verify_sops_installed()?;
+    let yaml = store.load()?;
+    let keys = tree::leaves(&yaml, prefix.unwrap_or_default());
 
-    debug!("Desencriptando con sops...");
-    let output = Command::new("sops")
-        .arg("-d")
-        .arg(secrets_file)
-        .output()
-        .context("No se pudo ejecutar sops")?;
+    println!("🔑 Claves en {}:", store.file().display());
+    if !long {
+        for key in keys {
+            println!("{}", key);
+        }
+        return Ok(());
+    }
 
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Error al desencriptar: {}", error);
+    let width = keys.iter().map(|k| k.chars().count()).max().unwrap_or(0);
+    for key in keys {
+        let meta = find(&yaml, &key)?.meta;
+        let updated = meta
+            .updated
+            .map(|d| d.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let owner = meta.owner.as_deref().unwrap_or("-");
+        let tags = if meta.tags.is_empty() {
+            "-".to_string()
+        } else {
+            meta.tags.join(",")
+        };
+        let note = meta.note.as_deref().unwrap_or("");
+        println!(
+            "{:<width$}  {:<16}  {:<12}  {:<16}  {}",
+            key, updated, owner, tags, note
+        );
     }
 
-    let decrypted_content = String::from_utf8(output.stdout)
-        .context("El contenido desencriptado no es UTF-8 válido")?;
+    Ok(())
+}
 
-    let mut yaml: Value =
-        serde_yaml::from_str(&decrypted_content).context("No se pudo parsear el contenido YAML")?;
+/// Muestra los metadatos de un secreto o los modifica si `update` no está vacío
+pub fn meta(store: &Store, key: &str, update: &MetadataUpdate) -> Result<()> {
+    info!("Metadatos del secreto '{}'", key);
+    let mut yaml = store.load()?;
+    let mut entry = find(&yaml, key)?;
+
+    if !update.is_empty() {
+        update.apply(&mut entry.meta)?;
+        tree::set(&mut yaml, key, entry.to_node()?)?;
+        store.save(&yaml)?;
+        println!("✅ Metadatos de '{}' actualizados.", key);
+    }
 
-    if let Value::Mapping(ref mut map) = yaml {
-        map.remove(&Value::String(key.to_string()));
+    println!("🏷️  Metadatos de '{}':", key);
+    let fields = [
+        ("created", entry.meta.created.map(|d| d.to_rfc3339())),
+        ("updated", entry.meta.updated.map(|d| d.to_rfc3339())),
+        ("updated_by", entry.meta.updated_by.clone()),
+        ("owner", entry.meta.owner.clone()),
+        ("url", entry.meta.url.clone()),
+        ("username", entry.meta.username.clone()),
+        (
+            "tags",
+            Some(entry.meta.tags.join(",")).filter(|t| !t.is_empty()),
+        ),
+        ("note", entry.meta.note.clone()),
+        ("expires", entry.meta.expires.map(|d| d.to_rfc3339())),
+        ("rotate_every", entry.meta.rotate_every.clone()),
+        ("label", entry.meta.label.clone()),
+        (
+            "attributes",
+            Some(
+                entry
+                    .meta
+                    .attributes
+                    .iter()
+                    .map(|(name, value)| format!("{}={}", name, value))
+                    .collect::<Vec<_>>()
+                    .join(","),
+            )
+            .filter(|a| !a.is_empty()),
+        ),
+        ("due", entry.meta.due().map(|d| d.to_rfc3339())),
+    ];
+    for (name, value) in fields {
+        if let Some(value) = value {
+            println!("{:<12}  {}", name, value);
+        }
     }
+    Ok(())
+}
 
-    let updated_yaml = serde_yaml::to_string(&yaml).context("No se pudo serializar el YAML")?;
-    debug!("YAML actualizado");
+/// Devuelve los secretos caducados o que caducan antes de `now + within`,
+/// ordenados por fecha
+pub fn due(
+    store: &Store,
+    now: DateTime<Utc>,
+    within: TimeDelta,
+) -> Result<Vec<(String, DateTime<Utc>)>> {
+    let yaml = store.load()?;
+    let limit = now
+        .checked_add_signed(within)
+        .unwrap_or(DateTime::<Utc>::MAX_UTC);
+    let mut due = Vec::new();
+    for key in tree::leaves(&yaml, "") {
+        if let Some(date) = find(&yaml, &key)?.meta.due() {
+            if date <= limit {
+                due.push((key, date));
+            }
+        }
+    }
+    due.sort_by_key(|(_, date)| *date);
+    Ok(due)
+}
 
-    // Encriptar con sops
-    let encrypted_content = encrypt_with_sops(&updated_yaml, secrets_file)?;
+/// Lista los secretos caducados o próximos a caducar. Devuelve
+/// [`Error::Expiring`] si hay alguno, para que el CLI termine con error.
+pub fn expiring(store: &Store, within: TimeDelta) -> Result<()> {
+    info!("Buscando secretos que caducan en {}", within);
+    let now = Utc::now();
+    let due = due(store, now, within)?;
 
-    fs::write(secrets_file, encrypted_content)
-        .context("No se pudo escribir el archivo de secretos")?;
-    debug!("Archivo reencriptado y guardado");
+    if due.is_empty() {
+        println!("✅ No hay secretos caducados ni por caducar.");
+        return Ok(());
+    }
 
-    println!("🗑️ Secreto '{}' eliminado.", key);
-    Ok(())
+    println!("⏰ Secretos caducados o por caducar:");
+    let width = due
+        .iter()
+        .map(|(k, _)| k.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (key, date) in &due {
+        let status = if *date <= now {
+            "caducado"
+        } else {
+            "por caducar"
+        };
+        println!(
+            "{:<width$}  {}  {}",
+            key,
+            date.format("%Y-%m-%d %H:%M"),
+            status
+        );
+    }
+    Err(Error::Expiring(due.len()).into())
 }
 
-fn verify_sops_installed() -> Result<()> {
-    debug!("Verificando que sops esté instalado...");
+pub fn remove(store: &Store, key: &str, recursive: bool) -> Result<()> {
+    info!("Eliminando secreto '{}'", key);
+    debug!("Archivo: {}", store.file().display());
 
-    let output = Command::new("which").arg("sops").output();
+    let mut yaml = store.load()?;
 
-    match output {
-        Ok(out) if out.status.success() => {
-            let path = String::from_utf8_lossy(&out.stdout).trim().to_string();
-            debug!("sops encontrado en: {}", path);
-            Ok(())
-        }
-        _ => {
-            anyhow::bail!(
-                "El comando 'sops' no está instalado.\n\n\
-                Instala SOPS:\n\
-                - Arch Linux: sudo pacman -S sops\n\
-                - Ubuntu/Debian: sudo apt install sops\n\
-                - macOS: brew install sops\n\
-                - O descarga desde: https://github.com/getsops/sops/releases"
-            )
-        }
+    if tree::remove(&mut yaml, key, recursive)?.is_none() {
+        return Err(Error::NotFound(key.to_string()).into());
     }
-}
 
-fn encrypt_with_sops(yaml_content: &str, secrets_file: &str) -> Result<Vec<u8>> {
-    debug!("Encriptando con sops...");
-
-    // Obtener el directorio del archivo de secretos para .sops.yaml
-    let secrets_path = Path::new(secrets_file);
-    let work_dir = secrets_path
-        .parent()
-        .context("No se pudo obtener el directorio del archivo de secretos")?;
-
-    // Escribir contenido a un archivo temporal .yml en el mismo directorio
-    // para que SOPS pueda aplicar las reglas de creación basadas en path
-    use std::io::Write;
-    let temp_file_path = work_dir.join(".crypta_temp.yml");
-    let mut temp_file =
-        fs::File::create(&temp_file_path).context("No se pudo crear archivo temporal")?;
-    temp_file
-        .write_all(yaml_content.as_bytes())
-        .context("No se pudo escribir al archivo temporal")?;
-    drop(temp_file); // Cerrar el archivo
-
-    // Encriptar el archivo temporal
-    let output = Command::new("sops")
-        .arg("-e")
-        .arg(&temp_file_path)
-        .current_dir(work_dir)
-        .output()
-        .context("No se pudo ejecutar sops")?;
-
-    // Limpiar archivo temporal
-    let _ = fs::remove_file(&temp_file_path);
-
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Error al encriptar: {}", error);
-    }
+    store.save(&yaml)?;
 
-    Ok(output.stdout)
+    println!("🗑️ Secreto '{}' eliminado.", key);
+    Ok(())
 }
 
 /// Extrae la clave pública de un archivo de clave privada Age
@@ -354,11 +542,13 @@ fn extract_public_key_from_output(output: &str) -> Result<String> {
     anyhow::bail!("No se pudo extraer la clave pública de la salida de age-keygen")
 }
 
-pub fn init(secrets_dir: &str, secrets_file: &str) -> Result<()> {
+pub fn init(store: &Store) -> Result<()> {
+    let secrets_dir = &store.dir().display().to_string();
+    let secrets_file = &store.file().display().to_string();
     info!("Inicializando directorio de secretos");
     debug!("Directorio: {}, Archivo: {}", secrets_dir, secrets_file);
 
-    verify_sops_installed()?;
+    store.backend().check()?;
 
     // Crear directorio si no existe
     if !Path::new(secrets_dir).exists() {
@@ -371,7 +561,7 @@ pub fn init(secrets_dir: &str, secrets_file: &str) -> Result<()> {
     }
 
     // Verificar si el archivo ya existe
-    if Path::new(secrets_file).exists() {
+    if store.exists() {
         println!("⚠️  El archivo de secretos ya existe: {}", secrets_file);
         println!("💡 Usa 'crypta set --key CLAVE --value VALOR' para añadir secretos");
         return Ok(());
@@ -415,20 +605,7 @@ pub fn init(secrets_dir: &str, secrets_file: &str) -> Result<()> {
     let sops_config_path = format!("{}/.sops.yaml", secrets_dir);
     if !Path::new(&sops_config_path).exists() {
         info!("Creando archivo de configuración SOPS con clave pública");
-        let sops_config = format!(
-            r#"# Configuración de SOPS para crypta
-# Generado automáticamente
-# 
-# Clave Age utilizada: {}
-# Variables de entorno recomendadas:
-#   export SOPS_AGE_KEY_FILE={}
-# 
-creation_rules:
-  - path_regex: \.yml$
-    age: {}
-"#,
-            age_key_path, age_key_path, public_key
-        );
+        let sops_config = sops_config(&age_key_path, &[public_key]);
 
         fs::write(&sops_config_path, sops_config)
             .context("No se pudo crear el archivo .sops.yaml")?;
@@ -460,6 +637,26 @@ creation_rules:
     Ok(())
 }
 
+/// Contenido del `.sops.yaml` con una regla de creación para los destinatarios Age
+pub(crate) fn sops_config(age_key_path: &str, recipients: &[String]) -> String {
+    format!(
+        r#"# Configuración de SOPS para crypta
+# Generado automáticamente
+# 
+# Clave Age utilizada: {}
+# Variables de entorno recomendadas:
+#   export SOPS_AGE_KEY_FILE={}
+# 
+creation_rules:
+  - path_regex: \.yml$
+    age: {}
+"#,
+        age_key_path,
+        age_key_path,
+        recipients.join(",")
+    )
+}
+
 /// Genera y escribe una contraseña aleatoria por stdout
 /// Devuelve una contraseña aleatoria como `String`.
 pub fn password_string(length: usize, special: bool) -> Result<String> {
@@ -467,7 +664,6 @@ pub fn password_string(length: usize, special: bool) -> Result<String> {
         anyhow::bail!("La longitud debe ser mayor que 0");
     }
 
-
     let mut rng = rand::rng();
     let mut chars: Vec<char> = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789"
         .chars()
@@ -0,0 +1,112 @@
+use crate::backend::CryptoBackend;
+use crate::error::Error;
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Acción que se ejecuta tras cada guardado
+pub type AfterSave = Box<dyn Fn() + Send + Sync>;
+
+/// Archivo de secretos junto con el backend que lo encripta
+pub struct Store {
+    dir: PathBuf,
+    file: PathBuf,
+    backend: Box<dyn CryptoBackend>,
+    after_save: Option<AfterSave>,
+}
+
+impl Store {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        file: impl Into<PathBuf>,
+        backend: Box<dyn CryptoBackend>,
+    ) -> Self {
+        Self {
+            dir: dir.into(),
+            file: file.into(),
+            backend,
+            after_save: None,
+        }
+    }
+
+    /// Ejecuta `hook` después de cada [`Store::save`] que termina bien (la
+    /// sincronización automática de los servicios de larga duración)
+    pub fn with_after_save(mut self, hook: AfterSave) -> Self {
+        self.after_save = Some(hook);
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    pub fn backend(&self) -> &dyn CryptoBackend {
+        self.backend.as_ref()
+    }
+
+    pub fn exists(&self) -> bool {
+        self.backend.exists(&self.file)
+    }
+
+    /// Desencripta y parsea el documento; falla si el archivo no existe
+    pub fn load(&self) -> Result<Value> {
+        if !self.exists() {
+            return Err(Error::StoreNotFound(self.file.clone()).into());
+        }
+
+        self.backend.check()?;
+        let decrypted_content = self.backend.decrypt(&self.file)?;
+        if decrypted_content.trim().is_empty() {
+            return Ok(Value::Mapping(serde_yaml::Mapping::new()));
+        }
+        serde_yaml::from_str(&decrypted_content).context("No se pudo parsear el contenido YAML")
+    }
+
+    /// Como [`Store::load`], pero devuelve un documento vacío si el archivo no existe
+    pub fn load_or_default(&self) -> Result<Value> {
+        if self.exists() {
+            self.load()
+        } else {
+            info!("Creando nuevo archivo de secretos");
+            Ok(Value::Mapping(serde_yaml::Mapping::new()))
+        }
+    }
+
+    /// Serializa y encripta el documento en el archivo de secretos
+    pub fn save(&self, data: &Value) -> Result<()> {
+        self.backend.check()?;
+
+        // Crear directorio si no existe
+        fs::create_dir_all(&self.dir).context("No se pudo crear el directorio de secretos")?;
+
+        let updated_yaml = serde_yaml::to_string(data).context("No se pudo serializar el YAML")?;
+        debug!("YAML actualizado");
+
+        self.backend.encrypt(&self.file, &updated_yaml)?;
+        debug!("Archivo encriptado y guardado");
+        if let Some(hook) = &self.after_save {
+            hook();
+        }
+        Ok(())
+    }
+}
+
+/// Almacén en claro con el documento `yaml` en un directorio temporal, para
+/// los tests de los módulos; se borra al soltar el [`tempfile::TempDir`]
+#[cfg(test)]
+pub(crate) fn memory_store(yaml: &str) -> (tempfile::TempDir, Store) {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let store = Store::new(
+        temp_dir.path(),
+        temp_dir.path().join("secrets.yml"),
+        Box::new(crate::backend::PlaintextBackend::new()),
+    );
+    store.save(&serde_yaml::from_str(yaml).unwrap()).unwrap();
+    (temp_dir, store)
+}
@@ -0,0 +1,306 @@
+//! Credenciales de servicios de systemd.
+//!
+//! `crypta systemd-creds export` entrega los secretos a una unidad como
+//! credenciales: en claro en un directorio privado que la unidad carga con
+//! `LoadCredential=`, o encriptados con `systemd-creds encrypt` para
+//! `LoadCredentialEncrypted=`/`SetCredentialEncrypted=`. En ambos casos se
+//! escribe por stdout el drop-in `[Service]` que hay que añadir a la unidad.
+//!
+//! Dentro del servicio, `crypta systemd-creds exec` lee las credenciales de
+//! `$CREDENTIALS_DIRECTORY` y las pasa como variables de entorno al comando,
+//! sin necesitar acceso al almacén.
+
+use crate::env::{self, EnvSpec};
+use crate::error::Error;
+use crate::export::{self, ExportOptions};
+use crate::materialize;
+use crate::secrets;
+use crate::store::Store;
+use anyhow::{Context, Result};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{debug, info, warn};
+
+/// Directorio donde systemd busca credenciales en tiempo de ejecución
+pub const CREDSTORE_DIR: &str = "/run/credstore";
+
+/// Opciones de [`export`]
+#[derive(Debug, Default, Clone)]
+pub struct CredsOptions {
+    /// Encripta las credenciales con `systemd-creds encrypt`
+    pub encrypt: bool,
+    /// Clave con la que encripta `systemd-creds` (`host`, `tpm2`, `auto`…)
+    pub with_key: Option<String>,
+    /// Directorio de las credenciales; al encriptar, sin él los blobs van
+    /// en el propio drop-in
+    pub output: Option<PathBuf>,
+}
+
+/// Nombre completo de la unidad: `myapp` → `myapp.service`
+pub fn unit_name(unit: &str) -> String {
+    if unit.contains('.') {
+        unit.to_string()
+    } else {
+        format!("{}.service", unit)
+    }
+}
+
+/// Directorio por defecto de las credenciales en claro de `unit`:
+/// `/run/credstore/UNIT` como root o, para las unidades de usuario, bajo
+/// [`materialize::runtime_dir`]
+pub fn default_dir(unit: &str) -> PathBuf {
+    // SAFETY: geteuid no tiene precondiciones
+    #[cfg(unix)]
+    if unsafe { libc::geteuid() } == 0 {
+        return Path::new(CREDSTORE_DIR).join(unit);
+    }
+    materialize::runtime_dir().join("credstore").join(unit)
+}
+
+/// Comprueba que `name` se puede usar como nombre de credencial: es un
+/// nombre de archivo y no contiene `:`, que separa el nombre de la ruta en
+/// `LoadCredential=`
+fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name == "." || name == ".." || name.contains([':', '/']) {
+        anyhow::bail!("'{}' no es un nombre de credencial válido", name);
+    }
+    Ok(())
+}
+
+/// Drop-in `[Service]` para `unit` con las líneas de credenciales
+fn drop_in(unit: &str, lines: &[String]) -> String {
+    let mut out = format!("# /etc/systemd/system/{}.d/crypta.conf\n[Service]\n", unit);
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Encripta `value` con `systemd-creds encrypt --name=NAME`. Con `pretty`
+/// devuelve la línea `SetCredentialEncrypted=` lista para la unidad.
+fn encrypt(name: &str, value: &[u8], with_key: Option<&str>, pretty: bool) -> Result<String> {
+    let mut cmd = Command::new("systemd-creds");
+    cmd.arg("encrypt").arg(format!("--name={}", name));
+    if let Some(key) = with_key {
+        cmd.arg(format!("--with-key={}", key));
+    }
+    if pretty {
+        cmd.arg("--pretty");
+    }
+    let mut child = cmd
+        .args(["-", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Error::BackendMissing("systemd-creds".to_string()).into(),
+            _ => anyhow::Error::new(e).context("No se pudo ejecutar systemd-creds"),
+        })?;
+    child
+        .stdin
+        .take()
+        .context("No se pudo escribir en systemd-creds")?
+        .write_all(value)
+        .context("No se pudo escribir en systemd-creds")?;
+    let output = child
+        .wait_with_output()
+        .context("No se pudo ejecutar systemd-creds")?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::EncryptFailed(error).into());
+    }
+    let blob = String::from_utf8(output.stdout)
+        .context("systemd-creds ha devuelto una credencial que no es UTF-8")?;
+    Ok(blob.trim_end().to_string())
+}
+
+/// Exporta los secretos seleccionados como credenciales de `unit` y escribe
+/// el drop-in por stdout. Los nombres son los de `docker-secrets`
+/// (`db/password` → `db_password`).
+///
+/// En claro, las credenciales se escriben con permisos 0400 en `output` (o
+/// [`default_dir`]), registrado para `crypta cleanup`. Encriptadas, se
+/// escriben como `NAME.cred` en `output` o, sin él, van en el drop-in.
+pub fn export(
+    store: &Store,
+    unit: &str,
+    select: &ExportOptions,
+    options: &CredsOptions,
+) -> Result<()> {
+    let unit = unit_name(unit);
+    info!("Exportando credenciales de systemd para {}", unit);
+    let creds = export::files(&store.load()?, select)?;
+    if creds.is_empty() {
+        anyhow::bail!("No se seleccionó ningún secreto. Usa --keys o --prefix");
+    }
+    for (name, _) in &creds {
+        check_name(name)?;
+    }
+
+    let mut lines = Vec::new();
+    if !options.encrypt {
+        let dir = options.output.clone().unwrap_or_else(|| default_dir(&unit));
+        let dir = materialize::write_dir(&dir, &creds)?;
+        for (name, _) in &creds {
+            lines.push(format!(
+                "LoadCredential={}:{}",
+                name,
+                dir.join(name).display()
+            ));
+        }
+        eprintln!(
+            "📦 {} credenciales escritas en {} (elimínalas con 'crypta cleanup')",
+            creds.len(),
+            dir.display()
+        );
+    } else if let Some(dir) = &options.output {
+        fs::create_dir_all(dir).with_context(|| format!("No se pudo crear {}", dir.display()))?;
+        let dir = dir
+            .canonicalize()
+            .with_context(|| format!("No se pudo resolver {}", dir.display()))?;
+        for (name, value) in &creds {
+            let blob = encrypt(name, value, options.with_key.as_deref(), false)?;
+            let path = dir.join(format!("{}.cred", name));
+            secrets::write_private(&path, format!("{}\n", blob).as_bytes())?;
+            lines.push(format!(
+                "LoadCredentialEncrypted={}:{}",
+                name,
+                path.display()
+            ));
+        }
+        eprintln!(
+            "🔐 {} credenciales encriptadas en {}",
+            creds.len(),
+            dir.display()
+        );
+    } else {
+        for (name, value) in &creds {
+            lines.push(encrypt(name, value, options.with_key.as_deref(), true)?);
+        }
+    }
+    debug!("{} líneas de credenciales para {}", lines.len(), unit);
+
+    print!("{}", drop_in(&unit, &lines));
+    Ok(())
+}
+
+/// Lee las credenciales de `dir` (`$CREDENTIALS_DIRECTORY`) ordenadas por
+/// nombre
+pub fn read_dir(dir: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let entries =
+        fs::read_dir(dir).with_context(|| format!("No se pudo leer {}", dir.display()))?;
+    let mut creds = Vec::new();
+    for entry in entries {
+        let entry = entry.with_context(|| format!("No se pudo leer {}", dir.display()))?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let value = fs::read(entry.path())
+            .with_context(|| format!("No se pudo leer la credencial '{}'", name))?;
+        creds.push((name, value));
+    }
+    creds.sort();
+    Ok(creds)
+}
+
+/// Variables de entorno con las credenciales: con `all`, todas con el
+/// nombre derivado (`db_password` → `DB_PASSWORD`); las de `specs`
+/// (`VAR=NOMBRE`) tienen prioridad. Las binarias se omiten con `all` y son
+/// un error si se piden explícitamente.
+pub fn collect(
+    creds: &[(String, Vec<u8>)],
+    specs: &[EnvSpec],
+    all: bool,
+) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+
+    if all {
+        for (name, value) in creds {
+            match std::str::from_utf8(value) {
+                Ok(value) => vars.push((env::var_name(name), value.to_string())),
+                Err(_) => warn!("Omitiendo '{}': la credencial es binaria", name),
+            }
+        }
+    }
+
+    for spec in specs {
+        let (_, value) = creds
+            .iter()
+            .find(|(name, _)| *name == spec.key)
+            .with_context(|| format!("La credencial '{}' no existe", spec.key))?;
+        let value = String::from_utf8(value.clone())
+            .map_err(|_| anyhow::anyhow!("La credencial '{}' es binaria", spec.key))?;
+        vars.retain(|(var, _)| *var != spec.var);
+        vars.push((spec.var.clone(), value));
+    }
+
+    Ok(vars)
+}
+
+/// Ejecuta `command` con las credenciales de `dir` como variables de
+/// entorno, igual que `crypta exec` con los secretos del almacén
+pub fn exec(dir: &Path, specs: &[EnvSpec], all: bool, command: &[String]) -> Result<()> {
+    let vars = collect(&read_dir(dir)?, specs, all)?;
+    if vars.is_empty() {
+        anyhow::bail!("No se seleccionó ninguna credencial. Usa --env o --all");
+    }
+    env::exec_with(vars, command)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_name() {
+        assert_eq!(unit_name("myapp"), "myapp.service");
+        assert_eq!(unit_name("myapp.service"), "myapp.service");
+        assert_eq!(unit_name("backup.timer"), "backup.timer");
+    }
+
+    #[test]
+    fn test_check_name() {
+        assert!(check_name("db_password").is_ok());
+        assert!(check_name("api.key").is_ok());
+        assert!(check_name("a:b").is_err());
+        assert!(check_name("..").is_err());
+        assert!(check_name("").is_err());
+    }
+
+    #[test]
+    fn test_drop_in() {
+        let lines = vec!["LoadCredential=token:/run/credstore/app.service/token".to_string()];
+        assert_eq!(
+            drop_in("app.service", &lines),
+            "# /etc/systemd/system/app.service.d/crypta.conf\n[Service]\n\
+             LoadCredential=token:/run/credstore/app.service/token\n"
+        );
+    }
+
+    #[test]
+    fn test_collect() {
+        let creds = vec![
+            ("db_password".to_string(), b"s3cret".to_vec()),
+            ("cert".to_string(), vec![0xff, 0x00]),
+            ("token".to_string(), b"t0ken".to_vec()),
+        ];
+        let specs = [EnvSpec::parse("TOKEN_OVERRIDE=token").unwrap()];
+        let vars = collect(&creds, &specs, true).unwrap();
+        assert_eq!(
+            vars,
+            [
+                ("DB_PASSWORD".to_string(), "s3cret".to_string()),
+                ("TOKEN".to_string(), "t0ken".to_string()),
+                ("TOKEN_OVERRIDE".to_string(), "t0ken".to_string()),
+            ]
+        );
+
+        assert!(collect(&creds, &[EnvSpec::parse("cert").unwrap()], false).is_err());
+        assert!(collect(&creds, &[EnvSpec::parse("nada").unwrap()], false).is_err());
+    }
+}
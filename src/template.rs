@@ -0,0 +1,204 @@
+use crate::error::{self, Error};
+use crate::secrets;
+use crate::store::Store;
+use anyhow::{Context, Result};
+use serde_yaml::Value;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+const OPEN: &str = "{{";
+const CLOSE: &str = "}}";
+const FUNCTION: &str = "secret";
+
+/// Marcador `{{ secret "CLAVE" }}` encontrado en una plantilla
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Placeholder<'a> {
+    /// Posición del marcador completo (de `{{` a `}}`) en la plantilla
+    start: usize,
+    end: usize,
+    key: &'a str,
+}
+
+/// Busca los marcadores de secretos. Las expresiones `{{ ... }}` que no usan
+/// `secret` se dejan tal cual, para no romper plantillas de otras herramientas.
+fn placeholders(template: &str) -> Result<Vec<Placeholder<'_>>> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+
+    while let Some(pos) = template[offset..].find(OPEN) {
+        let start = offset + pos;
+        let inner_start = start + OPEN.len();
+        let Some(len) = template[inner_start..].find(CLOSE) else {
+            break;
+        };
+        let end = inner_start + len + CLOSE.len();
+        let inner = template[inner_start..inner_start + len].trim();
+
+        if let Some(arg) = inner.strip_prefix(FUNCTION) {
+            if arg.is_empty() || arg.starts_with(char::is_whitespace) {
+                let line = template[..start].matches('\n').count() + 1;
+                let key = parse_key(arg.trim()).with_context(|| {
+                    format!(
+                        "Marcador no válido en la línea {}: {{{{ {} }}}}",
+                        line, inner
+                    )
+                })?;
+                found.push(Placeholder { start, end, key });
+            }
+        }
+        offset = end;
+    }
+
+    Ok(found)
+}
+
+fn parse_key(arg: &str) -> Result<&str> {
+    let key = arg
+        .strip_prefix('"')
+        .and_then(|a| a.strip_suffix('"'))
+        .filter(|k| !k.is_empty() && !k.contains('"'))
+        .context("Usa {{ secret \"CLAVE\" }}")?;
+    Ok(key)
+}
+
+/// Claves referenciadas en la plantilla, sin repetir y en orden de aparición
+pub fn keys(template: &str) -> Result<Vec<String>> {
+    let mut keys: Vec<String> = Vec::new();
+    for placeholder in placeholders(template)? {
+        if !keys.iter().any(|k| k == placeholder.key) {
+            keys.push(placeholder.key.to_string());
+        }
+    }
+    Ok(keys)
+}
+
+/// Sustituye los marcadores por los valores del documento ya desencriptado.
+///
+/// Falla si alguna clave no existe, indicando todas las que faltan.
+pub fn render(template: &str, doc: &Value) -> Result<String> {
+    check(template, doc)?;
+
+    let mut output = String::with_capacity(template.len());
+    let mut last = 0;
+    for placeholder in placeholders(template)? {
+        output.push_str(&template[last..placeholder.start]);
+        output.push_str(&secrets::text(doc, placeholder.key)?);
+        last = placeholder.end;
+    }
+    output.push_str(&template[last..]);
+    Ok(output)
+}
+
+/// Comprueba que todas las claves de la plantilla existen y las devuelve
+pub fn check(template: &str, doc: &Value) -> Result<Vec<String>> {
+    let keys = keys(template)?;
+    let mut missing = Vec::new();
+    for key in &keys {
+        match secrets::text(doc, key) {
+            Ok(_) => {}
+            Err(e) if matches!(error::find(&e), Some(Error::NotFound(_))) => missing.push(key),
+            // Binarios, valores estructurados…: el error dice qué pasa
+            Err(e) => return Err(e),
+        }
+    }
+
+    if let Some(first) = missing.first() {
+        let list = missing
+            .iter()
+            .map(|k| k.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(anyhow::Error::new(Error::NotFound(first.to_string()))
+            .context(format!("La plantilla usa claves que no existen: {}", list)));
+    }
+    Ok(keys)
+}
+
+/// Renderiza una plantilla en `output` (permisos 0600) o por stdout. Con
+/// `check_only` solo valida que todas las claves existen.
+pub fn render_file(
+    store: &Store,
+    template: &Path,
+    output: Option<&Path>,
+    check_only: bool,
+) -> Result<()> {
+    info!("Renderizando plantilla {}", template.display());
+    let content = fs::read_to_string(template)
+        .with_context(|| format!("No se pudo leer la plantilla {}", template.display()))?;
+    let doc = store.load()?;
+
+    if check_only {
+        let keys = check(&content, &doc)?;
+        println!(
+            "✅ Plantilla válida: {} ({} claves)",
+            template.display(),
+            keys.len()
+        );
+        return Ok(());
+    }
+
+    let rendered = render(&content, &doc)?;
+    match output {
+        Some(path) => {
+            secrets::write_private(path, rendered.as_bytes())?;
+            println!("📝 Plantilla renderizada en {}", path.display());
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc() -> Value {
+        serde_yaml::from_str("DB_PASSWORD: s3cret\nprod:\n  api: k3y\n").unwrap()
+    }
+
+    #[test]
+    fn test_render_replaces_placeholders() {
+        let template = "password={{ secret \"DB_PASSWORD\" }}\napi={{secret \"prod/api\"}}\n";
+        assert_eq!(
+            render(template, &doc()).unwrap(),
+            "password=s3cret\napi=k3y\n"
+        );
+    }
+
+    #[test]
+    fn test_other_expressions_are_kept() {
+        let template = "{{ .Values.name }} {{ secrets }} {{ secret \"DB_PASSWORD\" }}";
+        assert_eq!(
+            render(template, &doc()).unwrap(),
+            "{{ .Values.name }} {{ secrets }} s3cret"
+        );
+    }
+
+    #[test]
+    fn test_missing_keys_are_reported() {
+        let template = "{{ secret \"A\" }} {{ secret \"DB_PASSWORD\" }} {{ secret \"B\" }}";
+        let err = render(template, &doc()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "La plantilla usa claves que no existen: A, B"
+        );
+        assert_eq!(crate::error::exit_code(&err), 2);
+    }
+
+    #[test]
+    fn test_other_errors_are_not_reported_as_missing() {
+        let err = check("{{ secret \"prod\" }} {{ secret \"A\" }}", &doc()).unwrap_err();
+        assert!(err.to_string().contains("grupo de secretos"));
+        assert_ne!(crate::error::exit_code(&err), 2);
+    }
+
+    #[test]
+    fn test_invalid_placeholder() {
+        assert!(keys("x\n{{ secret DB_PASSWORD }}").is_err());
+        assert_eq!(
+            keys("{{ secret \"A\" }}{{ secret \"A\" }}").unwrap(),
+            vec!["A"]
+        );
+    }
+}
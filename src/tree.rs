@@ -0,0 +1,371 @@
+use crate::entry::{Entry, META_KEY, VALUE_KEY};
+use anyhow::Result;
+use serde_yaml::{Mapping, Value};
+
+/// Separador de las claves con ruta (`prod/db/password`)
+pub const SEPARATOR: char = '/';
+
+/// Separador de los campos de un valor estructurado (`DB_CONFIG.password`)
+pub const FIELD_SEPARATOR: char = '.';
+
+/// Divide una clave en sus segmentos, ignorando separadores repetidos o finales
+pub fn segments(key: &str) -> Vec<&str> {
+    key.split(SEPARATOR).filter(|s| !s.is_empty()).collect()
+}
+
+/// Indica si un valor es un secreto (hoja) y no un grupo de secretos
+pub fn is_leaf(value: &Value) -> bool {
+    Entry::is_entry(value)
+}
+
+/// Busca una clave en el documento.
+///
+/// Primero se busca la clave literal en el nivel superior (almacenes planos
+/// antiguos con claves como `prod/db`) y después se recorre la ruta anidada.
+pub fn get<'a>(doc: &'a Value, key: &str) -> Option<&'a Value> {
+    if let Some(value) = doc.get(key) {
+        return Some(value);
+    }
+
+    let mut node = doc;
+    for segment in segments(key) {
+        node = node.as_mapping()?.get(segment)?;
+    }
+    Some(node)
+}
+
+/// Inserta o reemplaza el valor de una clave, creando los grupos intermedios
+pub fn set(doc: &mut Value, key: &str, value: Value) -> Result<()> {
+    let parts = segments(key);
+    if parts.is_empty() {
+        anyhow::bail!("La clave no puede estar vacía");
+    }
+    if parts.iter().any(|p| *p == VALUE_KEY || *p == META_KEY) {
+        anyhow::bail!(
+            "La clave '{}' usa un nombre reservado ({} o {})",
+            key,
+            VALUE_KEY,
+            META_KEY
+        );
+    }
+
+    if !doc.is_mapping() {
+        *doc = Value::Mapping(Mapping::new());
+    }
+
+    // Las claves planas existentes se actualizan en su sitio
+    if let Some(map) = doc.as_mapping_mut() {
+        if map.get(key).is_some_and(is_leaf) {
+            map.insert(Value::String(key.to_string()), value);
+            return Ok(());
+        }
+    }
+
+    let (last, parents) = parts.split_last().expect("segmentos no vacíos");
+    let mut node = doc;
+    let mut path = String::new();
+    for segment in parents {
+        if !path.is_empty() {
+            path.push(SEPARATOR);
+        }
+        path.push_str(segment);
+
+        let map = node.as_mapping_mut().expect("los grupos son mappings");
+        let child = map
+            .entry(Value::String(segment.to_string()))
+            .or_insert_with(|| Value::Mapping(Mapping::new()));
+        if is_leaf(child) {
+            anyhow::bail!("'{}' es un secreto y no puede contener otras claves", path);
+        }
+        node = child;
+    }
+
+    let map = node.as_mapping_mut().expect("los grupos son mappings");
+    if map.get(*last).is_some_and(|v| !is_leaf(v)) {
+        anyhow::bail!(
+            "'{}' es un grupo de secretos; elimínalo con 'crypta delete -r {}'",
+            key,
+            key
+        );
+    }
+    map.insert(Value::String(last.to_string()), value);
+    Ok(())
+}
+
+/// Elimina una clave y devuelve su valor. Eliminar un grupo requiere `recursive`.
+///
+/// Los grupos que quedan vacíos tras la eliminación también se eliminan.
+pub fn remove(doc: &mut Value, key: &str, recursive: bool) -> Result<Option<Value>> {
+    if let Some(map) = doc.as_mapping_mut() {
+        if let Some(value) = map.get(key) {
+            if !is_leaf(value) && !recursive {
+                anyhow::bail!(
+                    "'{}' es un grupo de secretos; usa 'crypta delete -r {}'",
+                    key,
+                    key
+                );
+            }
+            return Ok(map.remove(key));
+        }
+    }
+
+    let parts = segments(key);
+    if parts.is_empty() {
+        anyhow::bail!("La clave no puede estar vacía");
+    }
+    remove_path(doc, &parts, key, recursive)
+}
+
+fn remove_path(
+    node: &mut Value,
+    parts: &[&str],
+    key: &str,
+    recursive: bool,
+) -> Result<Option<Value>> {
+    let Some(map) = node.as_mapping_mut() else {
+        return Ok(None);
+    };
+
+    let (first, rest) = parts.split_first().expect("segmentos no vacíos");
+    if rest.is_empty() {
+        if let Some(value) = map.get(*first) {
+            if !is_leaf(value) && !recursive {
+                anyhow::bail!(
+                    "'{}' es un grupo de secretos; usa 'crypta delete -r {}'",
+                    key,
+                    key
+                );
+            }
+        }
+        return Ok(map.remove(*first));
+    }
+
+    let Some(child) = map.get_mut(*first) else {
+        return Ok(None);
+    };
+    let removed = remove_path(child, rest, key, recursive)?;
+
+    // Eliminar grupos que se han quedado vacíos
+    if child.as_mapping().is_some_and(Mapping::is_empty) {
+        map.remove(*first);
+    }
+    Ok(removed)
+}
+
+/// Devuelve las rutas completas de todos los secretos bajo `prefix`
+/// (todas si `prefix` está vacío), en el orden del documento
+pub fn leaves(doc: &Value, prefix: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_leaves(doc, "", &mut paths);
+
+    let prefix = segments(prefix).join(&SEPARATOR.to_string());
+    if prefix.is_empty() {
+        return paths;
+    }
+    let subtree = format!("{}{}", prefix, SEPARATOR);
+    paths
+        .into_iter()
+        .filter(|p| *p == prefix || p.starts_with(&subtree))
+        .collect()
+}
+
+/// Ruta de `key` relativa a `base` (`prod/db/password` bajo `prod` →
+/// `db/password`). Si `key` es el propio `base` queda su último segmento,
+/// para que un prefijo que nombra un secreto no dé un nombre vacío.
+pub fn relative<'a>(key: &'a str, base: &str) -> &'a str {
+    let rest = key
+        .strip_prefix(base.trim_matches(SEPARATOR))
+        .unwrap_or(key)
+        .trim_start_matches(SEPARATOR);
+    if !rest.is_empty() {
+        return rest;
+    }
+    key.trim_end_matches(SEPARATOR)
+        .rsplit(SEPARATOR)
+        .next()
+        .unwrap_or(key)
+}
+
+/// Nombres de los hijos directos de `prefix` (de la raíz si está vacío):
+/// los secretos por su nombre y los grupos terminados en `/`, ordenados
+pub fn children(doc: &Value, prefix: &str) -> Vec<String> {
+    let base = segments(prefix).join(&SEPARATOR.to_string());
+    leaves(doc, &base)
+        .iter()
+        .filter_map(|leaf| {
+            let rest = if base.is_empty() {
+                leaf.as_str()
+            } else {
+                leaf.strip_prefix(base.as_str())?.strip_prefix(SEPARATOR)?
+            };
+            Some(match rest.split_once(SEPARATOR) {
+                Some((group, _)) => format!("{}{}", group, SEPARATOR),
+                None => rest.to_string(),
+            })
+        })
+        .collect::<std::collections::BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+fn collect_leaves(node: &Value, path: &str, paths: &mut Vec<String>) {
+    match node.as_mapping() {
+        Some(map) if !is_leaf(node) => {
+            for (key, value) in map {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                let child = if path.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{}{}{}", path, SEPARATOR, key)
+                };
+                collect_leaves(value, &child, paths);
+            }
+        }
+        _ => {
+            if !path.is_empty() {
+                paths.push(path.to_string());
+            }
+        }
+    }
+}
+
+/// Busca un campo dentro de un valor estructurado (`host`, `replicas.0.host`).
+/// Los segmentos numéricos indexan listas.
+pub fn field<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let mut node = value;
+    for segment in path.split(FIELD_SEPARATOR) {
+        node = match node {
+            Value::Mapping(map) => map.get(segment)?,
+            Value::Sequence(list) => list.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(node)
+}
+
+/// Convierte un secreto escalar en texto (cadenas, números y booleanos)
+pub fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(yaml: &str) -> Value {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_segments() {
+        assert_eq!(segments("prod/db/password"), vec!["prod", "db", "password"]);
+        assert_eq!(segments("/prod//db/"), vec!["prod", "db"]);
+        assert!(segments("").is_empty());
+    }
+
+    #[test]
+    fn test_get_nested_and_flat() {
+        let d = doc("API_KEY: a\nlegacy/key: b\nprod:\n  db:\n    password: c\n");
+        assert_eq!(get(&d, "API_KEY").and_then(Value::as_str), Some("a"));
+        assert_eq!(get(&d, "legacy/key").and_then(Value::as_str), Some("b"));
+        assert_eq!(
+            get(&d, "prod/db/password").and_then(Value::as_str),
+            Some("c")
+        );
+        assert!(get(&d, "prod/db").unwrap().is_mapping());
+        assert!(get(&d, "prod/db/user").is_none());
+        assert!(get(&d, "API_KEY/nested").is_none());
+    }
+
+    #[test]
+    fn test_set_creates_groups() {
+        let mut d = Value::Null;
+        set(&mut d, "prod/db/password", "secret".into()).unwrap();
+        set(&mut d, "prod/db/user", "admin".into()).unwrap();
+        assert_eq!(
+            serde_yaml::to_string(&d).unwrap(),
+            "prod:\n  db:\n    password: secret\n    user: admin\n"
+        );
+    }
+
+    #[test]
+    fn test_set_updates_flat_key_in_place() {
+        let mut d = doc("legacy/key: old\n");
+        set(&mut d, "legacy/key", "new".into()).unwrap();
+        assert_eq!(serde_yaml::to_string(&d).unwrap(), "legacy/key: new\n");
+    }
+
+    #[test]
+    fn test_set_rejects_leaf_as_group_and_group_as_leaf() {
+        let mut d = doc("prod:\n  db: value\n");
+        assert!(set(&mut d, "prod/db/password", "x".into()).is_err());
+        assert!(set(&mut d, "prod", "x".into()).is_err());
+    }
+
+    #[test]
+    fn test_remove_leaf_prunes_empty_groups() {
+        let mut d = doc("prod:\n  db:\n    password: c\nother: x\n");
+        let removed = remove(&mut d, "prod/db/password", false).unwrap();
+        assert_eq!(removed.as_ref().and_then(Value::as_str), Some("c"));
+        assert_eq!(serde_yaml::to_string(&d).unwrap(), "other: x\n");
+    }
+
+    #[test]
+    fn test_remove_group_requires_recursive() {
+        let mut d = doc("prod:\n  db:\n    password: c\n    user: u\n");
+        assert!(remove(&mut d, "prod/db", false).is_err());
+        assert!(remove(&mut d, "prod/db", true).unwrap().is_some());
+        assert!(get(&d, "prod").is_none());
+    }
+
+    #[test]
+    fn test_entries_with_metadata_are_leaves() {
+        let d = doc("prod:\n  db:\n    _value: c\n    _meta:\n      note: x\n");
+        assert!(is_leaf(get(&d, "prod/db").unwrap()));
+        assert_eq!(leaves(&d, ""), vec!["prod/db"]);
+
+        let mut d = d;
+        assert!(set(&mut d, "prod/db/password", "x".into()).is_err());
+        assert!(set(&mut d, "other/_meta", "x".into()).is_err());
+    }
+
+    #[test]
+    fn test_field() {
+        let d = doc("host: db\nport: 5432\nreplicas:\n  - host: r1\n");
+        assert_eq!(field(&d, "host").and_then(Value::as_str), Some("db"));
+        assert_eq!(
+            field(&d, "replicas.0.host").and_then(Value::as_str),
+            Some("r1")
+        );
+        assert!(field(&d, "replicas.1").is_none());
+        assert!(field(&d, "port.x").is_none());
+    }
+
+    #[test]
+    fn test_leaves_with_prefix() {
+        let d = doc("API_KEY: a\nprod:\n  db:\n    password: c\n  api: d\nproduction: e\n");
+        assert_eq!(
+            leaves(&d, ""),
+            vec!["API_KEY", "prod/db/password", "prod/api", "production"]
+        );
+        assert_eq!(leaves(&d, "prod/"), vec!["prod/db/password", "prod/api"]);
+        assert_eq!(leaves(&d, "prod/db"), vec!["prod/db/password"]);
+    }
+
+    #[test]
+    fn test_children() {
+        let d = doc("API_KEY: a\nprod:\n  db:\n    password: c\n  api: d\nlegacy/token: e\n");
+        assert_eq!(children(&d, ""), vec!["API_KEY", "legacy/", "prod/"]);
+        assert_eq!(children(&d, "prod"), vec!["api", "db/"]);
+        assert_eq!(children(&d, "legacy/"), vec!["token"]);
+        assert!(children(&d, "prod/api").is_empty());
+        assert!(children(&d, "nada").is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::backend;
+use crate::config::{Config, DEFAULT_FILE};
+use crate::git;
+use crate::secrets;
+use crate::store::Store;
+use anyhow::{Context, Result};
+use git2::Repository;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+/// Nombre del vault principal, que vive directamente en el directorio raíz
+pub const DEFAULT_VAULT: &str = "default";
+
+/// Subdirectorio del directorio raíz donde se guardan los vaults con nombre
+const VAULTS_DIR: &str = "vaults";
+
+/// Un almacén de secretos con nombre: su propio archivo, su propio `.sops.yaml`
+/// y, opcionalmente, su propio repositorio git.
+///
+/// El vault `default` es el directorio raíz (`~/.secrets`); el resto viven en
+/// `~/.secrets/vaults/<nombre>/`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vault {
+    name: String,
+    root: PathBuf,
+    dir: PathBuf,
+    file: PathBuf,
+}
+
+impl Vault {
+    /// Vault principal del directorio raíz
+    pub fn default_vault(root: impl Into<PathBuf>) -> Self {
+        let root = root.into();
+        Self {
+            name: DEFAULT_VAULT.to_string(),
+            dir: root.clone(),
+            root,
+            file: PathBuf::from(DEFAULT_FILE),
+        }
+    }
+
+    /// Vault con nombre (no comprueba que exista)
+    pub fn named(root: impl Into<PathBuf>, name: &str) -> Result<Self> {
+        if name == DEFAULT_VAULT {
+            return Ok(Self::default_vault(root));
+        }
+        validate_name(name)?;
+        let root = root.into();
+        Ok(Self {
+            name: name.to_string(),
+            dir: root.join(VAULTS_DIR).join(name),
+            root,
+            file: PathBuf::from(DEFAULT_FILE),
+        })
+    }
+
+    /// Resuelve el vault a usar; los vaults con nombre deben existir
+    pub fn resolve(root: impl Into<PathBuf>, name: Option<&str>) -> Result<Self> {
+        let vault = Self::named(root, name.unwrap_or(DEFAULT_VAULT))?;
+        if !vault.is_default() && !vault.dir.exists() {
+            anyhow::bail!(
+                "El vault '{}' no existe. Créalo con: crypta vault create {}",
+                vault.name,
+                vault.name
+            );
+        }
+        debug!("Usando vault '{}' en {}", vault.name, vault.dir.display());
+        Ok(vault)
+    }
+
+    /// Resuelve el vault con la prioridad de siempre: flag, variable de
+    /// entorno (`CRYPTA_DIR`, `CRYPTA_VAULT`, `CRYPTA_FILE`) y configuración.
+    /// Lo comparten `crypta` y los helpers que se ejecutan sin sus flags.
+    pub fn open(
+        config: &Config,
+        dir: Option<&Path>,
+        name: Option<&str>,
+        file: Option<&Path>,
+    ) -> Result<Self> {
+        let root = config.secrets_dir(dir)?;
+        let env_name = std::env::var("CRYPTA_VAULT").ok().filter(|v| !v.is_empty());
+        let name = config.vault(name.or(env_name.as_deref()));
+        Ok(Self::resolve(root, name)?.with_file(config.secrets_file(file)))
+    }
+
+    /// Almacén del vault con el backend de la configuración
+    pub fn store(&self, config: &Config) -> Result<Store> {
+        Ok(Store::new(
+            self.dir(),
+            self.file(),
+            backend::from_name(&config.backend())?,
+        ))
+    }
+
+    /// Almacén para los servicios que guardan mientras siguen en marcha
+    /// (`serve`, `mount`, `secret-service`): con `sync.auto` sincroniza tras
+    /// cada guardado. Si la sincronización falla solo se avisa, porque los
+    /// cambios ya están guardados.
+    pub fn service_store(&self, config: &Config) -> Result<Store> {
+        let store = self.store(config)?;
+        if !config.sync.auto {
+            return Ok(store);
+        }
+        let git_dir = self.git_dir().to_path_buf();
+        let message = config.sync.message.clone();
+        let git_config = config.git.clone();
+        Ok(store.with_after_save(Box::new(move || {
+            info!("Sincronización automática activada");
+            if let Err(e) = git::sync(&git_dir, Some(&message), &git_config) {
+                eprintln!("⚠️  No se pudo sincronizar el almacén: {:#}", e);
+            }
+        })))
+    }
+
+    /// Sincroniza el repositorio del vault si `sync.auto` está activado
+    pub fn auto_sync(&self, config: &Config) -> Result<()> {
+        if config.sync.auto {
+            info!("Sincronización automática activada");
+            git::sync(self.git_dir(), Some(&config.sync.message), &config.git)?;
+        }
+        Ok(())
+    }
+
+    /// Cambia el archivo de secretos; las rutas relativas son relativas al vault
+    pub fn with_file(mut self, file: impl Into<PathBuf>) -> Self {
+        self.file = file.into();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.name == DEFAULT_VAULT
+    }
+
+    /// Directorio del vault
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Archivo de secretos del vault
+    pub fn file(&self) -> PathBuf {
+        self.dir.join(&self.file)
+    }
+
+    /// Directorio del repositorio git que contiene el vault: el suyo propio si
+    /// lo tiene o, si no, el del directorio raíz
+    pub fn git_dir(&self) -> &Path {
+        if self.dir.join(".git").exists() {
+            &self.dir
+        } else {
+            &self.root
+        }
+    }
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        anyhow::bail!(
+            "Nombre de vault no válido: '{}'. Usa solo letras, números, '-' y '_'",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Devuelve los nombres de todos los vaults, empezando por `default`
+pub fn names(root: &Path) -> Result<Vec<String>> {
+    let mut names = vec![DEFAULT_VAULT.to_string()];
+
+    let vaults_dir = root.join(VAULTS_DIR);
+    if vaults_dir.exists() {
+        let mut named = Vec::new();
+        for entry in fs::read_dir(&vaults_dir).context("No se pudo leer el directorio de vaults")? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                if let Some(name) = entry.file_name().to_str() {
+                    named.push(name.to_string());
+                }
+            }
+        }
+        named.sort();
+        names.extend(named);
+    }
+
+    Ok(names)
+}
+
+/// Crea un vault con su `.sops.yaml`.
+///
+/// Si no se indican destinatarios se usan los del vault principal. Con
+/// `remote` el vault tiene su propio repositorio git con ese `origin`; si no,
+/// se sincroniza junto con el directorio raíz.
+pub fn create(root: &Path, name: &str, recipients: &[String], remote: Option<&str>) -> Result<()> {
+    info!("Creando vault '{}'", name);
+    let vault = Vault::named(root, name)?;
+    if vault.is_default() {
+        anyhow::bail!("El vault '{}' ya existe; usa 'crypta init'", DEFAULT_VAULT);
+    }
+    if vault.dir().exists() {
+        anyhow::bail!("El vault '{}' ya existe: {}", name, vault.dir().display());
+    }
+
+    let recipients = if recipients.is_empty() {
+        backend::sops_config_recipients(&root.join(DEFAULT_FILE)).context(
+            "No se pudieron obtener los destinatarios del vault principal. Usa --recipient",
+        )?
+    } else {
+        recipients.to_vec()
+    };
+
+    fs::create_dir_all(vault.dir()).context("No se pudo crear el directorio del vault")?;
+
+    let age_key_path = root.join("sops/age/key.txt");
+    let sops_config_path = vault.dir().join(".sops.yaml");
+    fs::write(
+        &sops_config_path,
+        secrets::sops_config(&age_key_path.display().to_string(), &recipients),
+    )
+    .context("No se pudo crear el archivo .sops.yaml")?;
+    println!(
+        "📄 Archivo de configuración creado: {}",
+        sops_config_path.display()
+    );
+
+    if let Some(remote) = remote {
+        debug!(
+            "Inicializando repositorio git del vault con remoto {}",
+            remote
+        );
+        let repo = Repository::init(vault.dir()).context("No se pudo crear el repositorio git")?;
+        repo.remote("origin", remote)
+            .context("No se pudo configurar el remoto 'origin'")?;
+
+        // Excluir el vault del repositorio del directorio raíz
+        let gitignore = root.join(".gitignore");
+        let entry = format!("/{}/{}/", VAULTS_DIR, name);
+        let mut content = fs::read_to_string(&gitignore).unwrap_or_default();
+        if !content.lines().any(|l| l == entry) {
+            if !content.is_empty() && !content.ends_with('\n') {
+                content.push('\n');
+            }
+            content.push_str(&entry);
+            content.push('\n');
+            fs::write(&gitignore, content).context("No se pudo actualizar .gitignore")?;
+        }
+        println!("🔄 Repositorio git creado con remoto: {}", remote);
+    }
+
+    println!("✅ Vault '{}' creado en {}", name, vault.dir().display());
+    println!(
+        "💡 Úsalo con: crypta --vault {} set --key CLAVE --value VALOR",
+        name
+    );
+    Ok(())
+}
+
+pub fn list(root: &Path, current: &str) -> Result<()> {
+    info!("Listando vaults");
+    println!("🗄️  Vaults en {}:", root.display());
+    for name in names(root)? {
+        let marker = if name == current { "*" } else { " " };
+        println!("{} {}", marker, name);
+    }
+    Ok(())
+}
+
+/// Elimina un vault y todo su contenido. Si contiene secretos se exige `force`.
+pub fn remove(root: &Path, name: &str, force: bool) -> Result<()> {
+    info!("Eliminando vault '{}'", name);
+    let vault = Vault::resolve(root, Some(name))?;
+    if vault.is_default() {
+        anyhow::bail!("El vault '{}' no se puede eliminar", DEFAULT_VAULT);
+    }
+    if vault.file().exists() && !force {
+        anyhow::bail!(
+            "El vault '{}' contiene secretos. Usa --force para eliminarlo",
+            name
+        );
+    }
+
+    fs::remove_dir_all(vault.dir()).context("No se pudo eliminar el directorio del vault")?;
+    println!("🗑️ Vault '{}' eliminado.", name);
+    Ok(())
+}
@@ -0,0 +1,521 @@
+//! API HTTP compatible con el motor KV v2 de HashiCorp Vault.
+//!
+//! Pensada para desarrollo y tests: los servicios que en producción leen
+//! secretos de Vault pueden apuntar `VAULT_ADDR` a crypta sin cambiar de
+//! cliente. Cada ruta KV (`secret/data/app/db`) es la clave del almacén con
+//! la misma ruta (`app/db`), opcionalmente bajo un prefijo.
+//!
+//! Los datos de un secreto KV son un objeto JSON. Un secreto estructurado
+//! se devuelve tal cual y uno simple como `{"value": "..."}`; al escribir,
+//! ese mismo objeto de un solo campo vuelve a guardarse como valor simple.
+//! crypta no guarda versiones, así que todo secreto existente es la
+//! versión 1.
+
+use crate::entry::Entry;
+use crate::error::{self, Error};
+use crate::materialize;
+use crate::secrets;
+use crate::store::Store;
+use crate::tree;
+use anyhow::{Context, Result};
+use serde_json::{json, Value as Json};
+use serde_yaml::Value;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Dirección por defecto, la misma que usa Vault
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8200";
+
+/// Punto de montaje del motor KV por defecto
+pub const DEFAULT_MOUNT: &str = "secret";
+
+/// Campo con el que se exponen los secretos simples
+pub const VALUE_FIELD: &str = "value";
+
+/// Tamaño máximo del cuerpo de una petición
+const MAX_BODY_LEN: u64 = 1024 * 1024;
+
+/// Petición ya leída del socket
+#[derive(Debug, Clone, Default)]
+pub struct Request {
+    pub method: String,
+    pub url: String,
+    pub token: Option<String>,
+    pub body: Vec<u8>,
+}
+
+/// Respuesta con el estado HTTP y el cuerpo JSON (ninguno para 204)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Option<Json>,
+}
+
+impl Response {
+    fn ok(data: Json) -> Self {
+        Self {
+            status: 200,
+            body: Some(json!({
+                "request_id": "",
+                "lease_id": "",
+                "renewable": false,
+                "lease_duration": 0,
+                "data": data,
+                "wrap_info": null,
+                "warnings": null,
+                "auth": null,
+            })),
+        }
+    }
+
+    fn no_content() -> Self {
+        Self {
+            status: 204,
+            body: None,
+        }
+    }
+
+    fn error(status: u16, errors: &[&str]) -> Self {
+        Self {
+            status,
+            body: Some(json!({ "errors": errors })),
+        }
+    }
+
+    /// Vault responde 404 con la lista de errores vacía
+    fn not_found() -> Self {
+        Self::error(404, &[])
+    }
+}
+
+/// Operación KV v2 sobre una ruta
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    Read,
+    Write,
+    Delete,
+    List,
+}
+
+/// API KV v2 sobre un almacén. El documento se desencripta en cada petición,
+/// así que los cambios hechos con el CLI se ven al momento.
+pub struct Api {
+    store: Store,
+    mount: String,
+    prefix: String,
+    token: String,
+}
+
+impl Api {
+    pub fn new(store: Store, mount: &str, prefix: Option<&str>, token: String) -> Self {
+        Self {
+            store,
+            mount: tree::segments(mount).join("/"),
+            prefix: prefix
+                .map(|p| tree::segments(p).join("/"))
+                .unwrap_or_default(),
+            token,
+        }
+    }
+
+    /// Atiende una petición. Los errores del almacén se devuelven como 500
+    /// con el mensaje en `errors`, igual que Vault.
+    pub fn handle(&self, request: &Request) -> Response {
+        let (path, query) = request
+            .url
+            .split_once('?')
+            .unwrap_or((request.url.as_str(), ""));
+        let Some(path) = path.strip_prefix("/v1/") else {
+            return Response::not_found();
+        };
+        // Los clientes comprueban el estado antes de autenticarse
+        if path == "sys/health" {
+            return Self::health();
+        }
+        if request.token.as_deref() != Some(self.token.as_str()) {
+            return Response::error(403, &["permission denied"]);
+        }
+
+        let Some((operation, key)) = self.route(&request.method, path, query) else {
+            return Response::error(405, &["Operación no admitida por crypta"]);
+        };
+        let result = match operation {
+            Operation::Read => self.read(&key),
+            Operation::Write => self.write(&key, &request.body),
+            Operation::Delete => self.delete(&key),
+            Operation::List => self.list(&key),
+        };
+        result.unwrap_or_else(|e| match error::find(&e) {
+            Some(Error::NotFound(_) | Error::StoreNotFound(_)) => Response::not_found(),
+            _ => Response::error(500, &[&format!("{:#}", e)]),
+        })
+    }
+
+    fn health() -> Response {
+        Response {
+            status: 200,
+            body: Some(json!({
+                "initialized": true,
+                "sealed": false,
+                "standby": false,
+                "version": concat!("crypta-", env!("CARGO_PKG_VERSION")),
+            })),
+        }
+    }
+
+    /// Operación y clave del almacén de una ruta bajo el montaje
+    /// (`data/RUTA` o `metadata/RUTA`)
+    fn route(&self, method: &str, path: &str, query: &str) -> Option<(Operation, String)> {
+        let path = path.strip_prefix(self.mount.as_str())?.strip_prefix('/')?;
+        let (kind, rest) = path.split_once('/').unwrap_or((path, ""));
+        let list = query.split('&').any(|param| param == "list=true");
+        let operation = match (method, kind) {
+            ("GET", "data") => Operation::Read,
+            ("POST" | "PUT", "data") => Operation::Write,
+            ("DELETE", "data" | "metadata") => Operation::Delete,
+            ("LIST", "metadata") => Operation::List,
+            ("GET", "metadata") if list => Operation::List,
+            _ => return None,
+        };
+        let key = tree::segments(&self.prefix)
+            .into_iter()
+            .chain(tree::segments(rest))
+            .collect::<Vec<_>>()
+            .join("/");
+        Some((operation, key))
+    }
+
+    fn load(&self) -> Result<Value> {
+        if !self.store.exists() {
+            return Err(Error::StoreNotFound(self.store.file().to_path_buf()).into());
+        }
+        self.store.load()
+    }
+
+    fn read(&self, key: &str) -> Result<Response> {
+        let doc = self.load()?;
+        let node = tree::get(&doc, key)
+            .filter(|node| !key.is_empty() && tree::is_leaf(node))
+            .ok_or_else(|| Error::NotFound(key.to_string()))?;
+        let entry = Entry::from_node(node)?;
+        Ok(Response::ok(json!({
+            "data": data(&entry)?,
+            "metadata": metadata(&entry),
+        })))
+    }
+
+    fn write(&self, key: &str, body: &[u8]) -> Result<Response> {
+        let body: Json = match serde_json::from_slice(body) {
+            Ok(body) => body,
+            Err(e) => return Ok(Response::error(400, &[&format!("JSON no válido: {}", e)])),
+        };
+        let Some(fields) = body.get("data").and_then(Json::as_object) else {
+            return Ok(Response::error(400, &["Falta el objeto 'data'"]));
+        };
+        if key.is_empty() {
+            return Ok(Response::error(400, &["Falta la ruta del secreto"]));
+        }
+
+        let mut doc = self.store.load_or_default()?;
+        let current = match tree::get(&doc, key) {
+            Some(node) if !tree::is_leaf(node) => {
+                return Ok(Response::error(
+                    400,
+                    &[&format!("'{}' es un grupo de secretos", key)],
+                ))
+            }
+            Some(_) => 1,
+            None => 0,
+        };
+        let cas = body.pointer("/options/cas").and_then(Json::as_u64);
+        if cas.is_some_and(|cas| cas != current) {
+            return Ok(Response::error(
+                400,
+                &["check-and-set parameter did not match the current version"],
+            ));
+        }
+
+        let value = match fields.get(VALUE_FIELD) {
+            Some(Json::String(text)) if fields.len() == 1 => Value::String(text.clone()),
+            _ => serde_yaml::to_value(fields).context("No se pudo convertir el secreto")?,
+        };
+        info!("Guardando '{}' desde la API de Vault", key);
+        secrets::insert(&mut doc, key, value)?;
+        self.store.save(&doc)?;
+
+        let entry = secrets::find(&doc, key)?;
+        Ok(Response::ok(metadata(&entry)))
+    }
+
+    fn delete(&self, key: &str) -> Result<Response> {
+        let mut doc = self.load()?;
+        if key.is_empty() || !tree::get(&doc, key).is_some_and(tree::is_leaf) {
+            return Err(Error::NotFound(key.to_string()).into());
+        }
+        info!("Eliminando '{}' desde la API de Vault", key);
+        tree::remove(&mut doc, key, false)?;
+        self.store.save(&doc)?;
+        Ok(Response::no_content())
+    }
+
+    fn list(&self, key: &str) -> Result<Response> {
+        let doc = self.load()?;
+        let keys = tree::children(&doc, key);
+        if keys.is_empty() {
+            return Err(Error::NotFound(key.to_string()).into());
+        }
+        Ok(Response::ok(json!({ "keys": keys })))
+    }
+}
+
+/// Datos KV de un secreto: el objeto si es estructurado y `{"value": ...}`
+/// si no. Los binarios que no son texto se devuelven en base64.
+fn data(entry: &Entry) -> Result<Json> {
+    if entry.value.is_mapping() {
+        return serde_json::to_value(&entry.value)
+            .context("El secreto no se puede expresar en JSON");
+    }
+    let value = match entry.text() {
+        Some(text) => Json::String(text),
+        None => {
+            serde_json::to_value(&entry.value).context("El secreto no se puede expresar en JSON")?
+        }
+    };
+    Ok(json!({ VALUE_FIELD: value }))
+}
+
+fn metadata(entry: &Entry) -> Json {
+    let created = entry
+        .meta
+        .created
+        .or(entry.meta.updated)
+        .map(|date| date.to_rfc3339())
+        .unwrap_or_default();
+    json!({
+        "created_time": created,
+        "custom_metadata": null,
+        "deletion_time": "",
+        "destroyed": false,
+        "version": 1,
+    })
+}
+
+/// Archivo de token por defecto: `vault-token` en [`materialize::runtime_dir`]
+pub fn default_token_file() -> PathBuf {
+    materialize::runtime_dir().join("vault-token")
+}
+
+/// Lee el token de `path`; si el archivo no existe genera uno aleatorio y lo
+/// guarda con permisos 0600
+pub fn load_token(path: &Path) -> Result<String> {
+    if path.exists() {
+        let token = std::fs::read_to_string(path)
+            .with_context(|| format!("No se pudo leer el token de {}", path.display()))?;
+        let token = token.trim();
+        if token.is_empty() {
+            anyhow::bail!("El archivo de token {} está vacío", path.display());
+        }
+        return Ok(token.to_string());
+    }
+
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        let mut builder = std::fs::DirBuilder::new();
+        builder.recursive(true);
+        #[cfg(unix)]
+        std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+        builder
+            .create(dir)
+            .with_context(|| format!("No se pudo crear {}", dir.display()))?;
+    }
+    let token = format!("hvs.{}", secrets::password_string(24, false)?);
+    secrets::write_private(path, token.as_bytes())?;
+    println!("🔑 Nuevo token guardado en {}", path.display());
+    Ok(token)
+}
+
+/// Sirve la API en `listen` hasta que se termine el proceso. El token llega
+/// en la cabecera `X-Vault-Token` o como `Authorization: Bearer`.
+pub fn serve(api: Api, listen: &str) -> Result<()> {
+    let server = tiny_http::Server::http(listen)
+        .map_err(|e| anyhow::anyhow!("No se pudo escuchar en {}: {}", listen, e))?;
+    let address = server
+        .server_addr()
+        .to_ip()
+        .map_or_else(|| listen.to_string(), |addr| addr.to_string());
+    println!(
+        "🗄️  API compatible con Vault KV v2 en http://{} (montaje '{}/')",
+        address, api.mount
+    );
+    println!("VAULT_ADDR=http://{}; export VAULT_ADDR;", address);
+
+    for mut request in server.incoming_requests() {
+        let mut body = Vec::new();
+        if let Err(e) = request
+            .as_reader()
+            .take(MAX_BODY_LEN)
+            .read_to_end(&mut body)
+        {
+            warn!("No se pudo leer la petición: {}", e);
+            continue;
+        }
+        let header = |name: &'static str| {
+            request
+                .headers()
+                .iter()
+                .find(|h| h.field.equiv(name))
+                .map(|h| h.value.as_str().to_string())
+        };
+        let token = header("X-Vault-Token").or_else(|| {
+            header("Authorization")
+                .and_then(|value| value.strip_prefix("Bearer ").map(str::to_string))
+        });
+        let parsed = Request {
+            method: request.method().as_str().to_ascii_uppercase(),
+            url: request.url().to_string(),
+            token,
+            body,
+        };
+
+        let response = api.handle(&parsed);
+        info!("{} {} → {}", parsed.method, parsed.url, response.status);
+        let body = response
+            .body
+            .map(|body| body.to_string())
+            .unwrap_or_default();
+        let content_type =
+            tiny_http::Header::from_bytes("Content-Type", "application/json").expect("cabecera");
+        let reply = tiny_http::Response::from_string(body)
+            .with_status_code(response.status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(reply) {
+            warn!("No se pudo enviar la respuesta: {}", e);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::memory_store;
+    use tempfile::TempDir;
+
+    fn api(yaml: &str) -> (TempDir, Api) {
+        let (temp_dir, store) = memory_store(yaml);
+        let api = Api::new(store, "secret", None, "t0ken".to_string());
+        (temp_dir, api)
+    }
+
+    fn request(method: &str, url: &str, body: &str) -> Request {
+        Request {
+            method: method.to_string(),
+            url: url.to_string(),
+            token: Some("t0ken".to_string()),
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_read_simple_and_structured() {
+        let (_temp_dir, api) = api("app:\n  API_KEY: abc123\n  db:\n    _value:\n      user: bob\n      port: 5432\n    _meta: {}\n");
+        let response = api.handle(&request("GET", "/v1/secret/data/app/API_KEY", ""));
+        assert_eq!(response.status, 200);
+        let body = response.body.unwrap();
+        assert_eq!(body["data"]["data"], json!({ "value": "abc123" }));
+        assert_eq!(body["data"]["metadata"]["version"], 1);
+
+        let body = api
+            .handle(&request("GET", "/v1/secret/data/app/db", ""))
+            .body
+            .unwrap();
+        assert_eq!(body["data"]["data"], json!({ "user": "bob", "port": 5432 }));
+
+        // Un grupo no es un secreto
+        let response = api.handle(&request("GET", "/v1/secret/data/app", ""));
+        assert_eq!(response, Response::not_found());
+    }
+
+    #[test]
+    fn test_token_is_required() {
+        let (_temp_dir, api) = api("API_KEY: abc123\n");
+        let mut anonymous = request("GET", "/v1/secret/data/API_KEY", "");
+        anonymous.token = None;
+        assert_eq!(api.handle(&anonymous).status, 403);
+        anonymous.token = Some("otro".to_string());
+        assert_eq!(api.handle(&anonymous).status, 403);
+        anonymous.url = "/v1/sys/health".to_string();
+        assert_eq!(api.handle(&anonymous).status, 200);
+    }
+
+    #[test]
+    fn test_write_and_cas() {
+        let (_temp_dir, api) = api("API_KEY: abc123\n");
+        let write = |path: &str, body: &str| {
+            api.handle(&request("POST", &format!("/v1/secret/data/{}", path), body))
+        };
+        assert_eq!(write("app/db", r#"{"data": {"user": "bob"}}"#).status, 200);
+        assert_eq!(
+            write("API_KEY", r#"{"data": {"value": "n3w"}}"#).status,
+            200
+        );
+        assert_eq!(
+            write(
+                "API_KEY",
+                r#"{"data": {"value": "x"}, "options": {"cas": 0}}"#
+            )
+            .status,
+            400
+        );
+        assert_eq!(write("app", r#"{"data": {"value": "x"}}"#).status, 400);
+        assert_eq!(write("app/x", r#"{"value": "x"}"#).status, 400);
+
+        let doc = api.store.load().unwrap();
+        assert_eq!(secrets::text(&doc, "API_KEY").unwrap(), "n3w");
+        let db = secrets::find(&doc, "app/db").unwrap();
+        assert_eq!(db.value["user"], Value::from("bob"));
+    }
+
+    #[test]
+    fn test_list_and_delete() {
+        let (_temp_dir, api) = api("API_KEY: a\napp:\n  TOKEN: b\n  db:\n    password: c\n");
+        let list = |url: &str| api.handle(&request("LIST", url, ""));
+        let body = list("/v1/secret/metadata/").body.unwrap();
+        assert_eq!(body["data"]["keys"], json!(["API_KEY", "app/"]));
+        let body = api
+            .handle(&request("GET", "/v1/secret/metadata/app?list=true", ""))
+            .body
+            .unwrap();
+        assert_eq!(body["data"]["keys"], json!(["TOKEN", "db/"]));
+        assert_eq!(list("/v1/secret/metadata/nada"), Response::not_found());
+
+        let response = api.handle(&request("DELETE", "/v1/secret/data/app/TOKEN", ""));
+        assert_eq!(response, Response::no_content());
+        let response = api.handle(&request("DELETE", "/v1/secret/data/app/TOKEN", ""));
+        assert_eq!(response, Response::not_found());
+    }
+
+    #[test]
+    fn test_prefix_and_mount() {
+        let (_temp_dir, store) = memory_store("dev:\n  API_KEY: abc123\n");
+        let api = Api::new(store, "/kv/", Some("dev/"), "t0ken".to_string());
+        let response = api.handle(&request("GET", "/v1/kv/data/API_KEY", ""));
+        assert_eq!(response.status, 200);
+        let response = api.handle(&request("GET", "/v1/secret/data/API_KEY", ""));
+        assert_eq!(response.status, 405);
+    }
+
+    #[test]
+    fn test_load_token() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("run/vault-token");
+        let token = load_token(&path).unwrap();
+        assert!(token.starts_with("hvs."));
+        assert_eq!(load_token(&path).unwrap(), token);
+
+        std::fs::write(&path, "mi-token\n").unwrap();
+        assert_eq!(load_token(&path).unwrap(), "mi-token");
+        std::fs::write(&path, "\n").unwrap();
+        assert!(load_token(&path).is_err());
+    }
+}
@@ -0,0 +1,86 @@
+mod common;
+
+use common::memory_store;
+use crypta::backend::{self, AgeBackend, CryptoBackend};
+use crypta::secrets;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_backend_from_name() {
+    assert_eq!(backend::from_name("sops").unwrap().name(), "sops");
+    assert_eq!(backend::from_name("age").unwrap().name(), "age");
+    assert_eq!(backend::from_name("plain").unwrap().name(), "plain");
+    assert!(backend::from_name("rot13").is_err());
+}
+
+#[test]
+fn test_plaintext_backend_roundtrip() {
+    let store = memory_store();
+    assert!(!store.exists());
+
+    secrets::add(&store, "API_KEY", "secret123").unwrap();
+    secrets::add(&store, "USER", "admin").unwrap();
+
+    assert!(store.exists());
+    assert_eq!(secrets::read(&store, "API_KEY").unwrap(), "secret123");
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["API_KEY", "USER"]);
+
+    secrets::remove(&store, "API_KEY", false).unwrap();
+    assert!(secrets::read(&store, "API_KEY").is_err());
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["USER"]);
+}
+
+#[test]
+fn test_load_missing_store_fails() {
+    let store = memory_store();
+    assert!(store.load().is_err());
+    assert!(store
+        .load_or_default()
+        .unwrap()
+        .as_mapping()
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_age_backend_roundtrip() {
+    let temp_dir = TempDir::new().unwrap();
+    let identity = age::x25519::Identity::generate();
+    let key_file = temp_dir.path().join("key.txt");
+    fs::write(
+        &key_file,
+        format!("# public key: {}\n{}\n", identity.to_public(), {
+            use age::secrecy::ExposeSecret;
+            identity.to_string().expose_secret().to_string()
+        }),
+    )
+    .unwrap();
+    fs::write(
+        temp_dir.path().join(".sops.yaml"),
+        format!(
+            "creation_rules:\n  - path_regex: \\.yml$\n    age: {}\n",
+            identity.to_public()
+        ),
+    )
+    .unwrap();
+
+    let secrets_file = temp_dir.path().join("secrets.yml");
+    let backend = AgeBackend::with_identity_file(&key_file);
+    assert_eq!(
+        backend.recipients(&secrets_file).unwrap(),
+        vec![identity.to_public().to_string()]
+    );
+
+    backend
+        .encrypt(&secrets_file, "API_KEY: secret123\n")
+        .unwrap();
+    let on_disk = fs::read_to_string(&secrets_file).unwrap();
+    assert!(!on_disk.contains("secret123"));
+    assert!(on_disk.starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+
+    assert_eq!(
+        backend.decrypt(&secrets_file).unwrap(),
+        "API_KEY: secret123\n"
+    );
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::{age_store, crypta, crypta_with_env};
+use crypta::secrets;
+use std::ffi::OsStr;
+use std::time::Instant;
+use tempfile::TempDir;
+
+/// Sin pantalla el error del proceso del portapapeles llega al CLI, que no
+/// se queda esperando
+#[test]
+fn test_get_without_display_reports_clipboard_error() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "API_KEY", "abc123").unwrap();
+    let no_display = [
+        ("DISPLAY", OsStr::new("")),
+        ("WAYLAND_DISPLAY", OsStr::new("")),
+    ];
+
+    for args in [
+        &["get", "API_KEY"][..],
+        &["get", "API_KEY", "--timeout", "0"],
+    ] {
+        let start = Instant::now();
+        let output = crypta_with_env(dir, args, &no_display);
+        assert!(!output.status.success(), "{:?}", output);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("portapapeles"), "{}", stderr);
+        assert!(start.elapsed().as_secs() < 10);
+    }
+}
+
+#[test]
+fn test_clipboard_helper_is_hidden() {
+    let temp_dir = TempDir::new().unwrap();
+    let output = crypta(temp_dir.path(), &["--help"]);
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("clipboard-helper"));
+}
+
+/// Con `--target osc52` el valor va a la terminal de control aunque stdout
+/// esté redirigida
+#[cfg(target_os = "linux")]
+#[test]
+fn test_get_osc52_writes_to_the_terminal() {
+    use common::command;
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::fd::FromRawFd;
+    use std::os::unix::process::CommandExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "API_KEY", "abc123").unwrap();
+    let args = ["get", "API_KEY", "--target", "osc52"];
+    let env = [("TERM", OsStr::new("xterm-256color"))];
+
+    // Una pseudoterminal hace de terminal de control del CLI
+    let (mut master, mut slave) = (0, 0);
+    // SAFETY: openpty solo escribe los descriptores que devuelve
+    let result = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null(),
+            std::ptr::null(),
+        )
+    };
+    assert_eq!(result, 0);
+    let mut cmd = command(dir, &args, &env);
+    cmd.env_remove("TMUX");
+    // SAFETY: setsid e ioctl son seguras entre fork y exec
+    unsafe {
+        cmd.pre_exec(move || {
+            libc::setsid();
+            libc::ioctl(slave, libc::TIOCSCTTY, 0);
+            Ok(())
+        });
+    }
+    let output = cmd.output().unwrap();
+    // SAFETY: los descriptores son nuestros y no se usan después
+    unsafe { libc::close(slave) };
+    let mut master = unsafe { File::from_raw_fd(master) };
+
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("portapapeles de la terminal"));
+    let mut terminal = Vec::new();
+    let _ = master.read_to_end(&mut terminal);
+    assert_eq!(terminal, b"\x1b]52;c;YWJjMTIz\x07");
+
+    // Sin terminal de control no hay adónde enviarlo
+    let mut cmd = command(dir, &args, &env);
+    // SAFETY: setsid es segura entre fork y exec
+    unsafe {
+        cmd.pre_exec(|| {
+            libc::setsid();
+            Ok(())
+        });
+    }
+    let output = cmd.output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("OSC 52"));
+}
@@ -0,0 +1,202 @@
+//! Utilidades compartidas por los tests del CLI
+
+// Cada archivo de tests compila este módulo y usa solo parte de él
+#![allow(dead_code)]
+
+use crypta::backend::{AgeBackend, PlaintextBackend};
+use crypta::store::Store;
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use tempfile::TempDir;
+
+/// Almacén en texto plano dentro de un directorio temporal, que se borra al
+/// terminar el test. Se usa como un [`Store`].
+pub struct TempStore {
+    store: Store,
+    _dir: TempDir,
+}
+
+impl Deref for TempStore {
+    type Target = Store;
+
+    fn deref(&self) -> &Store {
+        &self.store
+    }
+}
+
+/// Crea un [`TempStore`] vacío
+pub fn memory_store() -> TempStore {
+    let dir = TempDir::new().unwrap();
+    let store = Store::new(
+        dir.path(),
+        dir.path().join("secrets.yml"),
+        Box::new(PlaintextBackend::new()),
+    );
+    TempStore { store, _dir: dir }
+}
+
+/// Crea un almacén age en `dir` con la clave donde la busca el CLI
+pub fn age_store(dir: &Path) -> Store {
+    let identity = age::x25519::Identity::generate();
+    let key_file = dir.join("sops/age/key.txt");
+    fs::create_dir_all(key_file.parent().unwrap()).unwrap();
+    fs::write(
+        &key_file,
+        format!("# public key: {}\n{}\n", identity.to_public(), {
+            use age::secrecy::ExposeSecret;
+            identity.to_string().expose_secret().to_string()
+        }),
+    )
+    .unwrap();
+    fs::write(
+        dir.join(".sops.yaml"),
+        format!(
+            "creation_rules:\n  - path_regex: \\.yml$\n    age: {}\n",
+            identity.to_public()
+        ),
+    )
+    .unwrap();
+
+    Store::new(
+        dir,
+        dir.join("secrets.yml"),
+        Box::new(AgeBackend::with_identity_file(&key_file)),
+    )
+}
+
+/// Ejecuta el CLI sobre el almacén de `dir` con el backend age
+pub fn crypta(dir: &Path, args: &[&str]) -> Output {
+    crypta_with_env(dir, args, &[])
+}
+
+/// Como [`crypta`], escribiendo `input` en stdin
+pub fn crypta_with_stdin(dir: &Path, args: &[&str], input: &str) -> Output {
+    use std::io::Write;
+
+    let mut child = command(dir, args, &[])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().expect("Failed to execute command")
+}
+
+/// Como [`crypta`], con variables de entorno adicionales
+pub fn crypta_with_env(dir: &Path, args: &[&str], env: &[(&str, &std::ffi::OsStr)]) -> Output {
+    command(dir, args, env)
+        .output()
+        .expect("Failed to execute command")
+}
+
+/// Arranca el CLI como proceso de larga duración (servicios y agentes) con
+/// stdout capturado, para esperar a que anuncie que está listo
+pub fn spawn_crypta(dir: &Path, args: &[&str], env: &[(&str, &std::ffi::OsStr)]) -> Child {
+    command(dir, args, env)
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command")
+}
+
+/// Lee la salida de un proceso hasta la línea que contiene `ready`. El
+/// resto de la salida se descarta en segundo plano.
+pub fn wait_for_line(child: &mut Child, ready: &str) -> String {
+    use std::io::{BufRead, BufReader};
+
+    let stdout = child.stdout.take().expect("stdout capturado");
+    let mut lines = BufReader::new(stdout).lines();
+    loop {
+        match lines.next() {
+            Some(Ok(line)) if line.contains(ready) => {
+                std::thread::spawn(move || lines.for_each(drop));
+                return line;
+            }
+            Some(Ok(_)) => continue,
+            _ => panic!("El proceso terminó sin escribir '{}'", ready),
+        }
+    }
+}
+
+/// Comando del CLI sobre el almacén de `dir`, para los tests que necesitan
+/// configurar el proceso (terminal, sesión…)
+pub fn command(dir: &Path, args: &[&str], env: &[(&str, &std::ffi::OsStr)]) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_crypta"));
+    command
+        .arg("--dir")
+        .arg(dir)
+        .args(args)
+        .env("CRYPTA_BACKEND", "age")
+        .env("CRYPTA_CONFIG", dir.join("config.toml"))
+        .env("CRYPTA_STATE", dir.join("state.toml"))
+        .env_remove("CRYPTA_DIR")
+        .env_remove("CRYPTA_FILE")
+        .env_remove("CRYPTA_VAULT")
+        .envs(env.iter().copied());
+    command
+}
+
+/// Crea un programa falso `name` con el script `script` en `dir/bin` y
+/// devuelve un `PATH` que lo encuentra antes que los del sistema
+#[cfg(unix)]
+pub fn fake_command(dir: &Path, name: &str, script: &str) -> std::ffi::OsString {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = dir.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let program = bin.join(name);
+    fs::write(&program, script).unwrap();
+    fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+
+    std::env::join_paths(
+        std::iter::once(bin).chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap()
+}
+
+/// Mensaje de los commits de la sincronización automática en los tests
+pub const AUTO_SYNC_MESSAGE: &str = "auto-sync";
+
+/// Convierte `dir` en un repositorio git con un commit inicial y activa
+/// `sync.auto` en su configuración. No hay remoto: la sincronización falla
+/// en el pull, pero el commit ya se ha creado.
+pub fn auto_sync_repo(dir: &Path) {
+    fs::write(
+        dir.join("config.toml"),
+        format!("[sync]\nauto = true\nmessage = \"{}\"\n", AUTO_SYNC_MESSAGE),
+    )
+    .unwrap();
+    for args in [
+        &["init", "-q"][..],
+        &["add", "-A"],
+        &["commit", "-q", "-m", "init"],
+    ] {
+        let status = git(dir).args(args).status().unwrap();
+        assert!(status.success(), "git {:?}", args);
+    }
+}
+
+/// Commits creados por la sincronización automática en el repositorio de `dir`
+pub fn auto_sync_commits(dir: &Path) -> usize {
+    let output = git(dir).args(["log", "--format=%s"]).output().unwrap();
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|subject| *subject == AUTO_SYNC_MESSAGE)
+        .count()
+}
+
+fn git(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command
+        .arg("-C")
+        .arg(dir)
+        .args(["-c", "user.name=crypta", "-c", "user.email=crypta@local"]);
+    command
+}
@@ -0,0 +1,126 @@
+use crypta::config::Config;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_default_config() {
+    let config = Config::default();
+    assert_eq!(config.clipboard_timeout, 45);
+    assert!(!config.sync.auto);
+    assert_eq!(config.git.remote, "origin");
+    assert_eq!(config.git.branch, "main");
+    assert_eq!(config.vault(None), None);
+}
+
+#[test]
+fn test_parse_config() {
+    let config = Config::from_toml(
+        r#"
+dir = "/srv/secrets"
+file = "store.yml"
+default_vault = "work"
+backend = "age"
+clipboard_timeout = 10
+
+[sync]
+auto = true
+message = "Actualizar secretos"
+
+[git]
+remote = "upstream"
+branch = "trunk"
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.dir, Some(PathBuf::from("/srv/secrets")));
+    assert_eq!(config.clipboard_timeout, 10);
+    assert!(config.sync.auto);
+    assert_eq!(config.sync.message, "Actualizar secretos");
+    assert_eq!(config.git.remote, "upstream");
+    assert_eq!(config.git.branch, "trunk");
+    assert_eq!(config.vault(None), Some("work"));
+    assert_eq!(config.vault(Some("personal")), Some("personal"));
+}
+
+#[test]
+fn test_partial_sections_keep_defaults() {
+    let config = Config::from_toml("[git]\nbranch = \"trunk\"\n").unwrap();
+    assert_eq!(config.git.remote, "origin");
+    assert_eq!(config.git.branch, "trunk");
+    assert_eq!(config.sync.message, "Sync secrets");
+}
+
+#[test]
+fn test_unknown_keys_are_rejected() {
+    assert!(Config::from_toml("direcotry = \"/tmp\"\n").is_err());
+}
+
+#[test]
+fn test_flags_take_precedence() {
+    let config = Config::from_toml("dir = \"/srv/secrets\"\nfile = \"store.yml\"\n").unwrap();
+    assert_eq!(
+        config.secrets_dir(Some(Path::new("/tmp/other"))).unwrap(),
+        PathBuf::from("/tmp/other")
+    );
+    assert_eq!(
+        config.secrets_file(Some(Path::new("other.yml"))),
+        PathBuf::from("other.yml")
+    );
+}
+
+#[test]
+fn test_cli_without_home_does_not_panic() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crypta"))
+        .args(["lookup", "API_KEY"])
+        .env_remove("HOME")
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("CRYPTA_DIR")
+        .env_remove("CRYPTA_CONFIG")
+        .env("CRYPTA_BACKEND", "plain")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("CRYPTA_DIR"));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crypta"))
+        .args(["lookup", "API_KEY"])
+        .env_remove("HOME")
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("CRYPTA_CONFIG")
+        .env("CRYPTA_DIR", temp_dir.path())
+        .env("CRYPTA_BACKEND", "plain")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(output.status.code(), Some(3));
+}
+
+#[test]
+fn test_cli_reads_config_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let config_path = temp_dir.path().join("config.toml");
+    let secrets_dir = temp_dir.path().join("custom");
+    std::fs::write(
+        &config_path,
+        format!(
+            "dir = \"{}\"\nfile = \"store.yml\"\n",
+            secrets_dir.display()
+        ),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_crypta"))
+        .args(["lookup", "API_KEY"])
+        .env("CRYPTA_CONFIG", &config_path)
+        .env_remove("CRYPTA_DIR")
+        .env_remove("CRYPTA_FILE")
+        .env("CRYPTA_BACKEND", "plain")
+        .output()
+        .expect("Failed to execute command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(&secrets_dir.join("store.yml").display().to_string()));
+}
@@ -0,0 +1,217 @@
+mod common;
+
+use common::{age_store, crypta_with_stdin};
+use crypta::secrets;
+use std::path::Path;
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_git_credential_store_get_erase() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+
+    let output = crypta_with_stdin(
+        dir,
+        &["git-credential", "store"],
+        "protocol=https\nhost=github.com\nusername=bob\npassword=t0ken\n\n",
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "git/github.com/bob").unwrap();
+    assert_eq!(entry.text().unwrap(), "t0ken");
+    assert_eq!(entry.meta.username.as_deref(), Some("bob"));
+    assert_eq!(entry.meta.url.as_deref(), Some("https://github.com"));
+
+    let output = crypta_with_stdin(
+        dir,
+        &["git-credential", "get"],
+        "protocol=https\nhost=github.com\n\n",
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "username=bob\npassword=t0ken\n"
+    );
+
+    // Un host sin credenciales no devuelve nada
+    let output = crypta_with_stdin(
+        dir,
+        &["git-credential", "get"],
+        "protocol=https\nhost=gitlab.com\n\n",
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("password="));
+
+    // Solo se borra si la contraseña rechazada es la guardada
+    let erase = |password: &str| {
+        let input =
+            format!("protocol=https\nhost=github.com\nusername=bob\npassword={password}\n\n");
+        let output = crypta_with_stdin(dir, &["git-credential", "erase"], &input);
+        assert!(output.status.success(), "{:?}", output);
+    };
+    erase("antigua");
+    assert_eq!(
+        secrets::read(&store, "git/github.com/bob").unwrap(),
+        "t0ken"
+    );
+    erase("t0ken");
+    assert!(secrets::read(&store, "git/github.com/bob").is_err());
+}
+
+#[test]
+fn test_git_uses_crypta_as_credential_helper() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "tokens/git.example.com/ci", "ci-t0ken").unwrap();
+
+    // git busca `git-credential-crypta` en el PATH
+    let bin = Path::new(env!("CARGO_BIN_EXE_git-credential-crypta"))
+        .parent()
+        .unwrap();
+    let path = std::env::join_paths(
+        std::iter::once(bin.to_path_buf())
+            .chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+    let output = Command::new("git")
+        .args(["-c", "credential.helper="])
+        .args(["-c", "credential.helper=crypta --namespace tokens"])
+        .args(["credential", "fill"])
+        .env("PATH", path)
+        .env("CRYPTA_DIR", dir)
+        .env_remove("CRYPTA_FILE")
+        .env_remove("CRYPTA_VAULT")
+        .env("CRYPTA_BACKEND", "age")
+        .env("CRYPTA_CONFIG", dir.join("config.toml"))
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            use std::io::Write;
+            child
+                .stdin
+                .take()
+                .unwrap()
+                .write_all(b"url=https://ci@git.example.com/org/repo.git\n\n")?;
+            child.wait_with_output()
+        })
+        .expect("Failed to execute git");
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("password=ci-t0ken\n"));
+}
+
+#[test]
+fn test_docker_credential_protocol() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+
+    let output = crypta_with_stdin(
+        dir,
+        &["docker-credential", "store"],
+        r#"{"ServerURL":"https://ghcr.io","Username":"bob","Secret":"ghp_t0ken"}"#,
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(
+        secrets::read(&store, "docker/ghcr.io").unwrap(),
+        "ghp_t0ken"
+    );
+
+    let output = crypta_with_stdin(dir, &["docker-credential", "get"], "https://ghcr.io\n");
+    assert!(output.status.success(), "{:?}", output);
+    let credentials: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        credentials,
+        serde_json::json!({"ServerURL": "https://ghcr.io", "Username": "bob", "Secret": "ghp_t0ken"})
+    );
+
+    let output = crypta_with_stdin(dir, &["docker-credential", "list"], "");
+    assert!(output.status.success(), "{:?}", output);
+    let registries: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(registries, serde_json::json!({"https://ghcr.io": "bob"}));
+
+    let output = crypta_with_stdin(dir, &["docker-credential", "erase"], "https://ghcr.io");
+    assert!(output.status.success(), "{:?}", output);
+
+    // Docker reconoce la falta de credencial por el mensaje en stdout
+    let output = crypta_with_stdin(dir, &["docker-credential", "get"], "https://ghcr.io");
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        "credentials not found in native keychain"
+    );
+}
+
+#[test]
+fn test_docker_credential_binary_uses_the_same_store() {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "registries/registry.example.com:5000", "s3cret").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docker-credential-crypta"))
+        .arg("get")
+        .env("CRYPTA_DIR", dir)
+        .env("CRYPTA_BACKEND", "age")
+        .env("CRYPTA_CONFIG", dir.join("config.toml"))
+        .env("CRYPTA_DOCKER_NAMESPACE", "registries")
+        .env_remove("CRYPTA_FILE")
+        .env_remove("CRYPTA_VAULT")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"registry.example.com:5000")
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(r#""Secret":"s3cret""#));
+}
+
+#[test]
+fn test_docker_credential_binary_honours_crypta_file() {
+    use crypta::backend::AgeBackend;
+    use crypta::store::Store;
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    age_store(dir);
+    let store = Store::new(
+        dir,
+        dir.join("registries.yml"),
+        Box::new(AgeBackend::with_identity_file(dir.join("sops/age/key.txt"))),
+    );
+    secrets::add(&store, "docker/ghcr.io", "s3cret").unwrap();
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_docker-credential-crypta"))
+        .arg("get")
+        .env("CRYPTA_DIR", dir)
+        .env("CRYPTA_FILE", "registries.yml")
+        .env("CRYPTA_BACKEND", "age")
+        .env("CRYPTA_CONFIG", dir.join("config.toml"))
+        .env_remove("CRYPTA_DOCKER_NAMESPACE")
+        .env_remove("CRYPTA_VAULT")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"ghcr.io").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains(r#""Secret":"s3cret""#));
+}
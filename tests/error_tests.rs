@@ -0,0 +1,86 @@
+mod common;
+
+use chrono::TimeDelta;
+use common::memory_store;
+use crypta::entry::MetadataUpdate;
+use crypta::{error, secrets, Error};
+use std::process::Command;
+use tempfile::TempDir;
+
+#[test]
+fn test_missing_key_is_not_found() {
+    let store = memory_store();
+    secrets::add(&store, "API_KEY", "secret123").unwrap();
+
+    let err = secrets::read(&store, "NOPE").unwrap_err();
+    assert!(matches!(error::find(&err), Some(Error::NotFound(key)) if key == "NOPE"));
+    assert_eq!(error::exit_code(&err), 2);
+}
+
+#[test]
+fn test_missing_store_is_store_not_found() {
+    let store = memory_store();
+
+    let err = secrets::read(&store, "API_KEY").unwrap_err();
+    assert!(matches!(error::find(&err), Some(Error::StoreNotFound(_))));
+    assert_eq!(error::exit_code(&err), 3);
+}
+
+#[test]
+fn test_generic_errors_exit_with_one() {
+    let err = secrets::password_string(0, false).unwrap_err();
+    assert!(error::find(&err).is_none());
+    assert_eq!(error::exit_code(&err), 1);
+}
+
+#[test]
+fn test_exit_codes_are_distinct() {
+    let errors = [
+        Error::NotFound(String::new()),
+        Error::StoreNotFound("secrets.yml".into()),
+        Error::DecryptFailed(String::new()),
+        Error::EncryptFailed(String::new()),
+        Error::IntegrityFailed(String::new()),
+        Error::BackendMissing(String::new()),
+        Error::GitAuth(String::new()),
+        Error::Conflict(String::new()),
+        Error::Expiring(0),
+    ];
+    let mut codes: Vec<i32> = errors.iter().map(Error::exit_code).collect();
+    codes.sort();
+    codes.dedup();
+    assert_eq!(codes.len(), errors.len());
+    assert!(!codes.contains(&0) && !codes.contains(&1));
+}
+
+#[test]
+fn test_cli_exit_code_for_missing_store() {
+    let temp_dir = TempDir::new().unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_crypta"))
+        .args(["lookup", "API_KEY"])
+        .env("HOME", temp_dir.path())
+        .env("CRYPTA_BACKEND", "plain")
+        .output()
+        .expect("Failed to execute command");
+
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("El archivo de secretos no existe"));
+}
+
+#[test]
+fn test_expiring_secrets_exit_with_ten() {
+    let store = memory_store();
+    secrets::add(&store, "TOKEN", "t").unwrap();
+    secrets::expiring(&store, TimeDelta::days(14)).unwrap();
+
+    let update = MetadataUpdate {
+        expires: Some("2000-01-01".to_string()),
+        ..Default::default()
+    };
+    secrets::meta(&store, "TOKEN", &update).unwrap();
+
+    let err = secrets::expiring(&store, TimeDelta::days(14)).unwrap_err();
+    assert!(matches!(error::find(&err), Some(Error::Expiring(1))));
+    assert_eq!(error::exit_code(&err), 10);
+}
@@ -0,0 +1,71 @@
+mod common;
+
+use common::{age_store, crypta};
+use crypta::secrets;
+use tempfile::TempDir;
+
+#[test]
+fn test_exec_injects_secrets_and_forwards_exit_code() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = age_store(temp_dir.path());
+    secrets::add(&store, "API_KEY", "k3y").unwrap();
+    secrets::add(&store, "DB_PASSWORD", "s3cret").unwrap();
+
+    let output = crypta(
+        temp_dir.path(),
+        &[
+            "exec",
+            "--env",
+            "API_KEY",
+            "--env",
+            "DB=DB_PASSWORD",
+            "--",
+            "sh",
+            "-c",
+            "echo \"$API_KEY $DB\"; exit 7",
+        ],
+    );
+
+    assert_eq!(output.status.code(), Some(7));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "k3y s3cret\n");
+}
+
+#[test]
+fn test_exec_with_prefix() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = age_store(temp_dir.path());
+    secrets::add(&store, "prod/db/password", "p").unwrap();
+    secrets::add(&store, "prod/api-key", "a").unwrap();
+    secrets::add(&store, "dev/api-key", "d").unwrap();
+
+    let output = crypta(
+        temp_dir.path(),
+        &[
+            "exec",
+            "--prefix",
+            "prod/",
+            "--",
+            "sh",
+            "-c",
+            "echo \"$DB_PASSWORD $API_KEY\"",
+        ],
+    );
+
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "p a\n");
+}
+
+#[test]
+fn test_exec_missing_key_fails_before_running() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = age_store(temp_dir.path());
+    secrets::add(&store, "API_KEY", "k3y").unwrap();
+
+    let output = crypta(
+        temp_dir.path(),
+        &["exec", "--env", "NOPE", "--", "sh", "-c", "echo ran"],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("ran"));
+}
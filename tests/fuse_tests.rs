@@ -0,0 +1,206 @@
+#![cfg(target_os = "linux")]
+
+mod common;
+
+use common::{age_store, auto_sync_commits, auto_sync_repo, spawn_crypta};
+use crypta::secrets;
+use std::fs;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// `crypta mount` en marcha; al salir se desmonta con SIGTERM
+struct Mounted {
+    child: Child,
+    dir: PathBuf,
+}
+
+impl Mounted {
+    /// Monta el almacén de `dir` en `dir/mnt`. Devuelve `None` si el
+    /// entorno no permite montar FUSE (sin /dev/fuse o sin permisos).
+    fn start(dir: &Path, extra: &[&str]) -> Option<Self> {
+        Self::start_at(dir, &dir.join("mnt"), extra)
+    }
+
+    /// Como [`Mounted::start`], montando en `mnt`
+    fn start_at(dir: &Path, mnt: &Path, extra: &[&str]) -> Option<Self> {
+        if !Path::new("/dev/fuse").exists() {
+            eprintln!("/dev/fuse no existe; se omite el test");
+            return None;
+        }
+        let mnt = mnt.to_path_buf();
+        let mut args = vec!["mount", mnt.to_str().unwrap()];
+        args.extend_from_slice(extra);
+        let mut child = spawn_crypta(dir, &args, &[]);
+
+        let stdout = child.stdout.take().unwrap();
+        let mut lines = BufReader::new(stdout).lines();
+        match lines.next() {
+            Some(Ok(line)) if line.contains("Secretos montados") => {
+                std::thread::spawn(move || lines.for_each(drop));
+                Some(Self { child, dir: mnt })
+            }
+            _ => {
+                let _ = child.wait();
+                eprintln!("No se puede montar FUSE en este entorno; se omite el test");
+                None
+            }
+        }
+    }
+
+    fn is_mounted(&self) -> bool {
+        let mounts = fs::read_to_string("/proc/self/mounts").unwrap();
+        let dir = self.dir.canonicalize().unwrap_or_else(|_| self.dir.clone());
+        mounts
+            .lines()
+            .any(|line| line.split_whitespace().nth(1) == dir.to_str())
+    }
+
+    /// Envía SIGTERM y espera a que el proceso desmonte y termine
+    fn stop(&mut self) -> bool {
+        // SAFETY: kill solo envía una señal al proceso hijo
+        unsafe { libc::kill(self.child.id() as i32, libc::SIGTERM) };
+        for _ in 0..50 {
+            if let Some(status) = self.child.try_wait().unwrap() {
+                return status.success();
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        false
+    }
+}
+
+impl Drop for Mounted {
+    fn drop(&mut self) {
+        if self.child.try_wait().unwrap().is_none() && !self.stop() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[test]
+fn test_mount_exposes_secrets_as_read_only_files() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "API_KEY", "abc123").unwrap();
+    secrets::add(&store, "prod/db/password", "s3cret").unwrap();
+    secrets::add_bytes(&store, "certs/client.p12", &[0x30, 0x82, 0xff, 0x00]).unwrap();
+    secrets::add_json(&store, "app/config", r#"{"port": 5432}"#).unwrap();
+
+    let Some(mut mounted) = Mounted::start(dir, &[]) else {
+        return;
+    };
+    let mnt = mounted.dir.clone();
+    assert!(mounted.is_mounted());
+
+    let mut names: Vec<_> = fs::read_dir(&mnt)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["API_KEY", "app", "certs", "prod"]);
+    assert!(mnt.join("prod/db").is_dir());
+
+    assert_eq!(fs::read_to_string(mnt.join("API_KEY")).unwrap(), "abc123");
+    assert_eq!(
+        fs::read_to_string(mnt.join("prod/db/password")).unwrap(),
+        "s3cret"
+    );
+    assert_eq!(
+        fs::read(mnt.join("certs/client.p12")).unwrap(),
+        [0x30, 0x82, 0xff, 0x00]
+    );
+    let config: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(mnt.join("app/config")).unwrap()).unwrap();
+    assert_eq!(config["port"], 5432);
+
+    let metadata = fs::metadata(mnt.join("API_KEY")).unwrap();
+    assert_eq!(metadata.permissions().mode() & 0o777, 0o400);
+    assert_eq!(metadata.len(), 6);
+    assert_eq!(
+        fs::read_to_string(mnt.join("NADA")).unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+
+    // Solo lectura
+    let error = fs::write(mnt.join("API_KEY"), "x").unwrap_err();
+    assert_eq!(error.raw_os_error(), Some(libc::EROFS));
+    assert!(fs::remove_file(mnt.join("API_KEY")).is_err());
+
+    // Los cambios hechos con el CLI se ven sin volver a montar
+    secrets::add(&store, "API_KEY", "n3w").unwrap();
+    assert_eq!(fs::read_to_string(mnt.join("API_KEY")).unwrap(), "n3w");
+
+    // SIGTERM desmonta y el proceso termina con éxito
+    assert!(mounted.stop());
+    assert!(!mounted.is_mounted());
+}
+
+#[test]
+fn test_mount_with_write_reencrypts_on_close() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "API_KEY", "abc123").unwrap();
+    secrets::add(&store, "prod/TOKEN", "t0ken").unwrap();
+    secrets::add_json(&store, "app/config", r#"{"port": 5432}"#).unwrap();
+    auto_sync_repo(dir);
+
+    // Fuera del repositorio: git no debe recorrer el montaje que sirve
+    // el mismo proceso
+    let mnt_dir = TempDir::new().unwrap();
+    let Some(mounted) = Mounted::start_at(dir, mnt_dir.path(), &["--write"]) else {
+        return;
+    };
+    let mnt = mounted.dir.clone();
+
+    // Sobrescribir un secreto; con `sync.auto` se sincroniza al cerrar
+    fs::write(mnt.join("API_KEY"), "n3w").unwrap();
+    assert_eq!(secrets::read(&store, "API_KEY").unwrap(), "n3w");
+    assert_eq!(fs::read_to_string(mnt.join("API_KEY")).unwrap(), "n3w");
+    assert_eq!(auto_sync_commits(dir), 1);
+
+    // Escritura en varias partes y con append
+    {
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(mnt.join("API_KEY"))
+            .unwrap();
+        file.write_all(b"-1").unwrap();
+        file.write_all(b"-2").unwrap();
+    }
+    assert_eq!(secrets::read(&store, "API_KEY").unwrap(), "n3w-1-2");
+
+    // Crear uno nuevo en un grupo
+    fs::write(mnt.join("prod/NEW"), "nuevo").unwrap();
+    assert_eq!(secrets::read(&store, "prod/NEW").unwrap(), "nuevo");
+
+    // Un valor estructurado solo acepta JSON
+    // (`close` no devuelve errores en Rust: el error llega con fsync)
+    {
+        let mut file = fs::File::create(mnt.join("app/config")).unwrap();
+        file.write_all(b"no es json").unwrap();
+        assert!(file.sync_all().is_err());
+    }
+    fs::write(mnt.join("app/config"), r#"{"port": 6543}"#).unwrap();
+    let doc = store.load().unwrap();
+    let config = secrets::find(&doc, "app/config").unwrap();
+    assert_eq!(config.value["port"].as_u64(), Some(6543));
+
+    // Borrar secretos; el grupo desaparece con el último
+    let commits = auto_sync_commits(dir);
+    fs::remove_file(mnt.join("prod/TOKEN")).unwrap();
+    assert_eq!(auto_sync_commits(dir), commits + 1);
+    fs::remove_file(mnt.join("prod/NEW")).unwrap();
+    assert!(secrets::read(&store, "prod/TOKEN").is_err());
+    assert!(!mnt.join("prod").exists());
+
+    // En el disco solo está el documento encriptado
+    let raw = fs::read_to_string(dir.join("secrets.yml")).unwrap();
+    assert!(!raw.contains("n3w"));
+}
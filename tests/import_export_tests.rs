@@ -0,0 +1,531 @@
+mod common;
+
+#[cfg(unix)]
+use common::fake_command;
+use common::{age_store, crypta, crypta_with_env, memory_store};
+use crypta::export::{self, ExportFormat, ExportOptions};
+use crypta::import::{self, ConflictPolicy, ImportFormat, ImportOptions, Record};
+use crypta::secrets;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+#[test]
+fn test_detect_format() {
+    for name in [".env", ".env.production", "app.env"] {
+        assert_eq!(
+            ImportFormat::detect(Path::new(name)),
+            Some(ImportFormat::Dotenv)
+        );
+    }
+    assert_eq!(
+        ImportFormat::detect(Path::new("pass")),
+        Some(ImportFormat::Pass)
+    );
+    assert_eq!(
+        ImportFormat::detect(Path::new("Passwords.KDBX")),
+        Some(ImportFormat::Keepass)
+    );
+    assert_eq!(
+        ImportFormat::detect(Path::new("bitwarden_export.json")),
+        Some(ImportFormat::Bitwarden)
+    );
+    assert_eq!(
+        ImportFormat::detect(Path::new("1password.csv")),
+        Some(ImportFormat::Csv)
+    );
+    assert_eq!(ImportFormat::detect(Path::new("secrets.yml")), None);
+}
+
+#[test]
+fn test_import_dotenv_into() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join(".env");
+    fs::write(
+        &source,
+        "# app\nexport API_KEY=\"k3y\"\nDB_PASSWORD='p4ss#word'\nCERT=\"-----BEGIN-----\nabc\n-----END-----\"\n",
+    )
+    .unwrap();
+
+    let store = memory_store();
+    secrets::add(&store, "app/API_KEY", "old").unwrap();
+    let options = ImportOptions {
+        into: Some("app/".to_string()),
+        ..ImportOptions::default()
+    };
+    import::import(&store, &source, &options).unwrap();
+
+    assert_eq!(secrets::read(&store, "app/API_KEY").unwrap(), "k3y");
+    assert_eq!(
+        secrets::read(&store, "app/DB_PASSWORD").unwrap(),
+        "p4ss#word"
+    );
+    assert_eq!(
+        secrets::read(&store, "app/CERT").unwrap(),
+        "-----BEGIN-----\nabc\n-----END-----"
+    );
+}
+
+#[test]
+fn test_import_reports_added_and_updated() {
+    let mut doc = serde_yaml::from_str("A: old\n").unwrap();
+    let summary = import::apply(
+        &mut doc,
+        vec![Record::new("A", "1"), Record::new("B", "2")],
+        ConflictPolicy::Overwrite,
+    )
+    .unwrap();
+    assert_eq!(summary.updated, vec!["A"]);
+    assert_eq!(summary.added, vec!["B"]);
+}
+
+#[test]
+fn test_import_conflict_policies() {
+    let records = || vec![Record::new("A", "new"), Record::new("B", "2")];
+
+    let mut doc = serde_yaml::from_str("A: old\nA-2: taken\n").unwrap();
+    let summary = import::apply(&mut doc, records(), ConflictPolicy::Skip).unwrap();
+    assert_eq!(summary.skipped, vec!["A"]);
+    assert_eq!(summary.added, vec!["B"]);
+    assert_eq!(secrets::text(&doc, "A").unwrap(), "old");
+
+    let mut doc = serde_yaml::from_str("A: old\nA-2: taken\n").unwrap();
+    let summary = import::apply(&mut doc, records(), ConflictPolicy::Rename).unwrap();
+    assert_eq!(summary.renamed, vec![("A".to_string(), "A-3".to_string())]);
+    assert_eq!(secrets::text(&doc, "A").unwrap(), "old");
+    assert_eq!(secrets::text(&doc, "A-3").unwrap(), "new");
+}
+
+#[test]
+fn test_import_bitwarden_json_with_metadata() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("bitwarden.json");
+    fs::write(
+        &source,
+        r#"{"encrypted": false,
+            "folders": [{"id": "f1", "name": "Web"}],
+            "items": [{"type": 1, "name": "GitHub", "folderId": "f1", "notes": "2FA en el móvil",
+                       "login": {"username": "bob", "password": "gh-p4ss",
+                                 "uris": [{"uri": "https://github.com"}]}},
+                      {"type": 1, "name": "", "folderId": null,
+                       "login": {"password": "sin-nombre"}}]}"#,
+    )
+    .unwrap();
+
+    let store = memory_store();
+    let options = ImportOptions {
+        into: Some("bw".to_string()),
+        ..ImportOptions::default()
+    };
+    // El elemento sin nombre se omite sin abortar la importación
+    import::import(&store, &source, &options).unwrap();
+
+    let doc = store.load().unwrap();
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["bw/Web/GitHub"]);
+    let entry = secrets::find(&doc, "bw/Web/GitHub").unwrap();
+    assert_eq!(entry.text().unwrap(), "gh-p4ss");
+    assert_eq!(entry.meta.username.as_deref(), Some("bob"));
+    assert_eq!(entry.meta.url.as_deref(), Some("https://github.com"));
+    assert_eq!(entry.meta.note.as_deref(), Some("2FA en el móvil"));
+}
+
+#[test]
+fn test_import_csv_with_column_mapping() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("cuentas.csv");
+    fs::write(
+        &source,
+        "Servicio,Carpeta,Clave,Usuario\nsmtp,mail,s3cret,postmaster\n",
+    )
+    .unwrap();
+
+    let store = memory_store();
+    let options = ImportOptions {
+        columns: vec![
+            "key=Servicio".to_string(),
+            "folder=Carpeta".to_string(),
+            "value=Clave".to_string(),
+            "username=Usuario".to_string(),
+        ],
+        ..ImportOptions::default()
+    };
+    import::import(&store, &source, &options).unwrap();
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "mail/smtp").unwrap();
+    assert_eq!(entry.text().unwrap(), "s3cret");
+    assert_eq!(entry.meta.username.as_deref(), Some("postmaster"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_import_keepass_with_keepassxc_cli() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+
+    // keepassxc-cli falso que devuelve la exportación CSV
+    let path = fake_command(
+        dir,
+        "keepassxc-cli",
+        "#!/bin/sh\n\
+         echo '\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\"'\n\
+         echo '\"Root/Internet\",\"GitHub\",\"bob\",\"gh-p4ss\",\"https://github.com\",\"\"'\n\
+         echo '\"Root\",\"wifi\",\"\",\"w1f1\",\"\",\"casa\"'\n",
+    );
+
+    let kdbx = dir.join("Passwords.kdbx");
+    fs::write(&kdbx, "").unwrap();
+    let output = crypta_with_env(
+        dir,
+        &["import", kdbx.to_str().unwrap(), "--into", "kp"],
+        &[("PATH", &path)],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "kp/Internet/GitHub").unwrap();
+    assert_eq!(entry.text().unwrap(), "gh-p4ss");
+    assert_eq!(entry.meta.username.as_deref(), Some("bob"));
+    assert_eq!(secrets::text(&doc, "kp/wifi").unwrap(), "w1f1");
+}
+
+#[test]
+fn test_export_dotenv_roundtrip() {
+    let store = memory_store();
+    secrets::add(&store, "prod/db/password", "it's $ecret").unwrap();
+    secrets::add(&store, "prod/api-key", "abc123").unwrap();
+    secrets::add(&store, "dev/api-key", "dev").unwrap();
+
+    let doc = store.load().unwrap();
+    let prod = ExportOptions {
+        prefix: Some("prod/".to_string()),
+        ..ExportOptions::default()
+    };
+    let content = export::render(&doc, ExportFormat::Dotenv, &prod).unwrap();
+    assert_eq!(content, "DB_PASSWORD=\"it's \\$ecret\"\nAPI_KEY=abc123\n");
+
+    let vars = crypta::dotenv::parse(&content).unwrap();
+    assert_eq!(vars[0].1, "it's $ecret");
+
+    let all = export::render(&doc, ExportFormat::Dotenv, &ExportOptions::default()).unwrap();
+    assert!(all.contains("PROD_DB_PASSWORD="));
+    assert!(all.contains("DEV_API_KEY=dev\n"));
+}
+
+#[test]
+fn test_export_k8s_secret() {
+    let store = memory_store();
+    secrets::add(&store, "prod/db/password", "s3cret").unwrap();
+    secrets::add_bytes(&store, "prod/tls/key", &[0, 159, 146, 150]).unwrap();
+    secrets::add(&store, "dev/db/password", "dev").unwrap();
+
+    let doc = store.load().unwrap();
+    let options = ExportOptions {
+        keys: vec!["prod/*".to_string()],
+        name: Some("app-secrets".to_string()),
+        namespace: Some("prod".to_string()),
+        ..ExportOptions::default()
+    };
+    let content = export::render(&doc, ExportFormat::K8s, &options).unwrap();
+    assert_eq!(
+        content,
+        "apiVersion: v1\nkind: Secret\nmetadata:\n  name: app-secrets\n  namespace: prod\n\
+         type: Opaque\ndata:\n  DB_PASSWORD: czNjcmV0\n  TLS_KEY: AJ+Slg==\n"
+    );
+
+    let unnamed = ExportOptions {
+        name: None,
+        ..options
+    };
+    assert!(export::render(&doc, ExportFormat::K8s, &unnamed).is_err());
+}
+
+#[test]
+fn test_export_structured_values_and_name_collisions() {
+    let store = memory_store();
+    secrets::add_json(&store, "db/config", r#"{"port": 5432}"#).unwrap();
+    secrets::add(&store, "db/password", "s3cret").unwrap();
+
+    let doc = store.load().unwrap();
+    let options = ExportOptions {
+        name: Some("app".to_string()),
+        ..ExportOptions::default()
+    };
+    let dotenv = export::render(&doc, ExportFormat::Dotenv, &options).unwrap();
+    assert!(dotenv.contains("DB_CONFIG="), "{}", dotenv);
+    let k8s = export::render(&doc, ExportFormat::K8s, &options).unwrap();
+    assert!(k8s.contains("DB_CONFIG: "), "{}", k8s);
+
+    // `db/password` y `db-password` acabarían en la misma variable
+    secrets::add(&store, "db-password", "other").unwrap();
+    let doc = store.load().unwrap();
+    for format in [ExportFormat::Dotenv, ExportFormat::K8s] {
+        let err = export::render(&doc, format, &options).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("'db-password'"), "{}", message);
+        assert!(message.contains("'db/password'"), "{}", message);
+        assert!(message.contains("DB_PASSWORD"), "{}", message);
+    }
+
+    secrets::add(&store, "db_password", "other").unwrap();
+    let doc = store.load().unwrap();
+    assert!(export::files(&doc, &ExportOptions::default()).is_err());
+}
+
+#[test]
+fn test_export_prefix_naming_a_secret() {
+    let store = memory_store();
+    secrets::add(&store, "prod/api-key", "abc123").unwrap();
+
+    let doc = store.load().unwrap();
+    let options = ExportOptions {
+        prefix: Some("prod/api-key".to_string()),
+        ..ExportOptions::default()
+    };
+    assert_eq!(
+        export::render(&doc, ExportFormat::Dotenv, &options).unwrap(),
+        "API_KEY=abc123\n"
+    );
+    assert_eq!(
+        export::files(&doc, &options).unwrap(),
+        vec![("api-key".to_string(), b"abc123".to_vec())]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_export_sops_k8s_encrypts_for_store_recipients() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "prod/api-key", "abc123").unwrap();
+    let recipients = store.backend().recipients(store.file()).unwrap();
+
+    // sops falso que anota sus argumentos delante del manifiesto
+    let path = fake_command(dir, "sops", "#!/bin/sh\necho \"# $*\"\ncat\n");
+    let output = crypta_with_env(
+        dir,
+        &[
+            "export", "--format", "sops-k8s", "--name", "app", "--keys", "prod/*",
+        ],
+        &[("PATH", &path)],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("--age {}", recipients.join(","))));
+    assert!(stdout.contains("--encrypted-regex ^(data|stringData)$"));
+    assert!(stdout.contains("  API_KEY: YWJjMTIz\n"));
+}
+
+#[test]
+fn test_export_docker_secrets_files() {
+    let store = memory_store();
+    secrets::add(&store, "docker/db/password", "s3cret").unwrap();
+    secrets::add(&store, "docker/DATABASE_URL", "postgres://db").unwrap();
+    secrets::add(&store, "prod/api-key", "abc123").unwrap();
+
+    let doc = store.load().unwrap();
+    let options = ExportOptions {
+        prefix: Some("docker".to_string()),
+        ..ExportOptions::default()
+    };
+    assert_eq!(
+        export::files(&doc, &options).unwrap(),
+        vec![
+            ("db_password".to_string(), b"s3cret".to_vec()),
+            ("DATABASE_URL".to_string(), b"postgres://db".to_vec()),
+        ]
+    );
+    assert!(export::render(&doc, ExportFormat::DockerSecrets, &options).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_export_docker_secrets_and_cleanup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "docker/db/password", "s3cret").unwrap();
+    let target = dir.join("run/app");
+
+    let output = crypta(
+        dir,
+        &[
+            "export",
+            "--format",
+            "docker-secrets",
+            "--prefix",
+            "docker/",
+            "-o",
+            target.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let file = target.join("db_password");
+    assert_eq!(fs::read_to_string(&file).unwrap(), "s3cret");
+    let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+    assert_eq!(mode(&file), 0o400);
+    assert_eq!(mode(&target), 0o700);
+
+    // Se puede volver a exportar sobre el mismo directorio
+    let output = crypta(
+        dir,
+        &[
+            "export",
+            "--format",
+            "docker-secrets",
+            "-o",
+            target.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(target.join("docker_db_password").exists());
+    assert!(!file.exists());
+
+    // Un directorio ajeno con archivos no se toca
+    let foreign = dir.join("ajeno");
+    fs::create_dir_all(&foreign).unwrap();
+    fs::write(foreign.join("datos"), "x").unwrap();
+    let output = crypta(
+        dir,
+        &[
+            "export",
+            "--format",
+            "docker-secrets",
+            "-o",
+            foreign.to_str().unwrap(),
+        ],
+    );
+    assert!(!output.status.success());
+
+    let output = crypta(dir, &["cleanup"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!target.exists());
+    assert!(foreign.join("datos").exists());
+    let state = fs::read_to_string(dir.join("state.toml")).unwrap();
+    assert!(!state.contains("run/app"));
+}
+
+/// Almacén de pass con un `gpg` falso que devuelve el archivo tal cual
+#[cfg(unix)]
+fn fake_pass_store(root: &Path) -> (std::path::PathBuf, std::ffi::OsString) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let store_dir = root.join("password-store");
+    fs::create_dir_all(store_dir.join("web")).unwrap();
+    fs::write(store_dir.join(".gpg-id"), "bob@example.com\n").unwrap();
+    fs::write(store_dir.join("email.gpg"), "m41l\n").unwrap();
+    fs::write(
+        store_dir.join("web/github.gpg"),
+        "gh-t0ken\nuser: bob\nurl: https://github.com\n",
+    )
+    .unwrap();
+
+    let bin = root.join("bin");
+    fs::create_dir_all(&bin).unwrap();
+    let gpg = bin.join("gpg");
+    fs::write(
+        &gpg,
+        "#!/bin/sh\nfor last; do :; done\n[ \"$1\" = --version ] && exit 0\ncat \"$last\"\n",
+    )
+    .unwrap();
+    fs::set_permissions(&gpg, fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = std::env::join_paths(
+        std::iter::once(bin).chain(std::env::split_paths(&std::env::var_os("PATH").unwrap())),
+    )
+    .unwrap();
+    (store_dir, path)
+}
+
+#[cfg(unix)]
+#[test]
+fn test_import_pass_store() {
+    let temp_dir = TempDir::new().unwrap();
+    let (store_dir, path) = fake_pass_store(temp_dir.path());
+    let store = age_store(temp_dir.path());
+
+    let output = crypta_with_env(
+        temp_dir.path(),
+        &["import", "pass", "--into", "legacy"],
+        &[
+            ("PATH", &path),
+            ("PASSWORD_STORE_DIR", store_dir.as_os_str()),
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+
+    assert_eq!(secrets::read(&store, "legacy/email").unwrap(), "m41l");
+    assert_eq!(
+        secrets::read(&store, "legacy/web/github").unwrap(),
+        "gh-t0ken"
+    );
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "legacy/web/github").unwrap();
+    assert_eq!(
+        entry.meta.note.as_deref(),
+        Some("user: bob\nurl: https://github.com")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_import_pass_prefix_selects_subdirectory() {
+    let temp_dir = TempDir::new().unwrap();
+    let (store_dir, path) = fake_pass_store(temp_dir.path());
+    let store = age_store(temp_dir.path());
+
+    let output = crypta_with_env(
+        temp_dir.path(),
+        &["import", "pass", "--prefix", "web/", "--into", "legacy"],
+        &[
+            ("PATH", &path),
+            ("PASSWORD_STORE_DIR", store_dir.as_os_str()),
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["legacy/github"]);
+    assert_eq!(secrets::read(&store, "legacy/github").unwrap(), "gh-t0ken");
+}
+
+#[test]
+fn test_import_prefix_filters_source_keys() {
+    let temp_dir = TempDir::new().unwrap();
+    let source = temp_dir.path().join("cuentas.csv");
+    fs::write(
+        &source,
+        "name,folder,password\nsmtp,mail,s3cret\nimap,mail/old,0ld\ngithub,web,t0ken\n",
+    )
+    .unwrap();
+
+    let store = memory_store();
+    let options = ImportOptions {
+        prefix: Some("mail".to_string()),
+        ..ImportOptions::default()
+    };
+    import::import(&store, &source, &options).unwrap();
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["smtp", "old/imap"]);
+}
+
+#[test]
+fn test_import_pass_dry_run_does_not_decrypt() {
+    let temp_dir = TempDir::new().unwrap();
+    let store_dir = temp_dir.path().join("password-store");
+    fs::create_dir_all(&store_dir).unwrap();
+    fs::write(store_dir.join("token.gpg"), "no es gpg").unwrap();
+
+    let store = memory_store();
+    secrets::add(&store, "other", "x").unwrap();
+    let options = ImportOptions {
+        format: Some(ImportFormat::Pass),
+        into: Some("legacy".to_string()),
+        dry_run: true,
+        ..ImportOptions::default()
+    };
+    import::import(&store, &store_dir, &options).unwrap();
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["other"]);
+}
@@ -35,7 +35,7 @@ fn test_cli_subcommands() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         
         // Verificar que se muestra información de ayuda
-        assert!(stdout.len() > 0 || stderr.len() > 0);
+        assert!(!stdout.is_empty() || !stderr.is_empty());
     }
 }
 
@@ -50,7 +50,7 @@ fn test_cli_version() {
     // Solo verificamos que el comando se ejecuta
     let stdout = String::from_utf8_lossy(&output.stdout);
     let stderr = String::from_utf8_lossy(&output.stderr);
-    assert!(stdout.len() > 0 || stderr.len() > 0);
+    assert!(!stdout.is_empty() || !stderr.is_empty());
 }
 
 #[test]
@@ -70,7 +70,7 @@ fn test_cli_short_commands() {
         let stderr = String::from_utf8_lossy(&output.stderr);
         
         // Verificar que se muestra información de ayuda
-        assert!(stdout.len() > 0 || stderr.len() > 0, 
+        assert!(!stdout.is_empty() || !stderr.is_empty(), 
                 "Short command '{}' should show help output", cmd);
     }
 }
@@ -0,0 +1,287 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{age_store, auto_sync_commits, auto_sync_repo, spawn_crypta, wait_for_line};
+use crypta::secret_service::encode_path_element;
+use crypta::secrets;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use tempfile::TempDir;
+use zbus::blocking::{Connection, Proxy};
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+const BUS_NAME: &str = "org.freedesktop.secrets";
+
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+/// Bus de sesión privado para el test; se detiene al salir
+struct Bus {
+    daemon: Child,
+    address: String,
+}
+
+impl Bus {
+    fn start() -> Option<Self> {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--print-address", "--nofork"])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .ok()?;
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap())
+            .read_line(&mut address)
+            .unwrap();
+        Some(Self {
+            daemon,
+            address: address.trim().to_string(),
+        })
+    }
+
+    fn connect(&self) -> Connection {
+        zbus::blocking::connection::Builder::address(self.address.as_str())
+            .unwrap()
+            .build()
+            .unwrap()
+    }
+}
+
+impl Drop for Bus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn proxy<'a>(connection: &Connection, path: &'a str, interface: &'a str) -> Proxy<'a> {
+    Proxy::new(connection, BUS_NAME, path, interface).unwrap()
+}
+
+fn error_name(error: zbus::Error) -> String {
+    match error {
+        zbus::Error::MethodError(name, _, _) => name.to_string(),
+        other => panic!("Se esperaba un error D-Bus: {other:?}"),
+    }
+}
+
+#[test]
+fn test_secret_service_over_private_bus() {
+    let Some(bus) = Bus::start() else {
+        eprintln!("dbus-daemon no está instalado; se omite el test");
+        return;
+    };
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "prod/API_KEY", "abc123").unwrap();
+    secrets::add(&store, "secret-service/wifi", "w1f1").unwrap();
+    auto_sync_repo(dir);
+
+    let mut child = spawn_crypta(
+        dir,
+        &["secret-service"],
+        &[("DBUS_SESSION_BUS_ADDRESS", bus.address.as_ref())],
+    );
+    wait_for_line(&mut child, "Secret Service activo");
+    let daemon = Daemon(child);
+
+    let connection = bus.connect();
+    let service = proxy(
+        &connection,
+        "/org/freedesktop/secrets",
+        "org.freedesktop.Secret.Service",
+    );
+
+    // Solo se admiten sesiones sin cifrado
+    let error = service
+        .call::<_, _, (OwnedValue, OwnedObjectPath)>(
+            "OpenSession",
+            &(
+                "dh-ietf1024-sha256-aes128-cbc-pkcs7",
+                Value::from(vec![1u8]),
+            ),
+        )
+        .unwrap_err();
+    assert_eq!(error_name(error), "org.freedesktop.DBus.Error.NotSupported");
+    let (_, session): (OwnedValue, OwnedObjectPath) = service
+        .call("OpenSession", &("plain", Value::from("")))
+        .unwrap();
+
+    let collection: OwnedObjectPath = service.call("ReadAlias", &("default",)).unwrap();
+    assert_ne!(collection.as_str(), "/");
+    let collections: Vec<OwnedObjectPath> = service.get_property("Collections").unwrap();
+    assert_eq!(collections, vec![collection.clone()]);
+
+    // Crear un elemento como lo haría libsecret
+    let create = |password: &str, replace: bool| -> OwnedObjectPath {
+        let proxy = proxy(
+            &connection,
+            collection.as_str(),
+            "org.freedesktop.Secret.Collection",
+        );
+        let attributes = HashMap::from([("server", "example.com"), ("user", "bob")]);
+        let properties = HashMap::from([
+            (
+                "org.freedesktop.Secret.Item.Label",
+                Value::from("Web de ejemplo"),
+            ),
+            (
+                "org.freedesktop.Secret.Item.Attributes",
+                Value::from(attributes),
+            ),
+        ]);
+        let secret = (
+            session.as_ref(),
+            Vec::<u8>::new(),
+            password.as_bytes().to_vec(),
+            "text/plain",
+        );
+        let (item, prompt): (OwnedObjectPath, OwnedObjectPath) = proxy
+            .call("CreateItem", &(properties, secret, replace))
+            .unwrap();
+        assert_eq!(prompt.as_str(), "/");
+        item
+    };
+    let item = create("s3cret", true);
+    // Con `sync.auto` cada cambio se sincroniza, como en el CLI
+    assert_eq!(auto_sync_commits(dir), 1);
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "secret-service/Web de ejemplo").unwrap();
+    assert_eq!(entry.text().unwrap(), "s3cret");
+    assert_eq!(entry.meta.label.as_deref(), Some("Web de ejemplo"));
+    assert_eq!(
+        entry.meta.attributes.get("server").map(String::as_str),
+        Some("example.com")
+    );
+
+    // Con `replace` se actualiza el elemento con los mismos atributos
+    assert_eq!(create("n3w", true), item);
+    assert_eq!(
+        secrets::read(&store, "secret-service/Web de ejemplo").unwrap(),
+        "n3w"
+    );
+
+    // Búsqueda por atributos y lectura del secreto
+    let (unlocked, locked): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) = service
+        .call(
+            "SearchItems",
+            &(HashMap::from([("server", "example.com")]),),
+        )
+        .unwrap();
+    assert_eq!(unlocked, vec![item.clone()]);
+    assert!(locked.is_empty());
+
+    let item_proxy = proxy(&connection, item.as_str(), "org.freedesktop.Secret.Item");
+    let (_, _, value, content_type): Secret =
+        item_proxy.call("GetSecret", &(session.as_ref(),)).unwrap();
+    assert_eq!(value, b"n3w");
+    assert!(content_type.starts_with("text/plain"));
+    let label: String = item_proxy.get_property("Label").unwrap();
+    assert_eq!(label, "Web de ejemplo");
+
+    // Los secretos existentes del espacio de nombres se encuentran por su clave
+    let search_key = |key: &str| -> Vec<OwnedObjectPath> {
+        let (unlocked, locked): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) = service
+            .call("SearchItems", &(HashMap::from([("crypta:key", key)]),))
+            .unwrap();
+        assert!(locked.is_empty());
+        unlocked
+    };
+    let unlocked = search_key("secret-service/wifi");
+    assert_eq!(unlocked.len(), 1);
+    let secrets: HashMap<OwnedObjectPath, Secret> = service
+        .call("GetSecrets", &(unlocked.clone(), session.as_ref()))
+        .unwrap();
+    assert_eq!(secrets[&unlocked[0]].2, b"w1f1");
+
+    // El resto del vault no es visible ni con una ruta construida a mano
+    assert!(search_key("prod/API_KEY").is_empty());
+    let items: Vec<OwnedObjectPath> = proxy(
+        &connection,
+        collection.as_str(),
+        "org.freedesktop.Secret.Collection",
+    )
+    .get_property("Items")
+    .unwrap();
+    assert_eq!(items.len(), 2);
+    let forged = OwnedObjectPath::try_from(format!(
+        "{}/{}",
+        collection.as_str(),
+        encode_path_element("prod/API_KEY")
+    ))
+    .unwrap();
+    let error = service
+        .call::<_, _, HashMap<OwnedObjectPath, Secret>>(
+            "GetSecrets",
+            &(vec![forged], session.as_ref()),
+        )
+        .unwrap_err();
+    assert_eq!(
+        error_name(error),
+        "org.freedesktop.Secret.Error.NoSuchObject"
+    );
+
+    // No hay bloqueo: Lock no bloquea nada y los secretos siguen accesibles
+    let (locked, _): (Vec<OwnedObjectPath>, OwnedObjectPath) =
+        service.call("Lock", &(vec![collection.clone()],)).unwrap();
+    assert!(locked.is_empty());
+    let _: Secret = item_proxy.call("GetSecret", &(session.as_ref(),)).unwrap();
+
+    // Una sesión cerrada ya no sirve
+    let session_proxy = proxy(
+        &connection,
+        session.as_str(),
+        "org.freedesktop.Secret.Session",
+    );
+    let _: () = session_proxy.call("Close", &()).unwrap();
+    let error = item_proxy
+        .call::<_, _, Secret>(
+            "GetSecret",
+            &(ObjectPath::try_from(session.as_str()).unwrap(),),
+        )
+        .unwrap_err();
+    assert_eq!(error_name(error), "org.freedesktop.Secret.Error.NoSession");
+
+    // Eliminar el elemento lo borra del almacén
+    let commits = auto_sync_commits(dir);
+    let prompt: OwnedObjectPath = item_proxy.call("Delete", &()).unwrap();
+    assert_eq!(prompt.as_str(), "/");
+    assert!(secrets::read(&store, "secret-service/Web de ejemplo").is_err());
+    assert_eq!(auto_sync_commits(dir), commits + 1);
+    assert_eq!(secrets::read(&store, "prod/API_KEY").unwrap(), "abc123");
+
+    // Con --whole-vault se expone todo el vault
+    drop(daemon);
+    let mut child = spawn_crypta(
+        dir,
+        &["secret-service", "--whole-vault"],
+        &[("DBUS_SESSION_BUS_ADDRESS", bus.address.as_ref())],
+    );
+    wait_for_line(&mut child, "Secret Service activo");
+    let _daemon = Daemon(child);
+    let connection = bus.connect();
+    let service = proxy(
+        &connection,
+        "/org/freedesktop/secrets",
+        "org.freedesktop.Secret.Service",
+    );
+    let (unlocked, _): (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) = service
+        .call(
+            "SearchItems",
+            &(HashMap::from([("crypta:key", "prod/API_KEY")]),),
+        )
+        .unwrap();
+    assert_eq!(unlocked.len(), 1);
+}
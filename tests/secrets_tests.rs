@@ -34,8 +34,8 @@ fn test_yaml_parsing() {
     
     if let Value::Mapping(map) = yaml {
         assert_eq!(map.len(), 2);
-        assert!(map.contains_key(&Value::String("key1".to_string())));
-        assert!(map.contains_key(&Value::String("key2".to_string())));
+        assert!(map.contains_key(Value::String("key1".to_string())));
+        assert!(map.contains_key(Value::String("key2".to_string())));
     } else {
         panic!("Expected a mapping");
     }
@@ -71,7 +71,7 @@ fn test_yaml_removal() {
     if let Value::Mapping(ref mut map) = yaml {
         map.remove(Value::String("key1".to_string()));
         assert_eq!(map.len(), 1);
-        assert!(!map.contains_key(&Value::String("key1".to_string())));
+        assert!(!map.contains_key(Value::String("key1".to_string())));
     }
 }
 
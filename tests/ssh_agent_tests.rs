@@ -0,0 +1,167 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{age_store, fake_command, spawn_crypta, wait_for_line};
+use crypta::secrets;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Child, Command, Output, Stdio};
+use tempfile::TempDir;
+
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Genera una clave con ssh-keygen, la guarda en el almacén y borra la
+/// privada del disco; devuelve la ruta de la pública
+fn stored_key(dir: &Path, store: &crypta::store::Store, kind: &str, key: &str) -> String {
+    let path = dir.join(format!("id_{kind}"));
+    let status = Command::new("ssh-keygen")
+        .args(["-q", "-t", kind, "-N", "", "-C", key, "-f"])
+        .arg(&path)
+        .status()
+        .expect("ssh-keygen");
+    assert!(status.success());
+    secrets::add(store, key, &fs::read_to_string(&path).unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    format!("{}.pub", path.display())
+}
+
+fn ssh(program: &str, socket: &Path, args: &[&str], dir: &Path) -> Output {
+    Command::new(program)
+        .args(args)
+        .current_dir(dir)
+        .env("SSH_AUTH_SOCK", socket)
+        .stdin(Stdio::null())
+        .output()
+        .unwrap_or_else(|e| panic!("{program}: {e}"))
+}
+
+/// Firma `data` con `ssh-keygen -Y sign` a través del agente y comprueba
+/// la firma
+fn sign_and_verify(dir: &Path, socket: &Path, public: &str) -> bool {
+    let data = dir.join("data");
+    fs::write(&data, "hola").unwrap();
+    let _ = fs::remove_file(dir.join("data.sig"));
+    let output = ssh(
+        "ssh-keygen",
+        socket,
+        &["-Y", "sign", "-f", public, "-n", "file", "data"],
+        dir,
+    );
+    if !output.status.success() {
+        return false;
+    }
+    let verify = Command::new("ssh-keygen")
+        .args(["-Y", "check-novalidate", "-n", "file", "-s", "data.sig"])
+        .current_dir(dir)
+        .stdin(fs::File::open(&data).unwrap())
+        .output()
+        .unwrap();
+    assert!(verify.status.success(), "{:?}", verify);
+    true
+}
+
+#[test]
+fn test_ssh_agent_serves_keys_from_store() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    let ed25519 = stored_key(dir, &store, "ed25519", "ssh/id_ed25519");
+    let rsa = stored_key(dir, &store, "rsa", "ssh/id_rsa");
+    secrets::add(&store, "ssh/config", "Host *").unwrap();
+
+    // SSH_ASKPASS confirma solo si existe el archivo `allow`
+    let allow = dir.join("allow");
+    fake_command(
+        dir,
+        "askpass",
+        &format!(
+            "#!/bin/sh\n[ \"$SSH_ASKPASS_PROMPT\" = confirm ] && [ -e '{}' ]\n",
+            allow.display()
+        ),
+    );
+    let socket = dir.join("agent/agent.sock");
+    let askpass = dir.join("bin/askpass");
+    let mut child = spawn_crypta(
+        dir,
+        &[
+            "ssh-agent",
+            "--prefix",
+            "ssh/",
+            "--confirm",
+            "ssh/id_rsa",
+            "-a",
+            socket.to_str().unwrap(),
+        ],
+        &[("SSH_ASKPASS", askpass.as_os_str())],
+    );
+    let line = wait_for_line(&mut child, "SSH_AUTH_SOCK=");
+    let _daemon = Daemon(child);
+    assert!(line.contains(socket.to_str().unwrap()));
+    assert_eq!(
+        fs::metadata(&socket).unwrap().permissions().mode() & 0o777,
+        0o600
+    );
+
+    // Las dos claves, con la ruta del almacén como comentario
+    let output = ssh("ssh-add", &socket, &["-L"], dir);
+    assert!(output.status.success(), "{:?}", output);
+    let listed = String::from_utf8_lossy(&output.stdout);
+    for public in [&ed25519, &rsa] {
+        let public = fs::read_to_string(public).unwrap();
+        let blob = public.split_whitespace().nth(1).unwrap();
+        assert!(listed.contains(blob), "{listed}");
+    }
+    assert!(listed.contains("ssh/id_ed25519"));
+    assert!(!listed.contains("ssh/config"));
+
+    assert!(sign_and_verify(dir, &socket, &ed25519));
+
+    // La clave RSA pide confirmación en cada firma
+    assert!(!sign_and_verify(dir, &socket, &rsa));
+    fs::write(&allow, "").unwrap();
+    assert!(sign_and_verify(dir, &socket, &rsa));
+
+    // No se pueden añadir claves desde fuera del almacén
+    let extra = dir.join("id_extra");
+    Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+        .arg(&extra)
+        .status()
+        .unwrap();
+    let output = ssh("ssh-add", &socket, &[extra.to_str().unwrap()], dir);
+    assert!(!output.status.success());
+
+    // Eliminar una identidad y después todas
+    let output = ssh("ssh-add", &socket, &["-d", &ed25519], dir);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!sign_and_verify(dir, &socket, &ed25519));
+    let output = ssh("ssh-add", &socket, &["-D"], dir);
+    assert!(output.status.success(), "{:?}", output);
+    let output = ssh("ssh-add", &socket, &["-L"], dir);
+    assert!(!output.status.success());
+
+    // Las claves nunca se escriben en disco
+    assert!(!dir.join("id_ed25519").exists());
+    assert!(!dir.join("id_rsa").exists());
+}
+
+#[test]
+fn test_ssh_agent_refuses_secrets_that_are_not_keys() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "API_KEY", "abc123").unwrap();
+
+    let output = common::crypta(dir, &["ssh-agent", "--key", "API_KEY"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no es una clave privada OpenSSH"));
+}
@@ -0,0 +1,226 @@
+mod common;
+
+use chrono::TimeDelta;
+use common::memory_store;
+use crypta::entry::{Encoding, MetadataUpdate};
+use crypta::secrets::{self, Format};
+
+#[test]
+fn test_nested_paths() {
+    let store = memory_store();
+    secrets::add(&store, "prod/db/password", "s3cret").unwrap();
+    secrets::add(&store, "prod/db/user", "admin").unwrap();
+    secrets::add(&store, "dev/db/password", "dev").unwrap();
+
+    assert_eq!(secrets::read(&store, "prod/db/password").unwrap(), "s3cret");
+    assert_eq!(
+        secrets::keys(&store, "prod/").unwrap(),
+        vec!["prod/db/password", "prod/db/user"]
+    );
+    assert!(secrets::read(&store, "prod/db").is_err());
+
+    assert!(secrets::remove(&store, "prod", false).is_err());
+    secrets::remove(&store, "prod", true).unwrap();
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["dev/db/password"]);
+}
+
+#[test]
+fn test_flat_store_still_readable() {
+    let store = memory_store();
+    store
+        .save(&serde_yaml::from_str("PROD_DB_PASS: old\nlegacy/path: flat\nPORT: 5432\n").unwrap())
+        .unwrap();
+
+    assert_eq!(secrets::read(&store, "PROD_DB_PASS").unwrap(), "old");
+    assert_eq!(secrets::read(&store, "legacy/path").unwrap(), "flat");
+    assert_eq!(secrets::read(&store, "PORT").unwrap(), "5432");
+    assert_eq!(
+        secrets::keys(&store, "").unwrap(),
+        vec!["PROD_DB_PASS", "legacy/path", "PORT"]
+    );
+}
+
+#[test]
+fn test_add_records_timestamps_and_keeps_metadata() {
+    let store = memory_store();
+    secrets::add(&store, "prod/api", "v1").unwrap();
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "prod/api").unwrap();
+    let created = entry.meta.created.expect("created");
+    assert!(entry.meta.updated.is_some());
+
+    let update = MetadataUpdate {
+        note: Some("token de la API".to_string()),
+        owner: Some("plataforma".to_string()),
+        add_tags: vec!["api".to_string()],
+        ..Default::default()
+    };
+    secrets::meta(&store, "prod/api", &update).unwrap();
+    secrets::add(&store, "prod/api", "v2").unwrap();
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "prod/api").unwrap();
+    assert_eq!(entry.text().as_deref(), Some("v2"));
+    assert_eq!(entry.meta.created, Some(created));
+    assert_eq!(entry.meta.note.as_deref(), Some("token de la API"));
+    assert_eq!(entry.meta.owner.as_deref(), Some("plataforma"));
+    assert_eq!(entry.meta.tags, vec!["api"]);
+    assert_eq!(secrets::read(&store, "prod/api").unwrap(), "v2");
+    assert_eq!(secrets::keys(&store, "").unwrap(), vec!["prod/api"]);
+}
+
+#[test]
+fn test_meta_update_clears_fields_and_tags() {
+    let store = memory_store();
+    store
+        .save(&serde_yaml::from_str("TOKEN: plain\n").unwrap())
+        .unwrap();
+
+    let update = MetadataUpdate {
+        url: Some("https://example.com".to_string()),
+        add_tags: vec!["a".to_string(), "b".to_string()],
+        ..Default::default()
+    };
+    secrets::meta(&store, "TOKEN", &update).unwrap();
+
+    let update = MetadataUpdate {
+        url: Some(String::new()),
+        remove_tags: vec!["a".to_string()],
+        ..Default::default()
+    };
+    secrets::meta(&store, "TOKEN", &update).unwrap();
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "TOKEN").unwrap();
+    assert_eq!(entry.meta.url, None);
+    assert_eq!(entry.meta.tags, vec!["b"]);
+    assert_eq!(secrets::read(&store, "TOKEN").unwrap(), "plain");
+    assert!(secrets::meta(&store, "MISSING", &MetadataUpdate::default()).is_err());
+}
+
+#[test]
+fn test_due_secrets_by_expiry_and_rotation() {
+    let store = memory_store();
+    store
+        .save(
+            &serde_yaml::from_str(
+                "OLD:\n  _value: a\n  _meta:\n    updated: 2020-01-01T00:00:00Z\n    rotate_every: 90d\n\
+                 SOON:\n  _value: b\n  _meta:\n    expires: 2030-01-10T00:00:00Z\n\
+                 PLAIN: c\n",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+    let now = "2030-01-01T00:00:00Z".parse().unwrap();
+    let due = secrets::due(&store, now, TimeDelta::days(14)).unwrap();
+    let keys: Vec<_> = due.iter().map(|(k, _)| k.as_str()).collect();
+    assert_eq!(keys, vec!["OLD", "SOON"]);
+
+    let due = secrets::due(&store, now, TimeDelta::days(1)).unwrap();
+    assert_eq!(due.len(), 1);
+
+    // Rotar el secreto reinicia el periodo
+    secrets::add(&store, "OLD", "new").unwrap();
+    let doc = store.load().unwrap();
+    assert!(!secrets::find(&doc, "OLD")
+        .unwrap()
+        .meta
+        .is_expired(chrono::Utc::now()));
+}
+
+#[test]
+fn test_binary_secrets_keep_exact_bytes() {
+    let store = memory_store();
+    let blob: Vec<u8> = vec![0x30, 0x82, 0x00, 0xff, b'\n', 0x00];
+    secrets::add_bytes(&store, "certs/keystore.p12", &blob).unwrap();
+    secrets::add(&store, "certs/key.pem", "-----BEGIN KEY-----\nabc\n").unwrap();
+
+    assert_eq!(
+        secrets::read_bytes(&store, "certs/keystore.p12").unwrap(),
+        blob
+    );
+    assert!(secrets::read(&store, "certs/keystore.p12").is_err());
+    assert_eq!(
+        secrets::read(&store, "certs/key.pem").unwrap(),
+        "-----BEGIN KEY-----\nabc\n"
+    );
+
+    let doc = store.load().unwrap();
+    let entry = secrets::find(&doc, "certs/keystore.p12").unwrap();
+    assert_eq!(entry.meta.encoding, Some(Encoding::Base64));
+    assert_eq!(entry.value.as_str(), Some("MIIA/woA"));
+
+    // Sobrescribir con texto elimina la marca de binario
+    secrets::add(&store, "certs/keystore.p12", "texto").unwrap();
+    assert_eq!(
+        secrets::read(&store, "certs/keystore.p12").unwrap(),
+        "texto"
+    );
+}
+
+#[test]
+fn test_save_to_file_restricts_permissions() {
+    let store = memory_store();
+    let blob = vec![1, 2, 3, 0, 255];
+    secrets::add_bytes(&store, "blob", &blob).unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("blob.bin");
+    std::fs::write(&path, "old contents that are longer").unwrap();
+    secrets::save_to_file(&store, "blob", None, Format::Raw, &path).unwrap();
+
+    assert_eq!(std::fs::read(&path).unwrap(), blob);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn test_structured_values_and_fields() {
+    let store = memory_store();
+    secrets::add_json(
+        &store,
+        "DB_CONFIG",
+        r#"{"host": "localhost", "port": 5432, "replicas": [{"host": "r1"}]}"#,
+    )
+    .unwrap();
+    secrets::add(&store, "app.env", "prod").unwrap();
+
+    // Los objetos son secretos, no grupos
+    assert_eq!(
+        secrets::keys(&store, "").unwrap(),
+        vec!["DB_CONFIG", "app.env"]
+    );
+    assert_eq!(
+        secrets::read(&store, "DB_CONFIG.host").unwrap(),
+        "localhost"
+    );
+    assert_eq!(secrets::read(&store, "DB_CONFIG.port").unwrap(), "5432");
+    assert_eq!(
+        secrets::read(&store, "DB_CONFIG.replicas.0.host").unwrap(),
+        "r1"
+    );
+    assert_eq!(secrets::read(&store, "app.env").unwrap(), "prod");
+
+    let value = secrets::read_value(&store, "DB_CONFIG", Some("replicas")).unwrap();
+    assert_eq!(
+        secrets::format_value(&value, Format::Yaml).unwrap(),
+        "- host: r1"
+    );
+    assert_eq!(
+        secrets::format_value(&value, Format::Json).unwrap(),
+        "[\n  {\n    \"host\": \"r1\"\n  }\n]"
+    );
+
+    let err = secrets::read(&store, "DB_CONFIG.password").unwrap_err();
+    assert!(matches!(
+        crypta::error::find(&err),
+        Some(crypta::Error::NotFound(key)) if key == "DB_CONFIG.password"
+    ));
+    assert!(secrets::add_json(&store, "BAD", "{no es json").is_err());
+}
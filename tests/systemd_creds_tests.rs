@@ -0,0 +1,192 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{age_store, crypta, crypta_with_env, fake_command};
+use crypta::secrets;
+use std::ffi::OsStr;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[test]
+fn test_export_load_credential_and_exec() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "myapp/db/password", "s3cret").unwrap();
+    secrets::add(&store, "myapp/API_KEY", "abc123").unwrap();
+    secrets::add(&store, "other/TOKEN", "t0ken").unwrap();
+    let creds_dir = dir.join("credstore/myapp.service");
+
+    let output = crypta(
+        dir,
+        &[
+            "systemd-creds",
+            "export",
+            "myapp",
+            "--prefix",
+            "myapp/",
+            "-o",
+            creds_dir.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("# /etc/systemd/system/myapp.service.d/crypta.conf"));
+    assert!(stdout.contains("[Service]"));
+    assert!(stdout.contains(&format!(
+        "LoadCredential=db_password:{}",
+        creds_dir.join("db_password").display()
+    )));
+    assert!(stdout.contains("LoadCredential=API_KEY:"));
+    assert!(!stdout.contains("TOKEN"));
+
+    let file = creds_dir.join("db_password");
+    assert_eq!(fs::read_to_string(&file).unwrap(), "s3cret");
+    let mode = fs::metadata(&file).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o400);
+
+    // Dentro del servicio: las credenciales llegan por $CREDENTIALS_DIRECTORY
+    let output = crypta_with_env(
+        dir,
+        &[
+            "systemd-creds",
+            "exec",
+            "-e",
+            "DB=db_password",
+            "--",
+            "sh",
+            "-c",
+            "printf '%s %s' \"$DB\" \"$API_KEY\"",
+        ],
+        &[("CREDENTIALS_DIRECTORY", creds_dir.as_os_str())],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "s3cret ");
+
+    let output = crypta(
+        dir,
+        &[
+            "systemd-creds",
+            "exec",
+            "--all",
+            "--credentials-dir",
+            creds_dir.to_str().unwrap(),
+            "--",
+            "sh",
+            "-c",
+            "printf '%s %s' \"$DB_PASSWORD\" \"$API_KEY\"",
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "s3cret abc123");
+
+    // Una credencial inexistente es un error
+    let output = crypta(
+        dir,
+        &[
+            "systemd-creds",
+            "exec",
+            "-e",
+            "NADA",
+            "--credentials-dir",
+            creds_dir.to_str().unwrap(),
+            "--",
+            "true",
+        ],
+    );
+    assert!(!output.status.success());
+
+    let output = crypta(dir, &["cleanup"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!creds_dir.exists());
+}
+
+#[test]
+fn test_export_encrypted_with_systemd_creds() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "myapp/db/password", "s3cret").unwrap();
+    secrets::add(&store, "myapp/API_KEY", "abc123").unwrap();
+
+    // `systemd-creds` falso: registra los argumentos y codifica en base64
+    let log = dir.join("systemd-creds.log");
+    let path = fake_command(
+        dir,
+        "systemd-creds",
+        &format!(
+            "#!/bin/sh\necho \"$*\" >> '{}'\ncase \"$*\" in\n  *--pretty*) printf 'SetCredentialEncrypted=%s: \\\\\\n        ' \"${{2#--name=}}\"; base64 ;;\n  *) base64 ;;\nesac\n",
+            log.display()
+        ),
+    );
+    let env = [("PATH", path.as_os_str())];
+
+    // Sin -o, los blobs van en el drop-in
+    let output = crypta_with_env(
+        dir,
+        &[
+            "systemd-creds",
+            "export",
+            "myapp.service",
+            "--keys",
+            "myapp/db/*",
+            "--encrypt",
+            "--with-key",
+            "host",
+        ],
+        &env,
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("[Service]"));
+    assert!(stdout.contains("SetCredentialEncrypted=password: \\\n        czNjcmV0"));
+    let args = fs::read_to_string(&log).unwrap();
+    assert_eq!(
+        args.trim(),
+        "encrypt --name=password --with-key=host --pretty - -"
+    );
+
+    // Con -o, un archivo NAME.cred por credencial
+    let creds_dir = dir.join("credstore.encrypted");
+    let output = crypta_with_env(
+        dir,
+        &[
+            "systemd-creds",
+            "export",
+            "myapp",
+            "--prefix",
+            "myapp",
+            "--encrypt",
+            "-o",
+            creds_dir.to_str().unwrap(),
+        ],
+        &env,
+    );
+    assert!(output.status.success(), "{:?}", output);
+    let file = creds_dir.canonicalize().unwrap().join("db_password.cred");
+    assert_eq!(fs::read_to_string(&file).unwrap(), "czNjcmV0\n");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!(
+        "LoadCredentialEncrypted=db_password:{}",
+        file.display()
+    )));
+    assert!(stdout.contains("LoadCredentialEncrypted=API_KEY:"));
+
+    // Sin systemd-creds, el error lo dice
+    let output = crypta_with_env(
+        dir,
+        &[
+            "systemd-creds",
+            "export",
+            "myapp",
+            "--prefix",
+            "myapp",
+            "--encrypt",
+        ],
+        &[("PATH", OsStr::new("/nonexistent"))],
+    );
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("systemd-creds"));
+}
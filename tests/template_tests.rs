@@ -0,0 +1,62 @@
+mod common;
+
+use common::{age_store, crypta};
+use crypta::secrets;
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_render_check() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "DB_PASSWORD", "s3cret").unwrap();
+    secrets::add(&store, "prod/api", "k3y").unwrap();
+
+    let template = dir.join("app.conf.tmpl");
+    fs::write(&template, "password={{ secret \"DB_PASSWORD\" }}\n").unwrap();
+    let output = crypta(dir, &["render", template.to_str().unwrap(), "--check"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("(1 claves)"));
+
+    // Solo las claves que no existen se dan por ausentes
+    fs::write(&template, "{{ secret \"A\" }} {{ secret \"B\" }}\n").unwrap();
+    let output = crypta(dir, &["render", template.to_str().unwrap(), "--check"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("no existen: A, B"));
+
+    fs::write(&template, "{{ secret \"prod\" }}\n").unwrap();
+    let output = crypta(dir, &["render", template.to_str().unwrap(), "--check"]);
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("es un grupo de secretos"), "{}", stderr);
+    assert!(!stderr.contains("no existen"), "{}", stderr);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_render_output_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "DB_PASSWORD", "s3cret").unwrap();
+
+    let template = dir.join("app.conf.tmpl");
+    fs::write(&template, "password={{ secret \"DB_PASSWORD\" }}\n").unwrap();
+    let rendered = dir.join("app.conf");
+    let output = crypta(
+        dir,
+        &[
+            "render",
+            template.to_str().unwrap(),
+            "-o",
+            rendered.to_str().unwrap(),
+        ],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(fs::read_to_string(&rendered).unwrap(), "password=s3cret\n");
+    let mode = fs::metadata(&rendered).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+}
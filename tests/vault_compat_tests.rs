@@ -0,0 +1,183 @@
+mod common;
+
+use common::{age_store, auto_sync_commits, auto_sync_repo, spawn_crypta, wait_for_line};
+use crypta::secrets;
+use serde_json::Value;
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::process::Child;
+use tempfile::TempDir;
+
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Petición HTTP/1.1 mínima; devuelve el estado y el cuerpo JSON (si hay)
+fn http(
+    address: &str,
+    method: &str,
+    path: &str,
+    token: Option<&str>,
+    body: &str,
+) -> (u16, Option<Value>) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    let token = token
+        .map(|t| format!("X-Vault-Token: {}\r\n", t))
+        .unwrap_or_default();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\n{token}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+    let body = (!body.is_empty()).then(|| serde_json::from_str(body).unwrap());
+    (status, body)
+}
+
+#[test]
+fn test_vault_kv_v2_api() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "app/API_KEY", "abc123").unwrap();
+    secrets::add_json(&store, "app/db", r#"{"user": "bob", "password": "s3cret"}"#).unwrap();
+    auto_sync_repo(dir);
+
+    let token_file = dir.join("run/vault-token");
+    let mut child = spawn_crypta(
+        dir,
+        &[
+            "serve",
+            "--vault-compat",
+            "--listen",
+            "127.0.0.1:0",
+            "--token-file",
+            token_file.to_str().unwrap(),
+        ],
+        &[],
+    );
+    let line = wait_for_line(&mut child, "VAULT_ADDR=");
+    let _daemon = Daemon(child);
+    let address = line
+        .trim_start_matches("VAULT_ADDR=http://")
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+
+    // El token se genera con permisos 0600
+    let token = fs::read_to_string(&token_file).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(&token_file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+    let token = Some(token.trim());
+
+    let (status, _) = http(&address, "GET", "/v1/sys/health", None, "");
+    assert_eq!(status, 200);
+    let (status, _) = http(&address, "GET", "/v1/secret/data/app/API_KEY", None, "");
+    assert_eq!(status, 403);
+
+    // Lectura de secretos simples y estructurados
+    let (status, body) = http(&address, "GET", "/v1/secret/data/app/API_KEY", token, "");
+    assert_eq!(status, 200);
+    assert_eq!(body.unwrap()["data"]["data"]["value"], "abc123");
+    let (_, body) = http(&address, "GET", "/v1/secret/data/app/db", token, "");
+    let data = &body.unwrap()["data"]["data"];
+    assert_eq!(data["user"], "bob");
+    assert_eq!(data["password"], "s3cret");
+
+    // Escritura: se guarda encriptada en el almacén
+    let (status, body) = http(
+        &address,
+        "POST",
+        "/v1/secret/data/app/redis",
+        token,
+        r#"{"data": {"url": "redis://localhost"}}"#,
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body.unwrap()["data"]["version"], 1);
+    let doc = store.load().unwrap();
+    let redis = secrets::find(&doc, "app/redis").unwrap();
+    assert_eq!(redis.value["url"].as_str(), Some("redis://localhost"));
+    // Con `sync.auto` cada escritura se sincroniza, como en el CLI
+    assert_eq!(auto_sync_commits(dir), 1);
+
+    // Los cambios hechos con el CLI se ven sin reiniciar
+    secrets::add(&store, "app/API_KEY", "n3w").unwrap();
+    let (_, body) = http(&address, "GET", "/v1/secret/data/app/API_KEY", token, "");
+    assert_eq!(body.unwrap()["data"]["data"]["value"], "n3w");
+
+    // Listado al estilo `vault kv list`
+    let (status, body) = http(&address, "LIST", "/v1/secret/metadata/app", token, "");
+    assert_eq!(status, 200);
+    assert_eq!(
+        body.unwrap()["data"]["keys"],
+        serde_json::json!(["API_KEY", "db", "redis"])
+    );
+    let (_, body) = http(&address, "GET", "/v1/secret/metadata/?list=true", token, "");
+    assert_eq!(body.unwrap()["data"]["keys"], serde_json::json!(["app/"]));
+
+    // Borrado y secretos inexistentes
+    let (status, body) = http(&address, "DELETE", "/v1/secret/data/app/redis", token, "");
+    assert_eq!(status, 204);
+    assert!(body.is_none());
+    assert_eq!(auto_sync_commits(dir), 2);
+    let (status, body) = http(&address, "GET", "/v1/secret/data/app/redis", token, "");
+    assert_eq!(status, 404);
+    assert_eq!(body.unwrap()["errors"], serde_json::json!([]));
+}
+
+#[test]
+fn test_serve_reuses_existing_token_file() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+    let store = age_store(dir);
+    secrets::add(&store, "API_KEY", "abc123").unwrap();
+    let token_file = dir.join("token");
+    fs::write(&token_file, "dev-token\n").unwrap();
+
+    let mut child = spawn_crypta(
+        dir,
+        &[
+            "serve",
+            "--vault-compat",
+            "--listen",
+            "127.0.0.1:0",
+            "--token-file",
+            token_file.to_str().unwrap(),
+        ],
+        &[],
+    );
+    let line = wait_for_line(&mut child, "VAULT_ADDR=");
+    let _daemon = Daemon(child);
+    let address = line
+        .trim_start_matches("VAULT_ADDR=http://")
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+
+    let (status, body) = http(
+        &address,
+        "GET",
+        "/v1/secret/data/API_KEY",
+        Some("dev-token"),
+        "",
+    );
+    assert_eq!(status, 200);
+    assert_eq!(body.unwrap()["data"]["data"]["value"], "abc123");
+}
@@ -0,0 +1,107 @@
+mod common;
+
+use common::crypta;
+use crypta::vault::{self, Vault};
+use std::fs;
+use tempfile::TempDir;
+
+const RECIPIENT: &str = "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p";
+
+#[test]
+fn test_default_vault_is_root() {
+    let temp_dir = TempDir::new().unwrap();
+    let vault = Vault::resolve(temp_dir.path(), None).unwrap();
+
+    assert!(vault.is_default());
+    assert_eq!(vault.dir(), temp_dir.path());
+    assert_eq!(vault.file(), temp_dir.path().join("secrets.yml"));
+    assert_eq!(vault.git_dir(), temp_dir.path());
+}
+
+#[test]
+fn test_missing_named_vault_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    assert!(Vault::resolve(temp_dir.path(), Some("work")).is_err());
+    assert!(Vault::named(temp_dir.path(), "../escape").is_err());
+}
+
+#[test]
+fn test_storeless_commands_ignore_missing_vault() {
+    let temp_dir = TempDir::new().unwrap();
+    let dir = temp_dir.path();
+
+    let output = crypta(dir, &["--vault", "nope", "password", "--length", "12"]);
+    assert!(output.status.success(), "{:?}", output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim().len(), 12);
+
+    let output = crypta(dir, &["--vault", "nope", "cleanup"]);
+    assert!(output.status.success(), "{:?}", output);
+
+    assert!(!crypta(dir, &["--vault", "nope", "list"]).status.success());
+}
+
+#[test]
+fn test_create_list_and_remove_vault() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    vault::create(root, "work", &[RECIPIENT.to_string()], None).unwrap();
+    assert!(vault::create(root, "work", &[RECIPIENT.to_string()], None).is_err());
+
+    let work = Vault::resolve(root, Some("work")).unwrap();
+    assert_eq!(work.dir(), root.join("vaults/work"));
+    let sops_config = fs::read_to_string(work.dir().join(".sops.yaml")).unwrap();
+    assert!(sops_config.contains(RECIPIENT));
+    // Sin repositorio propio se sincroniza con el directorio raíz
+    assert_eq!(work.git_dir(), root);
+
+    assert_eq!(vault::names(root).unwrap(), vec!["default", "work"]);
+
+    // Con secretos hace falta --force
+    fs::write(work.file(), "encrypted").unwrap();
+    assert!(vault::remove(root, "work", false).is_err());
+    vault::remove(root, "work", true).unwrap();
+    assert_eq!(vault::names(root).unwrap(), vec!["default"]);
+}
+
+#[test]
+fn test_create_vault_with_own_repository() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+
+    vault::create(
+        root,
+        "team",
+        &[RECIPIENT.to_string()],
+        Some("git@example.com:team/secrets.git"),
+    )
+    .unwrap();
+
+    let team = Vault::resolve(root, Some("team")).unwrap();
+    assert_eq!(team.git_dir(), team.dir());
+    let repo = git2::Repository::open(team.dir()).unwrap();
+    assert_eq!(
+        repo.find_remote("origin").unwrap().url(),
+        Some("git@example.com:team/secrets.git")
+    );
+    let gitignore = fs::read_to_string(root.join(".gitignore")).unwrap();
+    assert!(gitignore.contains("/vaults/team/"));
+}
+
+#[test]
+fn test_create_vault_inherits_default_recipients() {
+    let temp_dir = TempDir::new().unwrap();
+    let root = temp_dir.path();
+    fs::write(
+        root.join(".sops.yaml"),
+        format!(
+            "creation_rules:\n  - path_regex: \\.yml$\n    age: {}\n",
+            RECIPIENT
+        ),
+    )
+    .unwrap();
+
+    vault::create(root, "personal", &[], None).unwrap();
+    let sops_config = fs::read_to_string(root.join("vaults/personal/.sops.yaml")).unwrap();
+    assert!(sops_config.contains(RECIPIENT));
+}